@@ -1,6 +1,18 @@
+pub mod animation;
 pub mod answer;
+pub mod assignment;
 pub mod constants;
+pub mod expr;
+pub mod geometry;
+pub mod gif;
+pub mod graph;
+pub mod hex;
+pub mod interval;
+pub mod json;
 pub mod math;
+pub mod ocr;
 pub mod output;
 pub mod parse;
 pub mod result_logger;
+pub mod segment;
+pub mod vm;
@@ -0,0 +1,113 @@
+use std::fmt;
+use std::fmt::Display;
+
+/// A single frame of a simulation, stored as a grid of characters with (0, 0) at the top-left
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Frame {
+    width: usize,
+    height: usize,
+    cells: Vec<char>,
+}
+
+impl Frame {
+    pub fn new(width: usize, height: usize) -> Frame {
+        Frame::filled(width, height, ' ')
+    }
+
+    pub fn filled(width: usize, height: usize, c: char) -> Frame {
+        let cells = vec![c; width * height];
+
+        Frame {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Builds the smallest frame that covers every given cell; anything unset is blank
+    pub fn from_cells<I>(cells: I) -> Frame
+    where
+        I: IntoIterator<Item = ((isize, isize), char)>,
+    {
+        let cells: Vec<((isize, isize), char)> = cells.into_iter().collect();
+
+        if cells.is_empty() {
+            return Frame::new(0, 0);
+        }
+
+        let min_x = cells.iter().map(|((x, _), _)| *x).min().unwrap();
+        let max_x = cells.iter().map(|((x, _), _)| *x).max().unwrap();
+        let min_y = cells.iter().map(|((_, y), _)| *y).min().unwrap();
+        let max_y = cells.iter().map(|((_, y), _)| *y).max().unwrap();
+
+        let mut frame = Frame::new((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize);
+
+        for ((x, y), c) in cells {
+            frame.set((x - min_x) as usize, (y - min_y) as usize, c);
+        }
+
+        frame
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        if x < self.width && y < self.height {
+            Some(self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Sets a cell; anything outside of the frame is silently clipped
+    pub fn set(&mut self, x: usize, y: usize, c: char) {
+        if x < self.width && y < self.height {
+            self.cells[y * self.width + x] = c;
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[char]> {
+        self.cells.chunks(self.width.max(1)).take(self.height)
+    }
+}
+
+impl Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<String> = self.rows().map(|row| row.iter().collect()).collect();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_get() {
+        let mut frame = Frame::filled(3, 2, '.');
+
+        frame.set(2, 1, '#');
+        frame.set(5, 5, '#');
+
+        assert_eq!(frame.get(2, 1), Some('#'));
+        assert_eq!(frame.get(0, 0), Some('.'));
+        assert_eq!(frame.get(3, 0), None);
+        assert_eq!(frame.to_string(), "...\n..#");
+    }
+
+    #[test]
+    fn test_from_cells() {
+        let frame = Frame::from_cells(vec![((-1, 5), 'a'), ((1, 6), 'b')]);
+
+        assert_eq!(frame.width(), 3);
+        assert_eq!(frame.height(), 2);
+        assert_eq!(frame.to_string(), "a  \n  b");
+    }
+}
@@ -47,6 +47,12 @@ pub enum AdventOfCodeError {
     CannotParseInteger(ParseIntError),
     NomParseError,
     CannotGetChar,
+    NoUniqueAssignment(String),
+    CannotEvaluate(String),
+    Unsupported(String),
+    Overflow(String),
+    NoSolution(String),
+    InvalidInput(String),
 }
 
 impl From<io::Error> for AdventOfCodeError {
@@ -68,6 +74,12 @@ impl fmt::Display for AdventOfCodeError {
             AdventOfCodeError::CannotParseInteger(p) => write!(f, "{}", p),
             AdventOfCodeError::NomParseError => write!(f, "nom parse error"),
             AdventOfCodeError::CannotGetChar => write!(f, "cannot get char"),
+            AdventOfCodeError::NoUniqueAssignment(s) => write!(f, "no unique assignment: {}", s),
+            AdventOfCodeError::CannotEvaluate(s) => write!(f, "cannot evaluate: {}", s),
+            AdventOfCodeError::Unsupported(s) => write!(f, "unsupported: {}", s),
+            AdventOfCodeError::Overflow(s) => write!(f, "overflow: {}", s),
+            AdventOfCodeError::NoSolution(s) => write!(f, "no solution: {}", s),
+            AdventOfCodeError::InvalidInput(s) => write!(f, "invalid input: {}", s),
             AdventOfCodeError::Unimplemented => write!(f, "Unimplemented"),
         }
     }
@@ -0,0 +1,248 @@
+use crate::common::answer::AdventOfCodeError;
+use log::debug;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Debug, Display};
+use std::iter::FromIterator;
+
+/**
+ * The values each slot could still take, to be narrowed down until every slot holds a different
+ * value
+ *
+ * Values that no slot can take are fine, so there can be more values than slots.
+ */
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Candidates<K, V> {
+    values_by_slot: BTreeMap<K, BTreeSet<V>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AssignmentError<K, V> {
+    /// Every value this slot could take is needed by another slot
+    NoCandidates(K),
+    /// Each slot can be filled on its own, but not all of them at once
+    Unsatisfiable,
+    /// There are at least two answers, which disagree about this slot
+    Ambiguous { slot: K, first: V, second: V },
+}
+
+impl<K, V> Candidates<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Ord + Clone + Debug,
+{
+    pub fn new() -> Candidates<K, V> {
+        Candidates {
+            values_by_slot: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a slot that has to be filled, even if nothing has been found to fill it yet
+    pub fn add_slot(&mut self, slot: K) {
+        self.values_by_slot.entry(slot).or_default();
+    }
+
+    pub fn add(&mut self, slot: K, value: V) {
+        self.values_by_slot.entry(slot).or_default().insert(value);
+    }
+
+    /**
+     * Finds the only way of giving every slot a different value
+     *
+     * Slots down to one value give that value up for every other slot until nothing changes.
+     * Usually that settles everything, but if not each remaining choice is tried in turn, looking
+     * for a second answer to prove the first one isn't the only one.
+     */
+    pub fn solve(&self) -> Result<BTreeMap<K, V>, AssignmentError<K, V>> {
+        let mut values_by_slot = self.values_by_slot.clone();
+
+        propagate(&mut values_by_slot)?;
+
+        let mut answers = Vec::new();
+        search(values_by_slot, &mut answers);
+
+        match answers.len() {
+            0 => Err(AssignmentError::Unsatisfiable),
+            1 => Ok(answers.pop().unwrap()),
+            _ => {
+                let (slot, first) = answers[0]
+                    .iter()
+                    .find(|(slot, value)| answers[1][slot] != **value)
+                    .unwrap();
+
+                Err(AssignmentError::Ambiguous {
+                    slot: slot.clone(),
+                    first: first.clone(),
+                    second: answers[1][slot].clone(),
+                })
+            }
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Candidates<K, V>
+where
+    K: Ord + Clone + Debug,
+    V: Ord + Clone + Debug,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut candidates = Candidates::new();
+
+        for (slot, value) in iter {
+            candidates.add(slot, value);
+        }
+
+        candidates
+    }
+}
+
+/**
+ * Removes the value of every slot that only has one left from all the other slots, failing as soon
+ * as a slot runs out of values
+ */
+fn propagate<K, V>(
+    values_by_slot: &mut BTreeMap<K, BTreeSet<V>>,
+) -> Result<(), AssignmentError<K, V>>
+where
+    K: Ord + Clone,
+    V: Ord + Clone,
+{
+    let mut settled = BTreeSet::new();
+
+    loop {
+        if let Some((slot, _)) = values_by_slot.iter().find(|(_, values)| values.is_empty()) {
+            return Err(AssignmentError::NoCandidates(slot.clone()));
+        }
+
+        let next = values_by_slot
+            .iter()
+            .find(|(slot, values)| values.len() == 1 && !settled.contains(*slot));
+
+        let (slot, value) = match next {
+            Some((slot, values)) => (slot.clone(), values.iter().next().unwrap().clone()),
+            None => return Ok(()),
+        };
+
+        for (other_slot, values) in values_by_slot.iter_mut() {
+            if *other_slot != slot {
+                values.remove(&value);
+            }
+        }
+
+        settled.insert(slot);
+    }
+}
+
+/// Collects up to two answers, choosing a value for the slot with the fewest left at each step
+fn search<K, V>(mut values_by_slot: BTreeMap<K, BTreeSet<V>>, answers: &mut Vec<BTreeMap<K, V>>)
+where
+    K: Ord + Clone + Debug,
+    V: Ord + Clone + Debug,
+{
+    if answers.len() >= 2 || propagate(&mut values_by_slot).is_err() {
+        return;
+    }
+
+    let undecided = values_by_slot
+        .iter()
+        .filter(|(_, values)| values.len() > 1)
+        .min_by_key(|(_, values)| values.len());
+
+    match undecided {
+        None => answers.push(
+            values_by_slot
+                .into_iter()
+                .map(|(slot, values)| (slot, values.into_iter().next().unwrap()))
+                .collect(),
+        ),
+        Some((slot, values)) => {
+            debug!(
+                "propagation stalled, trying each of {:?} for {:?}",
+                values, slot
+            );
+
+            for value in values.clone() {
+                let mut branch = values_by_slot.clone();
+                branch.insert(slot.clone(), vec![value].into_iter().collect());
+
+                search(branch, answers);
+            }
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> Display for AssignmentError<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssignmentError::NoCandidates(slot) => write!(f, "nothing left for {:?}", slot),
+            AssignmentError::Unsatisfiable => write!(f, "no way to fill every slot"),
+            AssignmentError::Ambiguous {
+                slot,
+                first,
+                second,
+            } => write!(f, "{:?} could be {:?} or {:?}", slot, first, second),
+        }
+    }
+}
+
+impl<K: Debug, V: Debug> From<AssignmentError<K, V>> for AdventOfCodeError {
+    fn from(err: AssignmentError<K, V>) -> AdventOfCodeError {
+        AdventOfCodeError::NoUniqueAssignment(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve_by_propagation() {
+        let candidates: Candidates<&str, u8> =
+            vec![("a", 1), ("a", 2), ("a", 3), ("b", 2), ("c", 2), ("c", 3)]
+                .into_iter()
+                .collect();
+
+        let expected = vec![("a", 1), ("b", 2), ("c", 3)].into_iter().collect();
+
+        assert_eq!(candidates.solve(), Ok(expected));
+    }
+
+    #[test]
+    fn test_no_candidates() {
+        let mut candidates: Candidates<&str, u8> =
+            vec![("a", 1), ("b", 1), ("b", 2)].into_iter().collect();
+        candidates.add_slot("c");
+
+        assert_eq!(candidates.solve(), Err(AssignmentError::NoCandidates("c")));
+
+        let candidates: Candidates<&str, u8> = vec![("a", 1), ("b", 1)].into_iter().collect();
+
+        assert_eq!(candidates.solve(), Err(AssignmentError::NoCandidates("b")));
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let candidates: Candidates<&str, u8> = vec!["a", "b", "c"]
+            .into_iter()
+            .flat_map(|slot| vec![(slot, 1), (slot, 2)])
+            .collect();
+
+        assert_eq!(candidates.solve(), Err(AssignmentError::Unsatisfiable));
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let candidates: Candidates<&str, u8> =
+            vec![("a", 1), ("a", 2), ("b", 1), ("b", 2), ("c", 3)]
+                .into_iter()
+                .collect();
+
+        assert_eq!(
+            candidates.solve(),
+            Err(AssignmentError::Ambiguous {
+                slot: "a",
+                first: 1,
+                second: 2
+            })
+        );
+    }
+}
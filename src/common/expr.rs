@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::iter::FromIterator;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, take_while1},
+    character::complete::space0,
+    combinator::{map, value, verify},
+    sequence::{delimited, preceded},
+    IResult,
+};
+
+use crate::common::answer::AdventOfCodeError;
+use crate::common::parse::unsigned_number;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Expr {
+    Number(i128),
+    Variable(String),
+    Binary {
+        lhs: Box<Expr>,
+        operator: BinaryOperator,
+        rhs: Box<Expr>,
+    },
+}
+
+impl Expr {
+    pub fn binary(lhs: Expr, operator: BinaryOperator, rhs: Expr) -> Expr {
+        Expr::Binary {
+            lhs: Box::new(lhs),
+            operator,
+            rhs: Box::new(rhs),
+        }
+    }
+
+    pub fn variable<S: Into<String>>(name: S) -> Expr {
+        Expr::Variable(name.into())
+    }
+}
+
+impl Display for Expr {
+    /// Binary expressions are always parenthesized, so the output doesn't depend on precedence
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(number) => write!(f, "{number}"),
+            Expr::Variable(name) => write!(f, "{name}"),
+            Expr::Binary { lhs, operator, rhs } => write!(f, "({lhs} {operator} {rhs})"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl BinaryOperator {
+    pub fn apply(&self, lhs: i128, rhs: i128) -> Result<i128, EvaluationError> {
+        match self {
+            BinaryOperator::Add => lhs.checked_add(rhs).ok_or(EvaluationError::Overflow),
+            BinaryOperator::Subtract => lhs.checked_sub(rhs).ok_or(EvaluationError::Overflow),
+            BinaryOperator::Multiply => lhs.checked_mul(rhs).ok_or(EvaluationError::Overflow),
+            BinaryOperator::Divide if rhs == 0 => Err(EvaluationError::DivisionByZero),
+            BinaryOperator::Divide => lhs.checked_div(rhs).ok_or(EvaluationError::Overflow),
+        }
+    }
+}
+
+impl Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryOperator::Add => write!(f, "+"),
+            BinaryOperator::Subtract => write!(f, "-"),
+            BinaryOperator::Multiply => write!(f, "*"),
+            BinaryOperator::Divide => write!(f, "/"),
+        }
+    }
+}
+
+/**
+ * Decides which operators a parser accepts and how tightly each of them binds
+ *
+ * Operators with a higher precedence bind more tightly, and operators with the same precedence are
+ * applied left to right. Operators that aren't in the table end the expression, the same as any
+ * other unexpected input.
+ */
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct OperatorTable {
+    precedences: HashMap<BinaryOperator, u8>,
+}
+
+impl OperatorTable {
+    pub fn new() -> OperatorTable {
+        OperatorTable::default()
+    }
+
+    /// The usual rules: `*` and `/` bind more tightly than `+` and `-`
+    pub fn arithmetic() -> OperatorTable {
+        OperatorTable::new()
+            .with(BinaryOperator::Add, 1)
+            .with(BinaryOperator::Subtract, 1)
+            .with(BinaryOperator::Multiply, 2)
+            .with(BinaryOperator::Divide, 2)
+    }
+
+    pub fn with(mut self, operator: BinaryOperator, precedence: u8) -> OperatorTable {
+        self.precedences.insert(operator, precedence);
+
+        self
+    }
+
+    /// Parses a single expression, leaving anything after it (including a trailing newline)
+    pub fn parse_expression<'a>(&self, i: &'a str) -> IResult<&'a str, Expr> {
+        self.expression_with_minimum_precedence(i, 0)
+    }
+
+    /**
+     * Pratt parsing: parse an operand, then keep folding in operators for as long as they bind at
+     * least as tightly as `minimum_precedence`. The right hand side of each operator is parsed
+     * with a higher minimum, so `a - b - c` groups as `(a - b) - c`
+     */
+    fn expression_with_minimum_precedence<'a>(
+        &self,
+        i: &'a str,
+        minimum_precedence: u16,
+    ) -> IResult<&'a str, Expr> {
+        let (mut i, mut lhs) = self.operand(i)?;
+
+        loop {
+            let (rest, operator) = match preceded(space0, binary_operator)(i) {
+                Ok(result) => result,
+                Err(nom::Err::Error(_)) => break,
+                Err(e) => return Err(e),
+            };
+
+            let precedence = match self.precedences.get(&operator) {
+                Some(precedence) if u16::from(*precedence) >= minimum_precedence => *precedence,
+                _ => break,
+            };
+
+            // wider than the precedences themselves, so that one past the highest still fits
+            let next_minimum_precedence = u16::from(precedence) + 1;
+
+            let (rest, rhs) =
+                self.expression_with_minimum_precedence(rest, next_minimum_precedence)?;
+
+            lhs = Expr::binary(lhs, operator, rhs);
+            i = rest;
+        }
+
+        Ok((i, lhs))
+    }
+
+    fn operand<'a>(&self, i: &'a str) -> IResult<&'a str, Expr> {
+        preceded(
+            space0,
+            alt((
+                |i| self.parenthesized(i),
+                map(unsigned_number, Expr::Number),
+                map(identifier, Expr::variable),
+            )),
+        )(i)
+    }
+
+    fn parenthesized<'a>(&self, i: &'a str) -> IResult<&'a str, Expr> {
+        delimited(
+            tag("("),
+            |i| self.parse_expression(i),
+            preceded(space0, tag(")")),
+        )(i)
+    }
+}
+
+fn binary_operator(i: &str) -> IResult<&str, BinaryOperator> {
+    alt((
+        value(BinaryOperator::Add, tag("+")),
+        value(BinaryOperator::Subtract, tag("-")),
+        value(BinaryOperator::Multiply, tag("*")),
+        value(BinaryOperator::Divide, tag("/")),
+    ))(i)
+}
+
+fn identifier(i: &str) -> IResult<&str, &str> {
+    verify(
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_'),
+        |s: &str| !s.starts_with(|c: char| c.is_ascii_digit()),
+    )(i)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum EvaluationError {
+    UnboundVariable(String),
+    DivisionByZero,
+    Overflow,
+}
+
+impl Display for EvaluationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::UnboundVariable(name) => write!(f, "{} is not bound", name),
+            EvaluationError::DivisionByZero => write!(f, "division by zero"),
+            EvaluationError::Overflow => write!(f, "overflow"),
+        }
+    }
+}
+
+impl From<EvaluationError> for AdventOfCodeError {
+    fn from(err: EvaluationError) -> AdventOfCodeError {
+        AdventOfCodeError::CannotEvaluate(err.to_string())
+    }
+}
+
+/**
+ * Named expressions that variables are resolved against
+ *
+ * A variable evaluates to whatever its bound expression evaluates to, so bindings can refer to
+ * each other, but they must not form a cycle.
+ */
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Environment {
+    bindings: HashMap<String, Expr>,
+}
+
+impl Environment {
+    pub fn new() -> Environment {
+        Environment::default()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.bindings.get(name)
+    }
+
+    pub fn evaluate(&self, expr: &Expr) -> Result<i128, EvaluationError> {
+        match expr {
+            Expr::Number(number) => Ok(*number),
+            Expr::Variable(name) => self.evaluate_variable(name),
+            Expr::Binary { lhs, operator, rhs } => {
+                operator.apply(self.evaluate(lhs)?, self.evaluate(rhs)?)
+            }
+        }
+    }
+
+    pub fn evaluate_variable(&self, name: &str) -> Result<i128, EvaluationError> {
+        match self.bindings.get(name) {
+            Some(expr) => self.evaluate(expr),
+            None => Err(EvaluationError::UnboundVariable(name.to_string())),
+        }
+    }
+}
+
+impl FromIterator<(String, Expr)> for Environment {
+    fn from_iter<T: IntoIterator<Item = (String, Expr)>>(iter: T) -> Environment {
+        Environment {
+            bindings: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::parse::finish;
+
+    fn parse(table: &OperatorTable, i: &str) -> Expr {
+        finish(|i| table.parse_expression(i))(i).unwrap().1
+    }
+
+    fn evaluate(table: &OperatorTable, i: &str) -> i128 {
+        Environment::new().evaluate(&parse(table, i)).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic_precedence() {
+        let table = OperatorTable::arithmetic();
+
+        assert_eq!(parse(&table, "1 + 2 * 3").to_string(), "(1 + (2 * 3))");
+        assert_eq!(parse(&table, "1 - 2 - 3").to_string(), "((1 - 2) - 3)");
+        assert_eq!(parse(&table, "(1 + 2) * 3").to_string(), "((1 + 2) * 3)");
+        assert_eq!(parse(&table, "8 / 4 / 2").to_string(), "((8 / 4) / 2)");
+
+        assert_eq!(evaluate(&table, "2 * 3 + (4 * 5)"), 26);
+        assert_eq!(evaluate(&table, "1 - 2 - 3"), -4);
+    }
+
+    #[test]
+    fn test_equal_precedence() {
+        let table = OperatorTable::new()
+            .with(BinaryOperator::Add, 1)
+            .with(BinaryOperator::Multiply, 1);
+
+        assert_eq!(evaluate(&table, "1 + 2 * 3 + 4 * 5 + 6"), 71);
+    }
+
+    #[test]
+    fn test_highest_precedence() {
+        let table = OperatorTable::new()
+            .with(BinaryOperator::Add, 0)
+            .with(BinaryOperator::Subtract, u8::MAX);
+
+        assert_eq!(
+            parse(&table, "1 - 2 - 3 + 4").to_string(),
+            "(((1 - 2) - 3) + 4)"
+        );
+    }
+
+    #[test]
+    fn test_operator_missing_from_table() {
+        let table = OperatorTable::new().with(BinaryOperator::Add, 1);
+
+        assert_eq!(
+            table.parse_expression("1 + 2 * 3"),
+            Ok((" * 3", parse(&table, "1 + 2")))
+        );
+    }
+
+    #[test]
+    fn test_parse_stops_at_newline() {
+        let table = OperatorTable::arithmetic();
+
+        assert_eq!(
+            table.parse_expression("abc * de_f\nnext"),
+            Ok((
+                "\nnext",
+                Expr::binary(
+                    Expr::variable("abc"),
+                    BinaryOperator::Multiply,
+                    Expr::variable("de_f")
+                )
+            ))
+        );
+    }
+
+    #[test]
+    fn test_environment() {
+        let table = OperatorTable::arithmetic();
+
+        let environment: Environment = vec![
+            ("a".to_string(), parse(&table, "b * 2")),
+            ("b".to_string(), Expr::Number(21)),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(environment.evaluate(&parse(&table, "a + b")), Ok(63));
+        assert_eq!(
+            environment.evaluate(&parse(&table, "a + c")),
+            Err(EvaluationError::UnboundVariable("c".to_string()))
+        );
+        assert_eq!(
+            environment.evaluate(&parse(&table, "a / (b - 21)")),
+            Err(EvaluationError::DivisionByZero)
+        );
+    }
+}
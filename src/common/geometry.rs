@@ -0,0 +1,452 @@
+use std::fmt::{self, Display};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+/// A point or displacement on an integer grid
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Default)]
+pub struct Vector2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl Vector2 {
+    pub const ZERO: Vector2 = Vector2 { x: 0, y: 0 };
+
+    pub fn new(x: i64, y: i64) -> Vector2 {
+        Vector2 { x, y }
+    }
+
+    /// The L1 norm, the number of orthogonal steps from the origin
+    pub fn manhattan(&self) -> i64 {
+        self.x.abs() + self.y.abs()
+    }
+
+    /// The L∞ norm, the number of steps from the origin when diagonal steps are allowed
+    #[cfg(test)]
+    pub fn chebyshev(&self) -> i64 {
+        self.x.abs().max(self.y.abs())
+    }
+
+    pub fn manhattan_distance(&self, other: &Vector2) -> i64 {
+        (*self - *other).manhattan()
+    }
+}
+
+impl Add for Vector2 {
+    type Output = Vector2;
+
+    fn add(self, other: Vector2) -> Vector2 {
+        Vector2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl AddAssign for Vector2 {
+    fn add_assign(&mut self, other: Vector2) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vector2 {
+    type Output = Vector2;
+
+    fn sub(self, other: Vector2) -> Vector2 {
+        Vector2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl SubAssign for Vector2 {
+    fn sub_assign(&mut self, other: Vector2) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Vector2 {
+    type Output = Vector2;
+
+    fn neg(self) -> Vector2 {
+        Vector2::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Vector2 {
+    type Output = Vector2;
+
+    fn mul(self, scalar: i64) -> Vector2 {
+        Vector2::new(self.x * scalar, self.y * scalar)
+    }
+}
+
+impl From<(i64, i64)> for Vector2 {
+    fn from((x, y): (i64, i64)) -> Vector2 {
+        Vector2::new(x, y)
+    }
+}
+
+impl Display for Vector2 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+/// A point or displacement on an integer lattice
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Default)]
+pub struct Vector3 {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl Vector3 {
+    pub const ZERO: Vector3 = Vector3 { x: 0, y: 0, z: 0 };
+
+    pub fn new(x: i64, y: i64, z: i64) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    /// The L1 norm, the number of orthogonal steps from the origin
+    pub fn manhattan(&self) -> i64 {
+        self.x.abs() + self.y.abs() + self.z.abs()
+    }
+
+    /// The L∞ norm, the number of steps from the origin when diagonal steps are allowed
+    pub fn chebyshev(&self) -> i64 {
+        self.x.abs().max(self.y.abs()).max(self.z.abs())
+    }
+
+    pub fn manhattan_distance(&self, other: &Vector3) -> i64 {
+        (*self - *other).manhattan()
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Vector3;
+
+    fn add(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl AddAssign for Vector3 {
+    fn add_assign(&mut self, other: Vector3) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+
+    fn sub(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl SubAssign for Vector3 {
+    fn sub_assign(&mut self, other: Vector3) {
+        *self = *self - other;
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+
+    fn neg(self) -> Vector3 {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl Mul<i64> for Vector3 {
+    type Output = Vector3;
+
+    fn mul(self, scalar: i64) -> Vector3 {
+        Vector3::new(self.x * scalar, self.y * scalar, self.z * scalar)
+    }
+}
+
+impl From<(i64, i64, i64)> for Vector3 {
+    fn from((x, y, z): (i64, i64, i64)) -> Vector3 {
+        Vector3::new(x, y, z)
+    }
+}
+
+impl Display for Vector3 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{},{})", self.x, self.y, self.z)
+    }
+}
+
+/// A linear map of the integer grid, stored by rows
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Matrix2 {
+    rows: [[i64; 2]; 2],
+}
+
+impl Matrix2 {
+    pub const IDENTITY: Matrix2 = Matrix2::new([[1, 0], [0, 1]]);
+
+    /// A quarter turn, taking the x axis to the y axis
+    pub const QUARTER_TURN: Matrix2 = Matrix2::new([[0, -1], [1, 0]]);
+
+    /// A reflection in the x axis, negating y
+    pub const REFLECTION: Matrix2 = Matrix2::new([[1, 0], [0, -1]]);
+
+    pub const fn new(rows: [[i64; 2]; 2]) -> Matrix2 {
+        Matrix2 { rows }
+    }
+
+    #[cfg(test)]
+    pub fn determinant(&self) -> i64 {
+        let [[a, b], [c, d]] = self.rows;
+
+        a * d - b * c
+    }
+
+    /**
+     * The symmetries of a square: the four quarter turns, then each of them after a reflection
+     *
+     * The identity comes first, so searching the group in order tries the untransformed shape
+     * before anything else.
+     */
+    pub fn dihedral_group() -> Vec<Matrix2> {
+        let rotations: Vec<Matrix2> = (0..4)
+            .scan(Matrix2::IDENTITY, |rotation, _| {
+                let current = *rotation;
+                *rotation = Matrix2::QUARTER_TURN * current;
+                Some(current)
+            })
+            .collect();
+
+        let reflections: Vec<Matrix2> = rotations
+            .iter()
+            .map(|rotation| *rotation * Matrix2::REFLECTION)
+            .collect();
+
+        rotations.into_iter().chain(reflections).collect()
+    }
+}
+
+impl Mul for Matrix2 {
+    type Output = Matrix2;
+
+    fn mul(self, other: Matrix2) -> Matrix2 {
+        let mut rows = [[0; 2]; 2];
+
+        for (row, values) in rows.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..2)
+                    .map(|k| self.rows[row][k] * other.rows[k][column])
+                    .sum();
+            }
+        }
+
+        Matrix2::new(rows)
+    }
+}
+
+impl Mul<Vector2> for Matrix2 {
+    type Output = Vector2;
+
+    fn mul(self, vector: Vector2) -> Vector2 {
+        let [[a, b], [c, d]] = self.rows;
+
+        Vector2::new(a * vector.x + b * vector.y, c * vector.x + d * vector.y)
+    }
+}
+
+/// A linear map of the integer lattice, stored by rows
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Matrix3 {
+    rows: [[i64; 3]; 3],
+}
+
+impl Matrix3 {
+    #[cfg(test)]
+    pub const IDENTITY: Matrix3 = Matrix3::new([[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+
+    pub const fn new(rows: [[i64; 3]; 3]) -> Matrix3 {
+        Matrix3 { rows }
+    }
+
+    pub fn determinant(&self) -> i64 {
+        let [[a, b, c], [d, e, f], [g, h, i]] = self.rows;
+
+        a * (e * i - f * h) - b * (d * i - f * g) + c * (d * h - e * g)
+    }
+
+    /// The inverse of a rotation or reflection, which only needs transposing
+    #[cfg(test)]
+    pub fn transpose(&self) -> Matrix3 {
+        let mut rows = [[0; 3]; 3];
+
+        for (row, values) in rows.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = self.rows[column][row];
+            }
+        }
+
+        Matrix3::new(rows)
+    }
+
+    /**
+     * The 24 rotations that take the axes onto the axes, i.e. every way of facing one of six
+     * directions with one of four directions as up
+     *
+     * These are the signed permutation matrices with determinant 1. The other 24 signed
+     * permutations are mirror images.
+     */
+    pub fn proper_rotations() -> Vec<Matrix3> {
+        let permutations = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+
+        let mut rotations = Vec::with_capacity(24);
+
+        for permutation in permutations.iter() {
+            for signs in 0..8 {
+                let mut rows = [[0; 3]; 3];
+
+                for (row, column) in permutation.iter().enumerate() {
+                    rows[row][*column] = if signs & (1 << row) == 0 { 1 } else { -1 };
+                }
+
+                let matrix = Matrix3::new(rows);
+
+                if matrix.determinant() == 1 {
+                    rotations.push(matrix);
+                }
+            }
+        }
+
+        rotations
+    }
+}
+
+impl Mul for Matrix3 {
+    type Output = Matrix3;
+
+    fn mul(self, other: Matrix3) -> Matrix3 {
+        let mut rows = [[0; 3]; 3];
+
+        for (row, values) in rows.iter_mut().enumerate() {
+            for (column, value) in values.iter_mut().enumerate() {
+                *value = (0..3)
+                    .map(|k| self.rows[row][k] * other.rows[k][column])
+                    .sum();
+            }
+        }
+
+        Matrix3::new(rows)
+    }
+}
+
+impl Mul<Vector3> for Matrix3 {
+    type Output = Vector3;
+
+    fn mul(self, vector: Vector3) -> Vector3 {
+        let [x, y, z] = self
+            .rows
+            .map(|[a, b, c]| a * vector.x + b * vector.y + c * vector.z);
+
+        Vector3::new(x, y, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_vector_operators() {
+        let a = Vector3::new(1, -2, 3);
+        let b = Vector3::new(-4, 5, 6);
+
+        assert_eq!(a + b, Vector3::new(-3, 3, 9));
+        assert_eq!(a - b, Vector3::new(5, -7, -3));
+        assert_eq!(-a, Vector3::new(-1, 2, -3));
+        assert_eq!(a * 2, Vector3::new(2, -4, 6));
+
+        let mut c = a;
+        c += b;
+        c -= a;
+        assert_eq!(c, b);
+
+        assert_eq!(Vector2::new(3, 4) - Vector2::new(1, 1), Vector2::new(2, 3));
+        assert_eq!(Vector2::from((1, 2)).to_string(), "(1,2)");
+    }
+
+    #[test]
+    fn test_norms() {
+        let v = Vector3::new(1, -7, 3);
+
+        assert_eq!(v.manhattan(), 11);
+        assert_eq!(v.chebyshev(), 7);
+        assert_eq!(v.manhattan_distance(&Vector3::ZERO), 11);
+
+        let v = Vector2::new(-2, 5);
+
+        assert_eq!(v.manhattan(), 7);
+        assert_eq!(v.chebyshev(), 5);
+        assert_eq!(v.manhattan_distance(&Vector2::new(1, 1)), 7);
+    }
+
+    #[test]
+    fn test_proper_rotations() {
+        let rotations = Matrix3::proper_rotations();
+
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(rotations[0], Matrix3::IDENTITY);
+
+        let distinct: HashSet<Matrix3> = rotations.iter().cloned().collect();
+        assert_eq!(distinct.len(), 24);
+
+        // the rotations form a group
+        for first in rotations.iter() {
+            assert_eq!(*first * first.transpose(), Matrix3::IDENTITY);
+
+            for second in rotations.iter() {
+                assert!(distinct.contains(&(*first * *second)));
+            }
+        }
+
+        let v = Vector3::new(1, 2, 3);
+        let images: HashSet<Vector3> = rotations.iter().map(|rotation| *rotation * v).collect();
+
+        assert_eq!(images.len(), 24);
+        assert!(images.contains(&Vector3::new(-1, -2, 3)));
+        assert!(!images.contains(&Vector3::new(1, 3, 2)));
+    }
+
+    #[test]
+    fn test_dihedral_group() {
+        let group = Matrix2::dihedral_group();
+
+        assert_eq!(group.len(), 8);
+        assert_eq!(group[0], Matrix2::IDENTITY);
+        assert_eq!(group[1], Matrix2::QUARTER_TURN);
+
+        let distinct: HashSet<Matrix2> = group.iter().cloned().collect();
+        assert_eq!(distinct.len(), 8);
+
+        for first in group.iter() {
+            for second in group.iter() {
+                assert!(distinct.contains(&(*first * *second)));
+            }
+        }
+
+        assert_eq!(
+            group
+                .iter()
+                .filter(|matrix| matrix.determinant() == -1)
+                .count(),
+            4
+        );
+        assert_eq!(
+            Matrix2::QUARTER_TURN * Vector2::new(1, 0),
+            Vector2::new(0, 1)
+        );
+    }
+}
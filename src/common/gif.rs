@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+
+use crate::common::animation::Frame;
+
+const PALETTE: [[u8; 3]; 16] = [
+    [0x00, 0x00, 0x00],
+    [0xff, 0xff, 0xff],
+    [0xe6, 0x19, 0x4b],
+    [0x3c, 0xb4, 0x4b],
+    [0xff, 0xe1, 0x19],
+    [0x43, 0x63, 0xd8],
+    [0xf5, 0x82, 0x31],
+    [0x91, 0x1e, 0xb4],
+    [0x42, 0xd4, 0xf4],
+    [0xf0, 0x32, 0xe6],
+    [0xbf, 0xef, 0x45],
+    [0xfa, 0xbe, 0xd4],
+    [0x46, 0x99, 0x90],
+    [0xdc, 0xbe, 0xff],
+    [0x9a, 0x63, 0x24],
+    [0x80, 0x80, 0x80],
+];
+
+// the global color table holds 2^(PALETTE_SIZE_FIELD + 1) entries
+const PALETTE_SIZE_FIELD: u8 = 3;
+const MIN_CODE_SIZE: u8 = 4;
+const MAX_CODE_SIZE: u8 = 12;
+
+/**
+ * A small animated GIF (GIF89a) encoder for character frames
+ *
+ * Every character is drawn as a square block of pixels. Colors come from a fixed 16-color palette
+ * so frames can be streamed without knowing every character up front.
+ */
+pub struct GifEncoder<W: Write> {
+    writer: W,
+    columns: usize,
+    rows: usize,
+    scale: usize,
+    delay_centiseconds: u16,
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Writes the GIF header; every frame added afterwards is clipped or padded to `columns` x `rows`
+    pub fn new(
+        writer: W,
+        columns: usize,
+        rows: usize,
+        scale: usize,
+        delay_centiseconds: u16,
+    ) -> io::Result<GifEncoder<W>> {
+        let mut encoder = GifEncoder {
+            writer,
+            columns,
+            rows,
+            scale,
+            delay_centiseconds,
+        };
+
+        let width = encoder.pixel_width()?;
+        let height = encoder.pixel_height()?;
+
+        encoder.writer.write_all(b"GIF89a")?;
+        encoder.writer.write_all(&width.to_le_bytes())?;
+        encoder.writer.write_all(&height.to_le_bytes())?;
+        // global color table, 8 bits of color resolution
+        encoder
+            .writer
+            .write_all(&[0xf0 | PALETTE_SIZE_FIELD, 0, 0])?;
+
+        for color in PALETTE.iter() {
+            encoder.writer.write_all(color)?;
+        }
+
+        // NETSCAPE2.0 extension so the animation loops forever
+        encoder.writer.write_all(&[0x21, 0xff, 0x0b])?;
+        encoder.writer.write_all(b"NETSCAPE2.0")?;
+        encoder.writer.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(encoder)
+    }
+
+    pub fn add_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let width = self.pixel_width()?;
+        let height = self.pixel_height()?;
+
+        // graphic control extension
+        self.writer.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        self.writer
+            .write_all(&self.delay_centiseconds.to_le_bytes())?;
+        self.writer.write_all(&[0x00, 0x00])?;
+
+        // image descriptor, no local color table
+        self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.writer.write_all(&width.to_le_bytes())?;
+        self.writer.write_all(&height.to_le_bytes())?;
+        self.writer.write_all(&[0x00])?;
+
+        let indices = self.rasterize(frame);
+
+        self.writer.write_all(&[MIN_CODE_SIZE])?;
+
+        for block in lzw_encode(MIN_CODE_SIZE, &indices).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+
+        self.writer.write_all(&[0x00])
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+
+    fn pixel_width(&self) -> io::Result<u16> {
+        to_dimension(self.columns * self.scale)
+    }
+
+    fn pixel_height(&self) -> io::Result<u16> {
+        to_dimension(self.rows * self.scale)
+    }
+
+    fn rasterize(&self, frame: &Frame) -> Vec<u8> {
+        let mut indices = Vec::with_capacity(self.columns * self.rows * self.scale * self.scale);
+
+        for row in 0..self.rows {
+            let row_indices: Vec<u8> = (0..self.columns)
+                .map(|column| frame.get(column, row).map(palette_index).unwrap_or(0))
+                .collect();
+
+            for _ in 0..self.scale {
+                for index in &row_indices {
+                    indices.extend(std::iter::repeat(*index).take(self.scale));
+                }
+            }
+        }
+
+        indices
+    }
+}
+
+fn to_dimension(pixels: usize) -> io::Result<u16> {
+    if pixels == 0 || pixels > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} pixels cannot be encoded as a GIF dimension", pixels),
+        ));
+    }
+
+    Ok(pixels as u16)
+}
+
+fn palette_index(c: char) -> u8 {
+    match c {
+        ' ' | '.' => 0,
+        '#' | '\u{2588}' => 1,
+        _ => 2 + (c as u32 % (PALETTE.len() as u32 - 2)) as u8,
+    }
+}
+
+fn lzw_encode(min_code_size: u8, indices: &[u8]) -> Vec<u8> {
+    let clear_code: u16 = 1 << min_code_size;
+    let end_code = clear_code + 1;
+
+    let mut writer = BitWriter::new();
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+
+    writer.write(clear_code, code_size);
+
+    let mut iter = indices.iter();
+
+    let mut prefix = match iter.next() {
+        Some(first) => *first as u16,
+        None => {
+            writer.write(end_code, code_size);
+            return writer.finish();
+        }
+    };
+
+    for index in iter {
+        if let Some(code) = dictionary.get(&(prefix, *index)) {
+            prefix = *code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+
+        // the decoder lags one code behind, so grow the code size once it would need the next bit
+        if next_code > (1 << code_size) - 1 && code_size < MAX_CODE_SIZE {
+            code_size += 1;
+        }
+
+        if next_code < 1 << MAX_CODE_SIZE {
+            dictionary.insert((prefix, *index), next_code);
+            next_code += 1;
+        } else {
+            writer.write(clear_code, code_size);
+            dictionary.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        }
+
+        prefix = *index as u16;
+    }
+
+    writer.write(prefix, code_size);
+
+    if next_code > (1 << code_size) - 1 && code_size < MAX_CODE_SIZE {
+        code_size += 1;
+    }
+
+    writer.write(end_code, code_size);
+
+    writer.finish()
+}
+
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    number_of_bits: u8,
+}
+
+impl BitWriter {
+    fn new() -> BitWriter {
+        BitWriter {
+            bytes: vec![],
+            buffer: 0,
+            number_of_bits: 0,
+        }
+    }
+
+    fn write(&mut self, code: u16, code_size: u8) {
+        self.buffer |= (code as u32) << self.number_of_bits;
+        self.number_of_bits += code_size;
+
+        while self.number_of_bits >= 8 {
+            self.bytes.push((self.buffer & 0xff) as u8);
+            self.buffer >>= 8;
+            self.number_of_bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.number_of_bits > 0 {
+            self.bytes.push((self.buffer & 0xff) as u8);
+        }
+
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a straightforward GIF LZW decoder, only used to check the encoder
+    fn lzw_decode(min_code_size: u8, bytes: &[u8]) -> Vec<u8> {
+        let clear_code: u16 = 1 << min_code_size;
+        let end_code = clear_code + 1;
+
+        let mut output = vec![];
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut code_size = min_code_size + 1;
+        let mut previous: Option<Vec<u8>> = None;
+
+        let mut bit_position = 0;
+
+        loop {
+            let mut code: u16 = 0;
+            for bit in 0..code_size {
+                let byte = bytes[(bit_position + bit as usize) / 8];
+                let value = (byte >> ((bit_position + bit as usize) % 8)) & 1;
+                code |= (value as u16) << bit;
+            }
+            bit_position += code_size as usize;
+
+            if code == clear_code {
+                table = (0..clear_code).map(|i| vec![i as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+
+            if code == end_code {
+                return output;
+            }
+
+            let entry = if (code as usize) < table.len() {
+                table[code as usize].clone()
+            } else {
+                let mut entry = previous.clone().unwrap();
+                entry.push(entry[0]);
+                entry
+            };
+
+            if let Some(mut previous) = previous {
+                previous.push(entry[0]);
+                table.push(previous);
+
+                if table.len() == 1 << code_size && code_size < MAX_CODE_SIZE {
+                    code_size += 1;
+                }
+            }
+
+            output.extend(&entry);
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_round_trip() {
+        let short = vec![1, 1, 1, 1, 2, 2, 2, 0, 0, 1, 1, 1, 1, 2];
+        assert_eq!(lzw_decode(4, &lzw_encode(4, &short)), short);
+
+        // long and varied enough to fill the dictionary and force a clear code
+        let long: Vec<u8> = (0..200_000u32)
+            .map(|i| ((i * 7919) ^ (i / 13)) as u8 % 16)
+            .collect();
+        assert_eq!(lzw_decode(4, &lzw_encode(4, &long)), long);
+
+        assert_eq!(lzw_decode(4, &lzw_encode(4, &[])), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_encode_frames() {
+        let mut first = Frame::new(2, 2);
+        first.set(0, 0, '#');
+
+        let mut second = Frame::new(3, 3);
+        second.set(1, 1, 'o');
+
+        let mut encoder = GifEncoder::new(vec![], 2, 2, 2, 10).unwrap();
+        encoder.add_frame(&first).unwrap();
+        encoder.add_frame(&second).unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        assert_eq!(&bytes[0..6], b"GIF89a");
+        assert_eq!(&bytes[6..10], &[4, 0, 4, 0]);
+        assert_eq!(bytes.last(), Some(&0x3b));
+    }
+
+    #[test]
+    fn test_rasterize() {
+        let mut frame = Frame::new(2, 1);
+        frame.set(1, 0, '#');
+
+        let encoder = GifEncoder::new(vec![], 2, 1, 2, 10).unwrap();
+
+        assert_eq!(encoder.rasterize(&frame), vec![0, 0, 1, 1, 0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn test_empty_canvas_is_an_error() {
+        assert!(GifEncoder::new(vec![], 0, 5, 2, 10).is_err());
+    }
+}
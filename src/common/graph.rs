@@ -0,0 +1,448 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt::Display;
+
+use petgraph::EdgeType;
+
+const SVG_SIZE: f64 = 800.0;
+const SVG_MARGIN: f64 = 40.0;
+const NODE_RADIUS: f64 = 14.0;
+const LAYOUT_ITERATIONS: usize = 300;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GraphKind {
+    Directed,
+    Undirected,
+}
+
+impl GraphKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            GraphKind::Directed => "digraph",
+            GraphKind::Undirected => "graph",
+        }
+    }
+
+    fn edge_operator(&self) -> &'static str {
+        match self {
+            GraphKind::Directed => "->",
+            GraphKind::Undirected => "--",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Node {
+    id: String,
+    label: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Edge {
+    from: usize,
+    to: usize,
+    label: Option<String>,
+}
+
+/// A minimal graph model that can be exported as DOT or rendered as a standalone SVG
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Graph {
+    name: String,
+    kind: GraphKind,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    indices_by_id: HashMap<String, usize>,
+}
+
+impl Graph {
+    pub fn new<S: Into<String>>(name: S, kind: GraphKind) -> Graph {
+        Graph {
+            name: name.into(),
+            kind,
+            nodes: vec![],
+            edges: vec![],
+            indices_by_id: HashMap::new(),
+        }
+    }
+
+    pub fn directed<S: Into<String>>(name: S) -> Graph {
+        Graph::new(name, GraphKind::Directed)
+    }
+
+    pub fn undirected<S: Into<String>>(name: S) -> Graph {
+        Graph::new(name, GraphKind::Undirected)
+    }
+
+    /// Adds a node (or relabels it, if it already exists) and returns its index
+    pub fn add_node<I: Into<String>, L: Into<String>>(&mut self, id: I, label: L) -> usize {
+        let id = id.into();
+        let label = label.into();
+
+        if let Some(index) = self.indices_by_id.get(&id) {
+            self.nodes[*index].label = label;
+            return *index;
+        }
+
+        let index = self.nodes.len();
+        self.indices_by_id.insert(id.clone(), index);
+        self.nodes.push(Node { id, label });
+
+        index
+    }
+
+    /// Adds an edge, creating either endpoint if it hasn't been seen yet
+    pub fn add_edge<F, T>(&mut self, from: F, to: T, label: Option<String>)
+    where
+        F: Into<String>,
+        T: Into<String>,
+    {
+        let from = self.node_index(from.into());
+        let to = self.node_index(to.into());
+
+        self.edges.push(Edge { from, to, label });
+    }
+
+    pub fn from_petgraph<N, E, Ty>(name: &str, graph: &petgraph::Graph<N, E, Ty>) -> Graph
+    where
+        N: Display,
+        E: Display,
+        Ty: EdgeType,
+    {
+        let mut result = if graph.is_directed() {
+            Graph::directed(name)
+        } else {
+            Graph::undirected(name)
+        };
+
+        for index in graph.node_indices() {
+            result.add_node(index.index().to_string(), graph[index].to_string());
+        }
+
+        for edge in graph.raw_edges() {
+            result.add_edge(
+                edge.source().index().to_string(),
+                edge.target().index().to_string(),
+                Some(edge.weight.to_string()),
+            );
+        }
+
+        result
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec![format!(
+            "{} {} {{",
+            self.kind.keyword(),
+            quote_dot(&self.name)
+        )];
+
+        for node in &self.nodes {
+            lines.push(format!(
+                "\t{} [ label={} ]",
+                quote_dot(&node.id),
+                quote_dot(&node.label)
+            ));
+        }
+
+        for edge in &self.edges {
+            let from = quote_dot(&self.nodes[edge.from].id);
+            let to = quote_dot(&self.nodes[edge.to].id);
+            let operator = self.kind.edge_operator();
+
+            match &edge.label {
+                Some(label) => lines.push(format!(
+                    "\t{} {} {} [ label={} ]",
+                    from,
+                    operator,
+                    to,
+                    quote_dot(label)
+                )),
+                None => lines.push(format!("\t{} {} {}", from, operator, to)),
+            }
+        }
+
+        lines.push("}".to_string());
+
+        lines.join("\n")
+    }
+
+    pub fn to_svg(&self) -> String {
+        let positions = self.layout();
+
+        let mut lines = vec![format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">",
+            size = SVG_SIZE
+        )];
+
+        lines.push(format!("<title>{}</title>", escape_xml(&self.name)));
+
+        if self.kind == GraphKind::Directed {
+            lines.push(
+                "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"6\" markerHeight=\"6\" orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\"/></marker></defs>"
+                    .to_string(),
+            );
+        }
+
+        for edge in &self.edges {
+            let (x1, y1) = positions[edge.from];
+            let (x2, y2) = positions[edge.to];
+
+            // stop short of the node circles so arrowheads stay visible
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let length = (dx * dx + dy * dy).sqrt().max(f64::EPSILON);
+            let (ux, uy) = (dx / length, dy / length);
+
+            let marker = match self.kind {
+                GraphKind::Directed => " marker-end=\"url(#arrow)\"",
+                GraphKind::Undirected => "",
+            };
+
+            lines.push(format!(
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\" stroke=\"black\"{}/>",
+                x1 + ux * NODE_RADIUS,
+                y1 + uy * NODE_RADIUS,
+                x2 - ux * NODE_RADIUS,
+                y2 - uy * NODE_RADIUS,
+                marker
+            ));
+
+            if let Some(label) = &edge.label {
+                lines.push(format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" fill=\"gray\">{}</text>",
+                    (x1 + x2) / 2.0,
+                    (y1 + y2) / 2.0,
+                    escape_xml(label)
+                ));
+            }
+        }
+
+        for (node, (x, y)) in self.nodes.iter().zip(positions.iter()) {
+            lines.push(format!(
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"{}\" fill=\"white\" stroke=\"black\"/>",
+                x, y, NODE_RADIUS
+            ));
+            lines.push(format!(
+                "<text x=\"{:.1}\" y=\"{:.1}\" font-size=\"10\" text-anchor=\"middle\" dominant-baseline=\"central\">{}</text>",
+                x,
+                y,
+                escape_xml(&node.label)
+            ));
+        }
+
+        lines.push("</svg>".to_string());
+
+        lines.join("\n")
+    }
+
+    fn node_index(&mut self, id: String) -> usize {
+        match self.indices_by_id.get(&id) {
+            Some(index) => *index,
+            None => self.add_node(id.clone(), id),
+        }
+    }
+
+    /// Fruchterman-Reingold force-directed layout, seeded from a circle so the output is deterministic
+    fn layout(&self) -> Vec<(f64, f64)> {
+        let number_of_nodes = self.nodes.len();
+
+        if number_of_nodes == 0 {
+            return vec![];
+        }
+
+        let usable = SVG_SIZE - 2.0 * SVG_MARGIN;
+        let center = SVG_SIZE / 2.0;
+
+        let mut positions: Vec<(f64, f64)> = (0..number_of_nodes)
+            .map(|index| {
+                let angle = 2.0 * PI * index as f64 / number_of_nodes as f64;
+                (
+                    center + usable / 2.0 * angle.cos(),
+                    center + usable / 2.0 * angle.sin(),
+                )
+            })
+            .collect();
+
+        if number_of_nodes == 1 {
+            return vec![(center, center)];
+        }
+
+        let ideal_distance = (usable * usable / number_of_nodes as f64).sqrt();
+        let mut temperature = usable / 10.0;
+        let cooling = temperature / LAYOUT_ITERATIONS as f64;
+
+        for _ in 0..LAYOUT_ITERATIONS {
+            let mut displacements = vec![(0.0, 0.0); number_of_nodes];
+
+            for i in 0..number_of_nodes {
+                for j in (i + 1)..number_of_nodes {
+                    let (dx, dy) = difference(positions[i], positions[j]);
+                    let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                    let force = ideal_distance * ideal_distance / distance;
+
+                    displacements[i].0 += dx / distance * force;
+                    displacements[i].1 += dy / distance * force;
+                    displacements[j].0 -= dx / distance * force;
+                    displacements[j].1 -= dy / distance * force;
+                }
+            }
+
+            for edge in &self.edges {
+                if edge.from == edge.to {
+                    continue;
+                }
+
+                let (dx, dy) = difference(positions[edge.from], positions[edge.to]);
+                let distance = (dx * dx + dy * dy).sqrt().max(0.01);
+                let force = distance * distance / ideal_distance;
+
+                displacements[edge.from].0 -= dx / distance * force;
+                displacements[edge.from].1 -= dy / distance * force;
+                displacements[edge.to].0 += dx / distance * force;
+                displacements[edge.to].1 += dy / distance * force;
+            }
+
+            for (position, (dx, dy)) in positions.iter_mut().zip(displacements) {
+                let length = (dx * dx + dy * dy).sqrt().max(0.01);
+                let step = length.min(temperature);
+
+                position.0 += dx / length * step;
+                position.1 += dy / length * step;
+            }
+
+            temperature -= cooling;
+        }
+
+        fit_to_canvas(&positions)
+    }
+}
+
+fn difference(a: (f64, f64), b: (f64, f64)) -> (f64, f64) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+fn fit_to_canvas(positions: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let min_x = positions.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let max_x = positions
+        .iter()
+        .map(|p| p.0)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let min_y = positions.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_y = positions
+        .iter()
+        .map(|p| p.1)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let usable = SVG_SIZE - 2.0 * SVG_MARGIN;
+    let scale = usable / (max_x - min_x).max(max_y - min_y).max(f64::EPSILON);
+
+    positions
+        .iter()
+        .map(|(x, y)| {
+            (
+                SVG_MARGIN + (x - min_x) * scale,
+                SVG_MARGIN + (y - min_y) * scale,
+            )
+        })
+        .collect()
+}
+
+fn quote_dot(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let mut graph = Graph::undirected("Valves");
+        graph.add_node("AA", "AA (0)");
+        graph.add_edge("AA", "BB", Some("1".to_string()));
+        graph.add_edge("BB", "CC", None);
+
+        assert_eq!(
+            graph.to_dot(),
+            [
+                "graph \"Valves\" {",
+                "\t\"AA\" [ label=\"AA (0)\" ]",
+                "\t\"BB\" [ label=\"BB\" ]",
+                "\t\"CC\" [ label=\"CC\" ]",
+                "\t\"AA\" -- \"BB\" [ label=\"1\" ]",
+                "\t\"BB\" -- \"CC\"",
+                "}"
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_add_node_relabels_existing_node() {
+        let mut graph = Graph::directed("test");
+
+        assert_eq!(graph.add_node("a", "first"), 0);
+        assert_eq!(graph.add_node("b", "b"), 1);
+        assert_eq!(graph.add_node("a", "second"), 0);
+
+        assert_eq!(graph.nodes[0].label, "second");
+    }
+
+    #[test]
+    fn test_from_petgraph() {
+        let graph = petgraph::graph::DiGraph::<char, usize>::from_edges([(0, 1, 5)]);
+        let graph = petgraph::Graph::map(
+            &graph,
+            |index, _| (b'a' + index.index() as u8) as char,
+            |_, e| *e,
+        );
+
+        assert_eq!(
+            Graph::from_petgraph("deps", &graph).to_dot(),
+            [
+                "digraph \"deps\" {",
+                "\t\"0\" [ label=\"a\" ]",
+                "\t\"1\" [ label=\"b\" ]",
+                "\t\"0\" -> \"1\" [ label=\"5\" ]",
+                "}"
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let mut graph = Graph::directed("a<b");
+        graph.add_edge("a", "b", None);
+        graph.add_edge("b", "c", Some("x&y".to_string()));
+
+        let svg = graph.to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("<title>a&lt;b</title>"));
+        assert!(svg.contains("x&amp;y"));
+        assert_eq!(svg.matches("<circle").count(), 3);
+        assert_eq!(svg.matches("marker-end").count(), 2);
+        assert_eq!(svg, graph.to_svg());
+    }
+
+    #[test]
+    fn test_layout_stays_on_canvas() {
+        let mut graph = Graph::undirected("ring");
+        for i in 0..10 {
+            graph.add_edge(i.to_string(), ((i + 1) % 10).to_string(), None);
+        }
+
+        for (x, y) in graph.layout() {
+            assert!((SVG_MARGIN - 0.001..=SVG_SIZE - SVG_MARGIN + 0.001).contains(&x));
+            assert!((SVG_MARGIN - 0.001..=SVG_SIZE - SVG_MARGIN + 0.001).contains(&y));
+        }
+    }
+}
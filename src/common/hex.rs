@@ -0,0 +1,261 @@
+use nom::{branch::alt, bytes::complete::tag, combinator::value, multi::many1, IResult};
+use std::fmt::{self, Display};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub};
+
+/**
+ * A hexagon on a hex grid, as cube coordinates that always sum to zero
+ *
+ * Stepping in any of the six directions adds one to one coordinate and takes one from another, so
+ * the distance between two hexagons is the largest difference between their coordinates.
+ */
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord, Default)]
+pub struct Cube {
+    pub q: i64,
+    pub r: i64,
+    pub s: i64,
+}
+
+/// Cube coordinates without the `s` coordinate, which can always be worked out from the others
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord, Default)]
+pub struct Axial {
+    pub q: i64,
+    pub r: i64,
+}
+
+impl Cube {
+    pub const ORIGIN: Cube = Cube { q: 0, r: 0, s: 0 };
+
+    pub fn new(q: i64, r: i64, s: i64) -> Cube {
+        debug_assert_eq!(q + r + s, 0, "cube coordinates have to sum to zero");
+
+        Cube { q, r, s }
+    }
+
+    /// The number of steps from the origin
+    pub fn length(&self) -> i64 {
+        self.q.abs().max(self.r.abs()).max(self.s.abs())
+    }
+
+    pub fn distance(&self, other: &Cube) -> i64 {
+        (*self - *other).length()
+    }
+
+    pub fn neighbours(&self) -> [Cube; 6] {
+        PointyDirection::ALL.map(|direction| *self + direction.offset())
+    }
+
+    /**
+     * The 6 × `radius` hexagons exactly `radius` steps away, going round anticlockwise on a
+     * pointy-top grid starting from the one furthest east
+     */
+    pub fn ring(&self, radius: i64) -> Vec<Cube> {
+        match radius {
+            0 => return vec![*self],
+            // nothing is a negative number of steps away
+            radius if radius < 0 => return vec![],
+            _ => {}
+        }
+
+        let mut hexagon = *self + PointyDirection::East.offset() * radius;
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+
+        // from the east corner, the first side runs northwest and each side after turns left
+        for direction in PointyDirection::ALL.iter().cycle().skip(2).take(6) {
+            for _ in 0..radius {
+                ring.push(hexagon);
+                hexagon += direction.offset();
+            }
+        }
+
+        ring
+    }
+
+    /// Every hexagon within `radius` steps, one ring at a time from this one outwards
+    pub fn spiral(&self, radius: i64) -> Vec<Cube> {
+        (0..=radius).flat_map(|radius| self.ring(radius)).collect()
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(axial: Axial) -> Cube {
+        Cube::new(axial.q, axial.r, -axial.q - axial.r)
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(cube: Cube) -> Axial {
+        Axial {
+            q: cube.q,
+            r: cube.r,
+        }
+    }
+}
+
+impl Add for Cube {
+    type Output = Cube;
+
+    fn add(self, other: Cube) -> Cube {
+        Cube::new(self.q + other.q, self.r + other.r, self.s + other.s)
+    }
+}
+
+impl AddAssign for Cube {
+    fn add_assign(&mut self, other: Cube) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Cube {
+    type Output = Cube;
+
+    fn sub(self, other: Cube) -> Cube {
+        Cube::new(self.q - other.q, self.r - other.r, self.s - other.s)
+    }
+}
+
+impl Neg for Cube {
+    type Output = Cube;
+
+    fn neg(self) -> Cube {
+        Cube::new(-self.q, -self.r, -self.s)
+    }
+}
+
+impl Mul<i64> for Cube {
+    type Output = Cube;
+
+    fn mul(self, scale: i64) -> Cube {
+        Cube::new(self.q * scale, self.r * scale, self.s * scale)
+    }
+}
+
+impl Display for Cube {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({},{},{})", self.q, self.r, self.s)
+    }
+}
+
+/// The neighbours of a hexagon with a point at the top, so it has neighbours east and west
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PointyDirection {
+    East,
+    Northeast,
+    Northwest,
+    West,
+    Southwest,
+    Southeast,
+}
+
+impl PointyDirection {
+    /// Every direction, anticlockwise from east
+    pub const ALL: [PointyDirection; 6] = [
+        PointyDirection::East,
+        PointyDirection::Northeast,
+        PointyDirection::Northwest,
+        PointyDirection::West,
+        PointyDirection::Southwest,
+        PointyDirection::Southeast,
+    ];
+
+    pub fn offset(&self) -> Cube {
+        match self {
+            PointyDirection::East => Cube::new(1, 0, -1),
+            PointyDirection::Northeast => Cube::new(1, -1, 0),
+            PointyDirection::Northwest => Cube::new(0, -1, 1),
+            PointyDirection::West => Cube::new(-1, 0, 1),
+            PointyDirection::Southwest => Cube::new(-1, 1, 0),
+            PointyDirection::Southeast => Cube::new(0, 1, -1),
+        }
+    }
+}
+
+/// Where a walk from `start` ends up
+pub fn walk<I: IntoIterator<Item = Cube>>(start: Cube, steps: I) -> Cube {
+    steps
+        .into_iter()
+        .fold(start, |hexagon, step| hexagon + step)
+}
+
+/// Pointy-top directions run together with nothing between them, like `nwwswee`
+pub fn pointy_directions(i: &str) -> IResult<&str, Vec<PointyDirection>> {
+    many1(pointy_direction)(i)
+}
+
+fn pointy_direction(i: &str) -> IResult<&str, PointyDirection> {
+    alt((
+        value(PointyDirection::Southeast, tag("se")),
+        value(PointyDirection::Southwest, tag("sw")),
+        value(PointyDirection::Northeast, tag("ne")),
+        value(PointyDirection::Northwest, tag("nw")),
+        value(PointyDirection::East, tag("e")),
+        value(PointyDirection::West, tag("w")),
+    ))(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pointy_directions() {
+        let (remaining, directions) = pointy_directions("nwwswee").unwrap();
+
+        assert_eq!(remaining, "");
+        assert_eq!(
+            walk(Cube::ORIGIN, directions.iter().map(|d| d.offset())),
+            Cube::ORIGIN
+        );
+
+        let (_, directions) = pointy_directions("esew").unwrap();
+
+        assert_eq!(
+            walk(Cube::ORIGIN, directions.iter().map(|d| d.offset())),
+            PointyDirection::Southeast.offset()
+        );
+    }
+
+    #[test]
+    fn test_distance() {
+        let a = Cube::new(1, -3, 2);
+        let b = Cube::new(-2, 1, 1);
+
+        assert_eq!(a.distance(&b), 4);
+        assert_eq!(b.distance(&a), 4);
+        assert_eq!(a.distance(&a), 0);
+    }
+
+    #[test]
+    fn test_rings_and_spirals() {
+        let centre = Cube::new(2, -1, -1);
+
+        for radius in 0..5 {
+            let ring = centre.ring(radius);
+
+            assert_eq!(ring.len(), (6 * radius).max(1) as usize);
+            assert!(ring
+                .iter()
+                .all(|hexagon| hexagon.distance(&centre) == radius));
+
+            for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                assert!(radius == 0 || a.distance(b) == 1);
+            }
+        }
+
+        assert!(centre.ring(-1).is_empty());
+
+        let spiral = centre.spiral(3);
+
+        assert_eq!(spiral.len(), 37);
+        assert_eq!(spiral[0], centre);
+        assert_eq!(spiral[1..7].to_vec(), centre.neighbours().to_vec());
+    }
+
+    #[test]
+    fn test_axial() {
+        let cube = Cube::new(3, -1, -2);
+        let axial: Axial = cube.into();
+
+        assert_eq!(axial, Axial { q: 3, r: -1 });
+        assert_eq!(Cube::from(axial), cube);
+    }
+}
@@ -0,0 +1,388 @@
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::RangeInclusive;
+
+/// A closed range of integers `[start, end]`
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Interval {
+    start: i64,
+    end: i64,
+}
+
+impl Interval {
+    pub fn new(start: i64, end: i64) -> Interval {
+        assert!(
+            start <= end,
+            "interval start {} is after end {}",
+            start,
+            end
+        );
+
+        Interval { start, end }
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    /// The number of integers in the interval, which can be one more than `u64::MAX`
+    pub fn len(&self) -> u128 {
+        u128::from(self.end.abs_diff(self.start)) + 1
+    }
+
+    #[cfg(test)]
+    pub fn contains(&self, value: i64) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn contains_interval(&self, other: &Interval) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    #[cfg(test)]
+    pub fn intersection(&self, other: &Interval) -> Option<Interval> {
+        if self.overlaps(other) {
+            Some(Interval::new(
+                self.start.max(other.start),
+                self.end.min(other.end),
+            ))
+        } else {
+            None
+        }
+    }
+
+    // overlapping or directly next to each other, e.g. [1, 3] and [4, 6]
+    fn touches(&self, other: &Interval) -> bool {
+        self.start <= other.end.saturating_add(1) && other.start <= self.end.saturating_add(1)
+    }
+}
+
+impl From<RangeInclusive<i64>> for Interval {
+    fn from(range: RangeInclusive<i64>) -> Interval {
+        Interval::new(*range.start(), *range.end())
+    }
+}
+
+/**
+ * A set of integers, stored as sorted, disjoint intervals
+ *
+ * Intervals that overlap or touch are merged as they're added, so the set always has a single
+ * canonical representation.
+ */
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct IntervalSet {
+    intervals: Vec<Interval>,
+}
+
+impl IntervalSet {
+    #[cfg(test)]
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval] {
+        &self.intervals
+    }
+
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    #[cfg(test)]
+    pub fn insert(&mut self, interval: Interval) {
+        // every interval before `first` ends too early to touch the new one
+        let first = self
+            .intervals
+            .partition_point(|existing| existing.end.saturating_add(1) < interval.start);
+
+        let mut merged = interval;
+        let mut last = first;
+
+        while last < self.intervals.len() && self.intervals[last].touches(&merged) {
+            merged = Interval::new(
+                merged.start.min(self.intervals[last].start),
+                merged.end.max(self.intervals[last].end),
+            );
+            last += 1;
+        }
+
+        self.intervals.splice(first..last, std::iter::once(merged));
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// Returns the interval containing `value`, if there is one
+    pub fn find(&self, value: i64) -> Option<&Interval> {
+        self.intervals
+            .binary_search_by(|interval| {
+                if interval.end < value {
+                    Ordering::Less
+                } else if interval.start > value {
+                    Ordering::Greater
+                } else {
+                    Ordering::Equal
+                }
+            })
+            .ok()
+            .map(|index| &self.intervals[index])
+    }
+
+    /// The number of integers in the set
+    pub fn coverage(&self) -> u128 {
+        self.intervals.iter().map(Interval::len).sum()
+    }
+
+    #[cfg(test)]
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        self.intervals
+            .iter()
+            .chain(other.intervals.iter())
+            .copied()
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = vec![];
+
+        let mut i = 0;
+        let mut j = 0;
+
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let first = &self.intervals[i];
+            let second = &other.intervals[j];
+
+            if let Some(intersection) = first.intersection(second) {
+                intervals.push(intersection);
+            }
+
+            if first.end < second.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+
+        // pieces of disjoint, non-touching intervals can't touch each other
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = vec![];
+
+        let mut j = 0;
+
+        for interval in &self.intervals {
+            // skip everything in `other` that ends before this interval
+            while j < other.intervals.len() && other.intervals[j].end < interval.start {
+                j += 1;
+            }
+
+            // the start of the part of this interval that hasn't been removed yet, if any
+            let mut remaining = Some(interval.start);
+
+            for removed in &other.intervals[j..] {
+                let start = match remaining {
+                    Some(start) if removed.start <= interval.end => start,
+                    _ => break,
+                };
+
+                if removed.start > start {
+                    intervals.push(Interval::new(start, removed.start - 1));
+                }
+
+                remaining = if removed.end < interval.end {
+                    Some(removed.end + 1)
+                } else {
+                    None
+                };
+            }
+
+            if let Some(start) = remaining {
+                intervals.push(Interval::new(start, interval.end));
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+
+    /// The maximal runs of integers within `bounds` that are not in the set
+    pub fn gaps(&self, bounds: Interval) -> IntervalSet {
+        IntervalSet::from(bounds).difference(self)
+    }
+}
+
+impl From<Interval> for IntervalSet {
+    fn from(interval: Interval) -> IntervalSet {
+        IntervalSet {
+            intervals: vec![interval],
+        }
+    }
+}
+
+impl FromIterator<Interval> for IntervalSet {
+    fn from_iter<T: IntoIterator<Item = Interval>>(iter: T) -> IntervalSet {
+        let mut sorted: Vec<Interval> = iter.into_iter().collect();
+        sorted.sort_unstable();
+
+        let mut intervals: Vec<Interval> = Vec::with_capacity(sorted.len());
+
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if last.touches(&interval) => last.end = last.end.max(interval.end),
+                _ => intervals.push(interval),
+            }
+        }
+
+        IntervalSet { intervals }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[(i64, i64)]) -> IntervalSet {
+        ranges
+            .iter()
+            .map(|(start, end)| Interval::new(*start, *end))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval() {
+        let interval = Interval::new(2, 6);
+
+        assert_eq!(interval.len(), 5);
+        assert!(interval.contains(2));
+        assert!(interval.contains(6));
+        assert!(!interval.contains(7));
+
+        assert!(interval.contains_interval(&Interval::new(3, 6)));
+        assert!(!interval.contains_interval(&Interval::new(3, 7)));
+
+        assert!(interval.overlaps(&Interval::new(6, 8)));
+        assert!(!interval.overlaps(&Interval::new(7, 8)));
+
+        assert_eq!(
+            interval.intersection(&Interval::new(4, 8)),
+            Some(Interval::new(4, 6))
+        );
+        assert_eq!(interval.intersection(&Interval::new(7, 8)), None);
+
+        assert_eq!(Interval::from(-3..=3).len(), 7);
+    }
+
+    #[test]
+    fn test_from_iter_merges() {
+        let merged = set(&[(5, 7), (1, 3), (4, 4), (10, 12), (11, 20)]);
+
+        assert_eq!(merged, set(&[(1, 7), (10, 20)]));
+        assert_eq!(merged.intervals().len(), 2);
+        assert_eq!(merged.coverage(), 18);
+    }
+
+    #[test]
+    fn test_insert() {
+        let mut intervals = IntervalSet::new();
+
+        intervals.insert(Interval::new(10, 12));
+        intervals.insert(Interval::new(1, 2));
+        intervals.insert(Interval::new(20, 25));
+        assert_eq!(intervals, set(&[(1, 2), (10, 12), (20, 25)]));
+
+        intervals.insert(Interval::new(3, 9));
+        assert_eq!(intervals, set(&[(1, 12), (20, 25)]));
+
+        intervals.insert(Interval::new(14, 30));
+        assert_eq!(intervals, set(&[(1, 12), (14, 30)]));
+
+        intervals.insert(Interval::new(13, 13));
+        assert_eq!(intervals, set(&[(1, 30)]));
+    }
+
+    #[test]
+    fn test_contains_and_find() {
+        let intervals = set(&[(1, 3), (10, 12)]);
+
+        assert!(intervals.contains(1));
+        assert!(intervals.contains(11));
+        assert!(!intervals.contains(5));
+        assert!(!intervals.contains(13));
+
+        assert_eq!(intervals.find(12), Some(&Interval::new(10, 12)));
+        assert_eq!(intervals.find(0), None);
+    }
+
+    #[test]
+    fn test_union() {
+        assert_eq!(
+            set(&[(1, 3), (10, 12)]).union(&set(&[(2, 5), (13, 14), (20, 20)])),
+            set(&[(1, 5), (10, 14), (20, 20)])
+        );
+    }
+
+    #[test]
+    fn test_intersection() {
+        assert_eq!(
+            set(&[(1, 10), (20, 30)]).intersection(&set(&[(5, 22), (25, 26), (29, 40)])),
+            set(&[(5, 10), (20, 22), (25, 26), (29, 30)])
+        );
+
+        assert!(set(&[(1, 2)]).intersection(&set(&[(3, 4)])).is_empty());
+    }
+
+    #[test]
+    fn test_difference() {
+        assert_eq!(
+            set(&[(1, 10), (20, 30)]).difference(&set(&[(3, 4), (8, 22), (30, 35)])),
+            set(&[(1, 2), (5, 7), (23, 29)])
+        );
+
+        assert_eq!(
+            set(&[(1, 10)]).difference(&set(&[(-5, 0), (11, 15)])),
+            set(&[(1, 10)])
+        );
+
+        assert!(set(&[(1, 10)]).difference(&set(&[(0, 11)])).is_empty());
+        assert_eq!(
+            set(&[(1, 10)]).difference(&IntervalSet::new()),
+            set(&[(1, 10)])
+        );
+    }
+
+    #[test]
+    fn test_gaps() {
+        let intervals = set(&[(-2, 3), (5, 8), (12, 20)]);
+
+        assert_eq!(
+            intervals.gaps(Interval::new(0, 15)),
+            set(&[(4, 4), (9, 11)])
+        );
+        assert_eq!(
+            intervals.gaps(Interval::new(-5, 25)),
+            set(&[(-5, -3), (4, 4), (9, 11), (21, 25)])
+        );
+        assert!(intervals.gaps(Interval::new(13, 19)).is_empty());
+    }
+
+    #[test]
+    fn test_extreme_values() {
+        let everything = set(&[(i64::MIN, i64::MAX)]);
+
+        assert_eq!(set(&[(i64::MIN, -1)]).coverage(), 1 << 63);
+        assert_eq!(everything.coverage(), 1 << 64);
+        assert!(everything.gaps(Interval::new(0, 10)).is_empty());
+        assert_eq!(
+            everything.difference(&set(&[(0, 0)])),
+            set(&[(i64::MIN, -1), (1, i64::MAX)])
+        );
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Write};
+use std::str::FromStr;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag, take_while_m_n},
+    character::complete::{char, multispace0},
+    combinator::{all_consuming, map, map_opt, value},
+    multi::{fold_many0, separated_list0},
+    sequence::{delimited, preceded, separated_pair},
+    IResult,
+};
+
+use crate::common::answer::AdventOfCodeError;
+use crate::common::parse::{number, whitespace};
+
+/**
+ * A minimal JSON value
+ *
+ * Numbers are limited to integers, which is all the puzzles use. Objects keep their keys sorted, so
+ * they always render the same way.
+ */
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+impl FromStr for Json {
+    type Err = AdventOfCodeError;
+
+    fn from_str(s: &str) -> Result<Json, AdventOfCodeError> {
+        all_consuming(whitespace(json))(s)
+            .map(|(_, json)| json)
+            .map_err(|_| AdventOfCodeError::NomParseError)
+    }
+}
+
+/// Renders compact JSON, with no whitespace between tokens
+impl Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_string(f, s),
+            Json::Array(elements) => {
+                f.write_char('[')?;
+
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+
+                    write!(f, "{}", element)?;
+                }
+
+                f.write_char(']')
+            }
+            Json::Object(members) => {
+                f.write_char('{')?;
+
+                for (index, (key, member)) in members.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+
+                    write_string(f, key)?;
+                    write!(f, ":{}", member)?;
+                }
+
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+fn json(i: &str) -> IResult<&str, Json> {
+    alt((
+        value(Json::Null, tag("null")),
+        value(Json::Bool(true), tag("true")),
+        value(Json::Bool(false), tag("false")),
+        map(number, Json::Number),
+        map(string, Json::String),
+        map(array, Json::Array),
+        map(object, Json::Object),
+    ))(i)
+}
+
+fn array(i: &str) -> IResult<&str, Vec<Json>> {
+    delimited(
+        char('['),
+        separated_list0(char(','), whitespace(json)),
+        preceded(multispace0, char(']')),
+    )(i)
+}
+
+fn object(i: &str) -> IResult<&str, BTreeMap<String, Json>> {
+    map(
+        delimited(
+            char('{'),
+            separated_list0(char(','), member),
+            preceded(multispace0, char('}')),
+        ),
+        |members| members.into_iter().collect(),
+    )(i)
+}
+
+fn member(i: &str) -> IResult<&str, (String, Json)> {
+    separated_pair(whitespace(string), char(':'), whitespace(json))(i)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum StringFragment<'a> {
+    Literal(&'a str),
+    Escaped(char),
+}
+
+fn string(i: &str) -> IResult<&str, String> {
+    delimited(
+        char('"'),
+        fold_many0(string_fragment, String::new, |mut s, fragment| {
+            match fragment {
+                StringFragment::Literal(literal) => s.push_str(literal),
+                StringFragment::Escaped(c) => s.push(c),
+            }
+            s
+        }),
+        char('"'),
+    )(i)
+}
+
+fn string_fragment(i: &str) -> IResult<&str, StringFragment<'_>> {
+    alt((
+        map(is_not("\"\\"), StringFragment::Literal),
+        map(preceded(char('\\'), escaped_char), StringFragment::Escaped),
+    ))(i)
+}
+
+fn escaped_char(i: &str) -> IResult<&str, char> {
+    alt((
+        map_opt(
+            preceded(
+                char('u'),
+                take_while_m_n(4, 4, |c: char| c.is_ascii_hexdigit()),
+            ),
+            |hex| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+        ),
+        value('"', char('"')),
+        value('\\', char('\\')),
+        value('/', char('/')),
+        value('\n', char('n')),
+        value('\r', char('r')),
+        value('\t', char('t')),
+        value('\u{08}', char('b')),
+        value('\u{0c}', char('f')),
+    ))(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scalars() {
+        assert_eq!("null".parse(), Ok(Json::Null));
+        assert_eq!("true".parse(), Ok(Json::Bool(true)));
+        assert_eq!(" false ".parse(), Ok(Json::Bool(false)));
+        assert_eq!("-42".parse(), Ok(Json::Number(-42)));
+        assert_eq!(
+            r#""a\"b\\c\né""#.parse(),
+            Ok(Json::String("a\"b\\c\né".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_containers() {
+        assert_eq!("[]".parse(), Ok(Json::Array(vec![])));
+        assert_eq!("[ ]".parse(), Ok(Json::Array(vec![])));
+        assert_eq!(
+            "[1, [2], []]".parse(),
+            Ok(Json::Array(vec![
+                Json::Number(1),
+                Json::Array(vec![Json::Number(2)]),
+                Json::Array(vec![]),
+            ]))
+        );
+
+        let object: Json = r#"{ "b": [true], "a": null }"#.parse().unwrap();
+        let expected = vec![
+            ("a".to_string(), Json::Null),
+            ("b".to_string(), Json::Array(vec![Json::Bool(true)])),
+        ];
+
+        assert_eq!(object, Json::Object(expected.into_iter().collect()));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("[1,]".parse::<Json>().is_err());
+        assert!("{\"a\"}".parse::<Json>().is_err());
+        assert!("\"unterminated".parse::<Json>().is_err());
+        assert!("1.5".parse::<Json>().is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let input = r#"{"a":[1,-2,"three\n",null],"b":{"c":false},"d":"\u0001"}"#;
+        let json: Json = input.parse().unwrap();
+
+        assert_eq!(json.to_string(), input);
+        assert_eq!(json.to_string().parse(), Ok(json));
+    }
+}
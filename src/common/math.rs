@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::ops::{Add, Div};
 
 use log::trace;
@@ -23,7 +24,259 @@ pub fn lcm<I: Into<i128>>(a: I, b: I) -> i128 {
 
     let gcd = gcd(a, b);
 
-    (a * b) / gcd
+    (a / gcd) * b
+}
+
+/// The same as `lcm`, but `None` if the result doesn't fit in an `i128`
+pub fn checked_lcm<I: Into<i128>>(a: I, b: I) -> Option<i128> {
+    let a = a.into();
+    let b = b.into();
+
+    if a == 0 && b == 0 {
+        return Some(0);
+    }
+
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g`, where `g` is the (non-negative) gcd of `a` and `b`
+pub fn extended_gcd<I: Into<i128>>(a: I, b: I) -> (i128, i128, i128) {
+    let mut old_r = a.into();
+    let mut r = b.into();
+    let mut old_s = 1;
+    let mut s = 0;
+    let mut old_t = 0;
+    let mut t = 1;
+
+    while r != 0 {
+        let quotient = old_r / r;
+
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+        (old_t, t) = (t, old_t - quotient * t);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_s, -old_t)
+    } else {
+        (old_r, old_s, old_t)
+    }
+}
+
+/// Adds two residues without overflowing, even when the modulus is close to `i128::MAX`
+fn add_mod(a: i128, b: i128, modulus: i128) -> i128 {
+    if a >= modulus - b {
+        a - (modulus - b)
+    } else {
+        a + b
+    }
+}
+
+/// Multiplies two numbers modulo `modulus` without overflowing, or `None` if `modulus` isn't positive
+pub fn mul_mod<I: Into<i128>>(a: I, b: I, modulus: I) -> Option<i128> {
+    let modulus = modulus.into();
+
+    if modulus <= 0 {
+        return None;
+    }
+
+    Some(mul_reduced(
+        a.into().rem_euclid(modulus),
+        b.into().rem_euclid(modulus),
+        modulus,
+    ))
+}
+
+/// `mul_mod` for residues that are already in `0..modulus`
+fn mul_reduced(a: i128, b: i128, modulus: i128) -> i128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product % modulus;
+    }
+
+    // fall back to double-and-add when the product doesn't fit
+    let mut result = 0;
+    let mut a = a;
+    let mut b = b;
+
+    while b > 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a, modulus);
+        }
+
+        a = add_mod(a, a, modulus);
+        b >>= 1;
+    }
+
+    result
+}
+
+/**
+ * Computes `base ^ exponent (mod modulus)` by repeated squaring
+ *
+ * A negative exponent raises the inverse of `base` instead. Returns `None` if `modulus` isn't
+ * positive, or if the exponent is negative and `base` has no inverse.
+ */
+pub fn mod_pow<I: Into<i128>>(base: I, exponent: I, modulus: I) -> Option<i128> {
+    let modulus = modulus.into();
+    let exponent = exponent.into();
+
+    if modulus <= 0 {
+        return None;
+    }
+
+    let base = if exponent < 0 {
+        mod_inverse(base.into(), modulus)?
+    } else {
+        base.into().rem_euclid(modulus)
+    };
+
+    Some(pow_reduced(base, exponent.unsigned_abs(), modulus))
+}
+
+/// `mod_pow` for a base that's already in `0..modulus`
+fn pow_reduced(base: i128, exponent: u128, modulus: i128) -> i128 {
+    let mut base = base;
+    let mut exponent = exponent;
+    let mut result = 1 % modulus;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_reduced(result, base, modulus);
+        }
+
+        base = mul_reduced(base, base, modulus);
+        exponent >>= 1;
+    }
+
+    result
+}
+
+/**
+ * Returns `x` such that `a * x == 1 (mod modulus)`, if `a` and `modulus` are coprime and `modulus`
+ * is positive
+ */
+pub fn mod_inverse<I: Into<i128>>(a: I, modulus: I) -> Option<i128> {
+    let modulus = modulus.into();
+
+    if modulus <= 0 {
+        return None;
+    }
+
+    let (g, x, _) = extended_gcd(a.into().rem_euclid(modulus), modulus);
+
+    if g == 1 {
+        Some(x.rem_euclid(modulus))
+    } else {
+        None
+    }
+}
+
+/**
+ * Solves a system of congruences `x == residue (mod modulus)`, where the moduli need not be coprime
+ *
+ * Returns `(x, lcm)` with `0 <= x < lcm`, where `lcm` is the least common multiple of the moduli;
+ * every solution is `x` plus a multiple of `lcm`. Returns `None` if the congruences are
+ * inconsistent, if a modulus isn't positive or if `lcm` doesn't fit in an `i128`.
+ */
+pub fn chinese_remainder<I: Into<i128> + Copy>(congruences: &[(I, I)]) -> Option<(i128, i128)> {
+    let mut x: i128 = 0;
+    let mut current_modulus: i128 = 1;
+
+    for (residue, modulus) in congruences {
+        let modulus: i128 = (*modulus).into();
+
+        if modulus <= 0 {
+            return None;
+        }
+
+        let residue = (*residue).into().rem_euclid(modulus);
+
+        let g = gcd(current_modulus, modulus);
+        let difference = residue - x;
+
+        if difference % g != 0 {
+            return None;
+        }
+
+        let reduced_modulus = modulus / g;
+        let combined_modulus = (current_modulus / g).checked_mul(modulus)?;
+
+        // current_modulus * t == difference (mod modulus), reduced by g
+        let inverse = mod_inverse(current_modulus / g, reduced_modulus)?;
+        let t = mul_mod(difference / g, inverse, reduced_modulus)?;
+
+        x = add_mod(
+            x,
+            mul_reduced(current_modulus, t, combined_modulus),
+            combined_modulus,
+        );
+        current_modulus = combined_modulus;
+    }
+
+    Some((x, current_modulus))
+}
+
+/**
+ * Finds the smallest `x >= 0` such that `base ^ x == target (mod modulus)` using baby-step giant-step
+ *
+ * Apart from `target == 1`, which is always reached at `x == 0`, `base` must be coprime to
+ * `modulus` or this gives `None`, as does a modulus that isn't positive. Runs in O(sqrt(modulus))
+ * time and space.
+ */
+pub fn discrete_log<I: Into<i128>>(base: I, target: I, modulus: I) -> Option<i128> {
+    let modulus = modulus.into();
+
+    if modulus <= 0 {
+        return None;
+    }
+
+    let base = base.into().rem_euclid(modulus);
+    let target = target.into().rem_euclid(modulus);
+
+    if modulus == 1 || target == 1 {
+        return Some(0);
+    }
+
+    let step = integer_sqrt(modulus - 1) + 1;
+
+    let mut baby_steps = HashMap::new();
+    let mut current = 1;
+
+    for j in 0..step {
+        baby_steps.entry(current).or_insert(j);
+        current = mul_reduced(current, base, modulus);
+    }
+
+    let giant_step = mod_pow(base, -step, modulus)?;
+    let mut gamma = target;
+
+    for i in 0..step {
+        if let Some(j) = baby_steps.get(&gamma) {
+            return Some(i * step + j);
+        }
+
+        gamma = mul_reduced(gamma, giant_step, modulus);
+    }
+
+    None
+}
+
+fn integer_sqrt(n: i128) -> i128 {
+    if n < 2 {
+        return n;
+    }
+
+    let mut x = (n as f64).sqrt() as i128;
+
+    while x * x > n {
+        x -= 1;
+    }
+
+    while (x + 1) * (x + 1) <= n {
+        x += 1;
+    }
+
+    x
 }
 
 pub fn median<I>(numbers: &[I]) -> f64
@@ -81,6 +334,96 @@ mod tests {
     fn test_lcm() {
         assert_eq!(lcm(21, 6), 42);
         assert_eq!(lcm(lcm(8, 9), 21), 504);
+
+        assert_eq!(checked_lcm(21, 6), Some(42));
+        assert_eq!(checked_lcm(i128::MAX, 2), None);
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        assert_eq!(extended_gcd(3, 4), (1, -1, 1));
+        assert_eq!(extended_gcd(5, 12), (1, 5, -2));
+        assert_eq!(extended_gcd(240, 46), (2, -9, 47));
+        assert_eq!(extended_gcd(-4, 6), (2, 1, 1));
+    }
+
+    #[test]
+    fn test_mul_mod() {
+        assert_eq!(mul_mod(7, 8, 5), Some(1));
+        assert_eq!(mul_mod(-3, 4, 5), Some(3));
+        assert_eq!(mul_mod(7, 8, 0), None);
+
+        let modulus = i128::MAX - 1;
+        assert_eq!(mul_mod(modulus - 1, modulus - 1, modulus), Some(1));
+        assert_eq!(mul_mod(modulus - 1, 2, modulus), Some(modulus - 2));
+    }
+
+    #[test]
+    fn test_mod_pow() {
+        assert_eq!(mod_pow(4, 13, 497), Some(445));
+        assert_eq!(mod_pow(7, 0, 1), Some(0));
+        assert_eq!(mod_pow(-2, 3, 5), Some(2));
+        assert_eq!(
+            mod_pow(3_i64, 1_000_000_000_000, 1_000_000_007),
+            Some(570_188_345)
+        );
+
+        // 2^127 - 1 is prime, so Fermat's little theorem applies
+        let prime = i128::MAX;
+        assert_eq!(mod_pow(123_456_789, prime - 1, prime), Some(1));
+
+        // negative exponents raise the inverse, and the inverse of 3 (mod 11) is 4
+        assert_eq!(mod_pow(3, -2, 11), Some(5));
+        assert_eq!(mod_pow(6, -1, 9), None);
+        assert_eq!(mod_pow(2, i128::MIN, 3), Some(1));
+
+        assert_eq!(mod_pow(2, 3, 0), None);
+        assert_eq!(mod_pow(2, 3, -5), None);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(10, 17), Some(12));
+        assert_eq!(mod_inverse(6, 9), None);
+        assert_eq!(mod_inverse(3, 0), None);
+    }
+
+    #[test]
+    fn test_chinese_remainder() {
+        assert_eq!(
+            chinese_remainder(&[(2, 3), (3, 5), (2, 7)]),
+            Some((23, 105))
+        );
+        assert_eq!(
+            chinese_remainder(&[(0, 17), (-2, 13), (-3, 19)]),
+            Some((3417, 4199))
+        );
+
+        // moduli that share factors
+        assert_eq!(chinese_remainder(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(chinese_remainder(&[(1, 4), (2, 6)]), None);
+
+        assert_eq!(chinese_remainder::<i128>(&[]), Some((0, 1)));
+        assert_eq!(chinese_remainder(&[(1, i128::MAX), (0, 2), (0, 3)]), None);
+        assert_eq!(chinese_remainder(&[(2, 3), (1, 0)]), None);
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        assert_eq!(discrete_log(7, 5764801, 20201227), Some(8));
+        assert_eq!(discrete_log(7, 17807724, 20201227), Some(11));
+        assert_eq!(discrete_log(2, 1, 11), Some(0));
+        assert_eq!(discrete_log(3, 13, 17), Some(4));
+
+        // 2 only generates the quadratic residues mod 7
+        assert_eq!(discrete_log(2, 3, 7), None);
+
+        // 1 needs no steps, even when the base isn't coprime to the modulus
+        assert_eq!(discrete_log(2, 1, 4), Some(0));
+
+        assert_eq!(discrete_log(2, 1, 0), None);
     }
 
     #[test]
@@ -0,0 +1,102 @@
+/// The capital letters drawn on six pixel high screens, one string per glyph with rows joined by `|`
+const GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.|#..#|#..#|####|#..#|#..#"),
+    ('B', "###.|#..#|###.|#..#|#..#|###."),
+    ('C', ".##.|#..#|#...|#...|#..#|.##."),
+    ('E', "####|#...|###.|#...|#...|####"),
+    ('F', "####|#...|###.|#...|#...|#..."),
+    ('G', ".##.|#..#|#...|#.##|#..#|.###"),
+    ('H', "#..#|#..#|####|#..#|#..#|#..#"),
+    ('I', "###|.#.|.#.|.#.|.#.|###"),
+    ('J', "..##|...#|...#|...#|#..#|.##."),
+    ('K', "#..#|#.#.|##..|#.#.|#.#.|#..#"),
+    ('L', "#...|#...|#...|#...|#...|####"),
+    ('O', ".##.|#..#|#..#|#..#|#..#|.##."),
+    ('P', "###.|#..#|#..#|###.|#...|#..."),
+    ('R', "###.|#..#|#..#|###.|#.#.|#..#"),
+    ('S', ".###|#...|#...|.##.|...#|###."),
+    ('U', "#..#|#..#|#..#|#..#|#..#|.##."),
+    ('Y', "#...#|#...#|.#.#.|..#..|..#..|..#.."),
+    ('Z', "####|...#|..#.|.#..|#...|####"),
+];
+
+pub const GLYPH_HEIGHT: usize = 6;
+
+/**
+ * Reads the letters off a screen of lit pixels, where letters are separated by columns with
+ * nothing lit
+ *
+ * `None` if the screen isn't six pixels high or holds something that isn't a known letter.
+ */
+pub fn decode(rows: &[Vec<bool>]) -> Option<String> {
+    if rows.len() != GLYPH_HEIGHT {
+        return None;
+    }
+
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    let is_lit = |row: usize, column: usize| rows[row].get(column).copied().unwrap_or(false);
+
+    let mut text = String::new();
+    let mut column = 0;
+
+    while column < width {
+        if (0..GLYPH_HEIGHT).all(|row| !is_lit(row, column)) {
+            column += 1;
+            continue;
+        }
+
+        let start = column;
+
+        while column < width && (0..GLYPH_HEIGHT).any(|row| is_lit(row, column)) {
+            column += 1;
+        }
+
+        let glyph: Vec<String> = (0..GLYPH_HEIGHT)
+            .map(|row| {
+                (start..column)
+                    .map(|column| if is_lit(row, column) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        let glyph = glyph.join("|");
+
+        let (letter, _) = GLYPHS.iter().find(|(_, pattern)| *pattern == glyph)?;
+
+        text.push(*letter);
+    }
+
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(drawing: &str) -> Vec<Vec<bool>> {
+        drawing
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_decode() {
+        let drawing = "\
+#..#.###...##..#...#
+#..#..#...#..#.#...#
+####..#...#..#..#.#.
+#..#..#...#..#...#..
+#..#..#...#..#...#..
+#..#.###...##....#..";
+
+        assert_eq!(decode(&screen(drawing)), Some("HIOY".to_string()));
+    }
+
+    #[test]
+    fn test_unknown() {
+        let drawing = "#\n#\n#\n#\n#\n#";
+
+        assert_eq!(decode(&screen(drawing)), None);
+        assert_eq!(decode(&screen("####")), None);
+    }
+}
@@ -1,51 +1,188 @@
-use std::process::Command;
-use std::{fs::File, io::Write, path::Path};
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::{fs::File, io, io::BufWriter, io::Write, path::Path};
 
-use log::warn;
+use log::{info, warn};
 
-pub fn write_dot(path: &str, dot: &str) {
-    let output_directory = Path::new("../output");
+use crate::common::animation::Frame;
+use crate::common::gif::GifEncoder;
+use crate::common::graph::Graph;
 
-    let dot_path = Path::new(path);
-    let dot_path = output_directory.join(dot_path);
+const GIF_SCALE: usize = 4;
 
-    let png_path = dot_path.with_extension("png");
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationMode {
+    Terminal,
+    TextFrames,
+    Gif,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputSettings {
+    visualize: bool,
+    directory: PathBuf,
+    animation: Option<AnimationMode>,
+    frames_per_second: u32,
+}
+
+impl OutputSettings {
+    pub fn new<P: Into<PathBuf>>(visualize: bool, directory: P) -> OutputSettings {
+        OutputSettings {
+            visualize,
+            directory: directory.into(),
+            animation: None,
+            frames_per_second: 10,
+        }
+    }
+
+    pub fn with_animation(
+        self,
+        animation: Option<AnimationMode>,
+        frames_per_second: u32,
+    ) -> OutputSettings {
+        OutputSettings {
+            animation,
+            frames_per_second: frames_per_second.max(1),
+            ..self
+        }
+    }
+
+    pub fn is_visualizing(&self) -> bool {
+        self.visualize
+    }
+
+    /// Writes `<name>.dot` and `<name>.svg` to the output directory, if visualization is enabled
+    pub fn write_graph(&self, name: &str, graph: &Graph) {
+        if !self.visualize {
+            return;
+        }
+
+        let base_path = self.directory.join(name);
 
-    let was_written = write_internal(dot_path.as_path(), dot);
-    if !was_written {
-        return;
+        for (extension, contents) in [("dot", graph.to_dot()), ("svg", graph.to_svg())] {
+            let path = base_path.with_extension(extension);
+
+            match write_internal(&path, &contents) {
+                Ok(()) => info!("Wrote {}", path.display()),
+                Err(e) => warn!("Could not write {} - {}", path.display(), e),
+            }
+        }
+    }
+
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
     }
 
-    if let Err(process_output) = Command::new("neato")
-        .arg("-Tpng")
-        .arg(dot_path.as_path())
-        .arg("-o")
-        .arg(png_path.as_path())
-        .output()
+    /// Plays or saves the frames of a simulation, depending on the configured animation mode
+    pub fn write_animation<I>(&self, name: &str, frames: I)
+    where
+        I: IntoIterator<Item = Frame>,
     {
-        warn!("Could not run neato for path {} - {}", path, process_output);
+        let result = match self.animation {
+            None => return,
+            Some(AnimationMode::Terminal) => play_in_terminal(frames, self.frames_per_second),
+            Some(AnimationMode::TextFrames) => {
+                write_text_frames(&self.directory.join(name), frames)
+            }
+            Some(AnimationMode::Gif) => write_gif(
+                &self.directory.join(name).with_extension("gif"),
+                frames,
+                self.frames_per_second,
+            ),
+        };
+
+        if let Err(e) = result {
+            warn!("Could not write animation {} - {}", name, e);
+        }
     }
 }
 
+impl Default for OutputSettings {
+    fn default() -> OutputSettings {
+        OutputSettings::new(false, "../output")
+    }
+}
+
+fn play_in_terminal<I>(frames: I, frames_per_second: u32) -> io::Result<()>
+where
+    I: IntoIterator<Item = Frame>,
+{
+    let delay = Duration::from_secs(1) / frames_per_second;
+    let stdout = io::stdout();
+
+    for frame in frames {
+        let mut handle = stdout.lock();
+        // clear the screen and move the cursor to the top-left
+        writeln!(handle, "\x1b[2J\x1b[H{}", frame)?;
+        handle.flush()?;
+
+        thread::sleep(delay);
+    }
+
+    Ok(())
+}
+
+fn write_text_frames<I>(directory: &Path, frames: I) -> io::Result<()>
+where
+    I: IntoIterator<Item = Frame>,
+{
+    let mut count = 0;
+
+    for (index, frame) in frames.into_iter().enumerate() {
+        let path = directory.join(format!("frame-{:05}.txt", index));
+        write_internal(&path, &frame.to_string())?;
+        count += 1;
+    }
+
+    info!("Wrote {} frames to {}", count, directory.display());
+
+    Ok(())
+}
+
+fn write_gif<I>(path: &Path, frames: I, frames_per_second: u32) -> io::Result<()>
+where
+    I: IntoIterator<Item = Frame>,
+{
+    let mut frames = frames.into_iter().peekable();
+
+    // the first frame decides the size of the canvas
+    let (columns, rows) = match frames.peek() {
+        Some(frame) => (frame.width(), frame.height()),
+        None => return Ok(()),
+    };
+
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
+    }
+
+    let delay_centiseconds = (100 / frames_per_second).max(1) as u16;
+    let writer = BufWriter::new(File::create(path)?);
+
+    let mut encoder = GifEncoder::new(writer, columns, rows, GIF_SCALE, delay_centiseconds)?;
+
+    for frame in frames {
+        encoder.add_frame(&frame)?;
+    }
+
+    encoder.finish()?;
+
+    info!("Wrote {}", path.display());
+
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub fn write_output(path: &str, out: &str) -> bool {
     let path = Path::new(path);
 
-    return write_internal(path, out);
+    write_internal(path, out).is_ok()
 }
 
-// todo(@nhirakawa) - this is horrendously un-idiomatic - fix this
-fn write_internal(path: &Path, out: &str) -> bool {
-    let directory = path.parent().unwrap();
-    if let Err(e) = std::fs::create_dir_all(directory) {
-        warn!("Could not create output directory - {}", e);
-        return false;
+fn write_internal(path: &Path, out: &str) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)?;
     }
 
-    if let Ok(mut file) = File::create(path) {
-        return file.write_all(out.as_bytes()).is_ok();
-    } else {
-        warn!("Could not create file {:?}", path);
-        return false;
-    }
+    File::create(path)?.write_all(out.as_bytes())
 }
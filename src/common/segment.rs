@@ -0,0 +1,265 @@
+use crate::common::geometry::Vector2;
+use std::collections::HashMap;
+
+/// A straight run of grid points along a row or a column, from `start` to `end` inclusive
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub struct Segment {
+    start: Vector2,
+    end: Vector2,
+}
+
+impl Segment {
+    pub fn new(start: Vector2, end: Vector2) -> Segment {
+        assert!(
+            start.x == end.x || start.y == end.y,
+            "segment from {} to {} is not along a row or a column",
+            start,
+            end
+        );
+
+        Segment { start, end }
+    }
+
+    /// The number of steps from one end to the other
+    pub fn len(&self) -> i64 {
+        self.start.manhattan_distance(&self.end)
+    }
+
+    pub fn contains(&self, point: &Vector2) -> bool {
+        let (low, high) = self.bounds();
+
+        low.x <= point.x && point.x <= high.x && low.y <= point.y && point.y <= high.y
+    }
+
+    /// The number of steps from the start to a point on the segment
+    pub fn steps_to(&self, point: &Vector2) -> Option<i64> {
+        if self.contains(point) {
+            Some(self.start.manhattan_distance(point))
+        } else {
+            None
+        }
+    }
+
+    /**
+     * Every grid point on both segments, nearest this one's start first
+     *
+     * Perpendicular segments cross at one point at most, but segments along the same line can
+     * overlap for any number of points.
+     */
+    pub fn intersections(&self, other: &Segment) -> Vec<Vector2> {
+        let (low, high) = self.bounds();
+        let (other_low, other_high) = other.bounds();
+
+        let low = Vector2::new(low.x.max(other_low.x), low.y.max(other_low.y));
+        let high = Vector2::new(high.x.min(other_high.x), high.y.min(other_high.y));
+
+        let mut points: Vec<Vector2> = (low.x..=high.x)
+            .flat_map(|x| (low.y..=high.y).map(move |y| Vector2::new(x, y)))
+            .collect();
+
+        points.sort_by_key(|point| self.start.manhattan_distance(point));
+
+        points
+    }
+
+    fn bounds(&self) -> (Vector2, Vector2) {
+        (
+            Vector2::new(self.start.x.min(self.end.x), self.start.y.min(self.end.y)),
+            Vector2::new(self.start.x.max(self.end.x), self.start.y.max(self.end.y)),
+        )
+    }
+}
+
+/**
+ * A walk around a grid in straight lines, kept as the segments walked rather than every point
+ * passed through
+ */
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Path {
+    segments: Vec<Segment>,
+    position: Vector2,
+}
+
+impl Path {
+    pub fn new(start: Vector2) -> Path {
+        Path {
+            segments: Vec::new(),
+            position: start,
+        }
+    }
+
+    /**
+     * Walks along a row or a column by `offset`
+     *
+     * Standing still doesn't add a segment, as one that starts where it ends would hide a revisit
+     * of that point from itself and from the segment after it.
+     */
+    pub fn walk(&mut self, offset: Vector2) {
+        if offset == Vector2::ZERO {
+            return;
+        }
+
+        let end = self.position + offset;
+
+        self.segments.push(Segment::new(self.position, end));
+        self.position = end;
+    }
+
+    pub fn position(&self) -> Vector2 {
+        self.position
+    }
+
+    /**
+     * The first point the path comes back to, whether that's at a turn or partway along a segment
+     *
+     * The point where a segment starts is where the one before it ended, so it only counts as a
+     * revisit for the earlier segment.
+     */
+    pub fn first_revisit(&self) -> Option<Vector2> {
+        for (index, segment) in self.segments.iter().enumerate() {
+            let revisit = self.segments[..index]
+                .iter()
+                .flat_map(|earlier| segment.intersections(earlier))
+                .filter(|point| *point != segment.start)
+                .min_by_key(|point| segment.start.manhattan_distance(point));
+
+            if revisit.is_some() {
+                return revisit;
+            }
+        }
+
+        None
+    }
+
+    /// Every point both paths pass through, with the fewest steps each path takes to get there
+    pub fn crossings(&self, other: &Path) -> HashMap<Vector2, (i64, i64)> {
+        let mut crossings: HashMap<Vector2, (i64, i64)> = HashMap::new();
+
+        for (segment, steps_before) in self.segments_with_steps() {
+            for (other_segment, other_steps_before) in other.segments_with_steps() {
+                for point in segment.intersections(&other_segment) {
+                    let steps = steps_before + segment.steps_to(&point).unwrap();
+                    let other_steps = other_steps_before + other_segment.steps_to(&point).unwrap();
+
+                    crossings
+                        .entry(point)
+                        .and_modify(|(fewest, other_fewest)| {
+                            *fewest = (*fewest).min(steps);
+                            *other_fewest = (*other_fewest).min(other_steps);
+                        })
+                        .or_insert((steps, other_steps));
+                }
+            }
+        }
+
+        crossings
+    }
+
+    /// Each segment along with the number of steps taken before it starts
+    fn segments_with_steps(&self) -> impl Iterator<Item = (Segment, i64)> + '_ {
+        self.segments.iter().scan(0, |steps, segment| {
+            let steps_before = *steps;
+            *steps += segment.len();
+
+            Some((*segment, steps_before))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: (i64, i64), end: (i64, i64)) -> Segment {
+        Segment::new(start.into(), end.into())
+    }
+
+    #[test]
+    fn test_intersections() {
+        let horizontal = segment((-2, 1), (3, 1));
+
+        assert_eq!(
+            horizontal.intersections(&segment((0, 4), (0, -4))),
+            vec![Vector2::new(0, 1)]
+        );
+        assert_eq!(horizontal.intersections(&segment((0, 4), (0, 2))), vec![]);
+        assert_eq!(horizontal.intersections(&segment((5, 1), (9, 1))), vec![]);
+        assert_eq!(
+            segment((3, 1), (-2, 1)).intersections(&segment((-5, 1), (1, 1))),
+            vec![
+                Vector2::new(1, 1),
+                Vector2::new(0, 1),
+                Vector2::new(-1, 1),
+                Vector2::new(-2, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_first_revisit() {
+        let mut path = Path::new(Vector2::ZERO);
+
+        for offset in &[(8, 0), (0, -4), (-4, 0), (0, 8)] {
+            path.walk((*offset).into());
+        }
+
+        assert_eq!(path.first_revisit(), Some(Vector2::new(4, 0)));
+
+        let mut path = Path::new(Vector2::ZERO);
+
+        for offset in &[(3, 0), (0, 2), (0, -1)] {
+            path.walk((*offset).into());
+        }
+
+        assert_eq!(path.first_revisit(), Some(Vector2::new(3, 1)));
+
+        let mut path = Path::new(Vector2::ZERO);
+
+        for offset in &[(3, 0), (0, 2), (-3, 0)] {
+            path.walk((*offset).into());
+        }
+
+        assert_eq!(path.first_revisit(), None);
+    }
+
+    #[test]
+    fn test_standing_still() {
+        let mut path = Path::new(Vector2::ZERO);
+        let mut still = Path::new(Vector2::ZERO);
+
+        for offset in &[(0, 0), (8, 0), (0, 0), (0, -4), (-4, 0), (0, 0), (0, 8)] {
+            still.walk((*offset).into());
+
+            if *offset != (0, 0) {
+                path.walk((*offset).into());
+            }
+        }
+
+        assert_eq!(still, path);
+        assert_eq!(still.first_revisit(), Some(Vector2::new(4, 0)));
+        assert_eq!(still.position(), Vector2::new(4, 4));
+    }
+
+    #[test]
+    fn test_crossings() {
+        let walk = |offsets: &[(i64, i64)]| {
+            let mut path = Path::new(Vector2::ZERO);
+
+            for offset in offsets {
+                path.walk((*offset).into());
+            }
+
+            path
+        };
+
+        let first = walk(&[(8, 0), (0, 5), (-5, 0), (0, -3)]);
+        let second = walk(&[(0, 7), (6, 0), (0, -4), (-4, 0)]);
+
+        let crossings = first.crossings(&second);
+
+        assert_eq!(crossings.len(), 3);
+        assert_eq!(crossings[&Vector2::ZERO], (0, 0));
+        assert_eq!(crossings[&Vector2::new(3, 3)], (20, 20));
+        assert_eq!(crossings[&Vector2::new(6, 5)], (15, 15));
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt::Debug;
+
+/// Where the program counter goes after an instruction
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Step {
+    Next,
+    Jump(i64),
+}
+
+/**
+ * The opcodes a machine understands and what each of them does to the registers
+ */
+pub trait InstructionSet {
+    type Instruction: Copy + Debug;
+
+    const REGISTERS: usize;
+
+    fn execute(&self, instruction: &Self::Instruction, registers: &mut [i64]) -> Step;
+
+    /**
+     * Where control goes after the instruction when that can be known without running it, which
+     * is what the static analysis works from. Sets with jumps that depend on the registers leave
+     * this as `None`.
+     */
+    fn successor(&self, _instruction: &Self::Instruction) -> Option<Step> {
+        None
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Outcome {
+    /// The program counter moved to just past the last instruction
+    Terminated,
+    /// The instruction at this address was about to run for a second time
+    Looped {
+        address: usize,
+    },
+    /// The program counter jumped somewhere other than an instruction or the end
+    OutOfBounds {
+        address: i64,
+    },
+    StepLimitReached,
+}
+
+pub struct Machine<S: InstructionSet> {
+    instruction_set: S,
+    program: Vec<S::Instruction>,
+    program_counter: usize,
+    registers: Vec<i64>,
+    steps: usize,
+    step_limit: Option<usize>,
+    detect_loops: bool,
+}
+
+impl<S: InstructionSet> Machine<S> {
+    pub fn new(instruction_set: S, program: Vec<S::Instruction>) -> Machine<S> {
+        Machine {
+            instruction_set,
+            program,
+            program_counter: 0,
+            registers: vec![0; S::REGISTERS],
+            steps: 0,
+            step_limit: None,
+            detect_loops: false,
+        }
+    }
+
+    /// Stops once `step_limit` instructions have run, if the program hasn't finished by then
+    pub fn with_step_limit(mut self, step_limit: usize) -> Machine<S> {
+        self.step_limit = Some(step_limit);
+        self
+    }
+
+    /**
+     * Stops the first time an instruction is about to run again. That only means the program
+     * never finishes when control flow doesn't depend on the registers.
+     */
+    pub fn with_loop_detection(mut self) -> Machine<S> {
+        self.detect_loops = true;
+        self
+    }
+
+    pub fn registers(&self) -> &[i64] {
+        &self.registers
+    }
+
+    /// The number of instructions run so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn run(&mut self) -> Outcome {
+        let mut seen = HashSet::new();
+
+        loop {
+            if self.program_counter == self.program.len() {
+                return Outcome::Terminated;
+            }
+
+            if self.detect_loops && !seen.insert(self.program_counter) {
+                return Outcome::Looped {
+                    address: self.program_counter,
+                };
+            }
+
+            if self.step_limit == Some(self.steps) {
+                return Outcome::StepLimitReached;
+            }
+
+            let instruction = self.program[self.program_counter];
+            self.steps += 1;
+
+            let step = self
+                .instruction_set
+                .execute(&instruction, &mut self.registers);
+
+            let next = match step {
+                Step::Next => self.program_counter as i64 + 1,
+                Step::Jump(offset) => self.program_counter as i64 + offset,
+            };
+
+            if next < 0 || next > self.program.len() as i64 {
+                return Outcome::OutOfBounds { address: next };
+            }
+
+            self.program_counter = next as usize;
+        }
+    }
+}
+
+/**
+ * The addresses from which the program is bound to finish, found by working backwards from the
+ * end
+ *
+ * `None` if some instruction's successor can't be known without running it.
+ */
+pub fn finishing_addresses<S: InstructionSet>(
+    instruction_set: &S,
+    program: &[S::Instruction],
+) -> Option<HashSet<usize>> {
+    let end = program.len();
+    let mut predecessors = vec![Vec::new(); end + 1];
+    let mut queue = VecDeque::new();
+
+    queue.push_back(end);
+
+    for (address, instruction) in program.iter().enumerate() {
+        match flow(instruction_set, program, instruction, address)? {
+            Flow::To(next) => predecessors[next].push(address),
+            Flow::Leaves => {}
+        }
+    }
+
+    let mut finishing = HashSet::new();
+
+    while let Some(address) = queue.pop_front() {
+        if finishing.insert(address) {
+            queue.extend(predecessors[address].iter().copied());
+        }
+    }
+
+    Some(finishing)
+}
+
+/**
+ * Finds the one instruction that has to be swapped for an alternative to make the program finish
+ *
+ * The swapped instruction must be one that the program actually runs before looping, and the
+ * instruction it leads to must already be bound to finish. Nothing bound to finish can lead back
+ * through the swapped instruction, or the unswapped program would have finished as well.
+ */
+pub fn find_repair<S, F>(
+    instruction_set: &S,
+    program: &[S::Instruction],
+    alternative: F,
+) -> Option<(usize, S::Instruction)>
+where
+    S: InstructionSet,
+    F: Fn(&S::Instruction) -> Option<S::Instruction>,
+{
+    let finishing = finishing_addresses(instruction_set, program)?;
+
+    let mut visited = HashSet::new();
+    let mut address = 0;
+
+    while address < program.len() && visited.insert(address) {
+        let instruction = &program[address];
+
+        if let Some(replacement) = alternative(instruction) {
+            let repaired = match flow(instruction_set, program, &replacement, address)? {
+                Flow::To(next) => finishing.contains(&next),
+                Flow::Leaves => false,
+            };
+
+            if repaired {
+                return Some((address, replacement));
+            }
+        }
+
+        address = match flow(instruction_set, program, instruction, address)? {
+            Flow::To(next) => next,
+            Flow::Leaves => return None,
+        };
+    }
+
+    None
+}
+
+/// Where control goes from an instruction, as far as can be told without running it
+enum Flow {
+    /// To another instruction, or to the end of the program
+    To(usize),
+    /// Out of the program altogether
+    Leaves,
+}
+
+/// `None` if the instruction's successor depends on the registers
+fn flow<S: InstructionSet>(
+    instruction_set: &S,
+    program: &[S::Instruction],
+    instruction: &S::Instruction,
+    address: usize,
+) -> Option<Flow> {
+    let next = match instruction_set.successor(instruction)? {
+        Step::Next => address as i64 + 1,
+        Step::Jump(offset) => address as i64 + offset,
+    };
+
+    if next < 0 || next > program.len() as i64 {
+        Some(Flow::Leaves)
+    } else {
+        Some(Flow::To(next as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone)]
+    enum Op {
+        Add(i64),
+        Jump(i64),
+        JumpIfZero(i64),
+    }
+
+    struct Counter;
+
+    impl InstructionSet for Counter {
+        type Instruction = Op;
+
+        const REGISTERS: usize = 1;
+
+        fn execute(&self, instruction: &Op, registers: &mut [i64]) -> Step {
+            match instruction {
+                Op::Add(value) => {
+                    registers[0] += value;
+                    Step::Next
+                }
+                Op::Jump(offset) => Step::Jump(*offset),
+                Op::JumpIfZero(offset) if registers[0] == 0 => Step::Jump(*offset),
+                Op::JumpIfZero(_) => Step::Next,
+            }
+        }
+
+        fn successor(&self, instruction: &Op) -> Option<Step> {
+            match instruction {
+                Op::Add(_) => Some(Step::Next),
+                Op::Jump(offset) => Some(Step::Jump(*offset)),
+                Op::JumpIfZero(_) => None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let mut machine = Machine::new(Counter, vec![Op::Add(3), Op::Add(-1)]);
+        assert_eq!(machine.run(), Outcome::Terminated);
+        assert_eq!(machine.registers(), &[2]);
+
+        let mut machine = Machine::new(Counter, vec![Op::Add(1), Op::Jump(-2)]);
+        assert_eq!(machine.run(), Outcome::OutOfBounds { address: -1 });
+
+        let program = vec![Op::Add(1), Op::Jump(-1)];
+
+        let mut machine = Machine::new(Counter, program.clone()).with_step_limit(5);
+        assert_eq!(machine.run(), Outcome::StepLimitReached);
+        assert_eq!(machine.registers(), &[3]);
+
+        let mut machine = Machine::new(Counter, program).with_loop_detection();
+        assert_eq!(machine.run(), Outcome::Looped { address: 0 });
+        assert_eq!(machine.steps(), 2);
+    }
+
+    #[test]
+    fn test_finishing_addresses() {
+        let program = vec![
+            Op::Jump(2),
+            Op::Jump(5),
+            Op::Jump(0),
+            Op::Add(1),
+            Op::Jump(-3),
+            Op::Jump(10),
+        ];
+
+        let finishing = finishing_addresses(&Counter, &program).unwrap();
+
+        assert_eq!(finishing, vec![1, 3, 4, 6].into_iter().collect());
+
+        let program = vec![Op::JumpIfZero(2), Op::Add(1)];
+
+        assert_eq!(finishing_addresses(&Counter, &program), None);
+    }
+
+    #[test]
+    fn test_find_repair() {
+        let program = vec![
+            Op::Add(1),
+            Op::Jump(2),
+            Op::Jump(-2),
+            Op::Jump(-1),
+            Op::Add(1),
+        ];
+
+        let repair = find_repair(&Counter, &program, |op| match op {
+            Op::Jump(offset) if *offset < 0 => Some(Op::Jump(1)),
+            _ => None,
+        });
+
+        assert!(matches!(repair, Some((3, Op::Jump(1)))));
+    }
+}
@@ -12,6 +12,7 @@ mod year_2022;
 
 use clap::{App, Arg};
 use common::answer::AdventOfCodeError;
+use common::output::{AnimationMode, OutputSettings};
 use env_logger::Env;
 
 fn main() -> Result<(), AdventOfCodeError> {
@@ -35,15 +36,58 @@ fn main() -> Result<(), AdventOfCodeError> {
                 ])
                 .requires("year"),
         )
+        .arg(
+            Arg::with_name("visualize")
+                .long("visualize")
+                .help("Writes visualizations (graphs, etc.) to the output directory"),
+        )
+        .arg(
+            Arg::with_name("output-directory")
+                .long("output-directory")
+                .takes_value(true)
+                .value_name("DIRECTORY")
+                .default_value("../output")
+                .help("Directory that visualizations are written to"),
+        )
+        .arg(
+            Arg::with_name("animate")
+                .long("animate")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["terminal", "frames", "gif"])
+                .help("Animates simulations in the terminal, as numbered text frames, or as a GIF"),
+        )
+        .arg(
+            Arg::with_name("fps")
+                .long("fps")
+                .takes_value(true)
+                .default_value("10")
+                .help("Frames per second for animations"),
+        )
         .get_matches();
 
+    let animation = match matches.value_of("animate") {
+        Some("terminal") => Some(AnimationMode::Terminal),
+        Some("frames") => Some(AnimationMode::TextFrames),
+        Some("gif") => Some(AnimationMode::Gif),
+        _ => None,
+    };
+
+    let frames_per_second = matches.value_of("fps").unwrap().parse::<u32>()?;
+
+    let output = OutputSettings::new(
+        matches.is_present("visualize"),
+        matches.value_of("output-directory").unwrap(),
+    )
+    .with_animation(animation, frames_per_second);
+
     if let Some(year) = matches.value_of("year").and_then(|s| s.parse::<u32>().ok()) {
         if let Some(day) = matches.value_of("day").and_then(|s| s.parse::<u8>().ok()) {
             match year {
-                2022 => year_2022::run_day(day)?,
+                2022 => year_2022::run_day(day, &output)?,
                 2021 => year_2021::run_day(day)?,
                 2020 => year_2020::run_day(day)?,
-                2019 => year_2019::run_day(day)?,
+                2019 => year_2019::run_day(day, &output)?,
                 2018 => year_2018::run_day(day)?,
                 2017 => year_2017::run_day(day)?,
                 2016 => year_2016::run_day(day)?,
@@ -52,10 +96,10 @@ fn main() -> Result<(), AdventOfCodeError> {
             }
         } else {
             match year {
-                2022 => year_2022::run_all()?,
+                2022 => year_2022::run_all(&output)?,
                 2021 => year_2021::run_all()?,
                 2020 => year_2020::run_all()?,
-                2019 => year_2019::run_all()?,
+                2019 => year_2019::run_all(&output)?,
                 2018 => year_2018::run_all()?,
                 2017 => year_2017::run_all()?,
                 2016 => year_2016::run_all()?,
@@ -64,10 +108,10 @@ fn main() -> Result<(), AdventOfCodeError> {
             }
         }
     } else {
-        year_2022::run_all()?;
+        year_2022::run_all(&output)?;
         year_2021::run_all()?;
         year_2020::run_all()?;
-        year_2019::run_all()?;
+        year_2019::run_all(&output)?;
         year_2018::run_all()?;
         year_2017::run_all()?;
         year_2016::run_all()?;
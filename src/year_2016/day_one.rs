@@ -1,6 +1,7 @@
-use std::collections::HashSet;
 use std::time;
 use crate::common::answer::*;
+use crate::common::geometry::Vector2;
+use crate::common::segment::Path;
 use time::SystemTime;
 use nom::{
     branch::alt, bytes::complete::tag, combinator::map, multi::separated_list1, sequence::preceded,
@@ -14,7 +15,7 @@ pub fn run() -> AdventOfCodeResult {
     let directions = parse(input);
 
     let part_one = part_one(&directions);
-    let part_two = part_two(&directions);
+    let part_two = part_two(&directions)?;
 
     Ok((part_one, part_two))
 }
@@ -22,60 +23,40 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(directions: &[Direction]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut current_direction = CardinalDirection::North;
-
-    let mut current_location: (isize, isize) = (0, 0);
-
-    for direction in directions {
-        current_direction = current_direction.apply(direction);
-
-        match current_direction {
-            CardinalDirection::North => current_location.1 += direction.value(),
-            CardinalDirection::East => current_location.0 += direction.value(),
-            CardinalDirection::South => current_location.1 -= direction.value(),
-            CardinalDirection::West => current_location.0 -= direction.value(),
-        }
-    }
-
-    let answer = current_location.0.abs() + current_location.1.abs();
+    let answer = walk(directions).position().manhattan();
 
     let elapsed = start.elapsed().unwrap();
 
     PartAnswer::new(answer, elapsed)
 }
 
-fn part_two(directions: &[Direction]) -> PartAnswer {
+fn part_two(directions: &[Direction]) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut current_direction = CardinalDirection::North;
+    let answer = walk(directions)
+        .first_revisit()
+        .map(|location| location.manhattan())
+        .ok_or_else(|| {
+            AdventOfCodeError::NoSolution("the path never visits a location twice".to_string())
+        })?;
 
-    let mut current_location: (isize, isize) = (0, 0);
+    let elapsed = start.elapsed().unwrap();
 
-    let mut seen_locations = HashSet::new();
+    Ok(PartAnswer::new(answer, elapsed))
+}
+
+/// Follows the directions from the start, facing north
+fn walk(directions: &[Direction]) -> Path {
+    let mut current_direction = CardinalDirection::North;
+    let mut path = Path::new(Vector2::ZERO);
 
     for direction in directions {
         current_direction = current_direction.apply(direction);
 
-        for _ in 0..direction.value() {
-            match current_direction {
-                CardinalDirection::North => current_location.1 += 1,
-                CardinalDirection::East => current_location.0 += 1,
-                CardinalDirection::South => current_location.1 -= 1,
-                CardinalDirection::West => current_location.0 -= 1,
-            };
-
-            if !seen_locations.insert(current_location) {
-                let answer = current_location.0.abs() + current_location.1.abs();
-
-                let elapsed = start.elapsed().unwrap();
-
-                // 310 is too high
-                return PartAnswer::new(answer, elapsed);
-            }
-        }
+        path.walk(current_direction.unit() * direction.value() as i64);
     }
 
-    PartAnswer::default()
+    path
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -102,6 +83,15 @@ enum CardinalDirection {
 }
 
 impl CardinalDirection {
+    fn unit(&self) -> Vector2 {
+        match self {
+            CardinalDirection::North => Vector2::new(0, 1),
+            CardinalDirection::East => Vector2::new(1, 0),
+            CardinalDirection::South => Vector2::new(0, -1),
+            CardinalDirection::West => Vector2::new(-1, 0),
+        }
+    }
+
     fn apply(&self, direction: &Direction) -> Self {
         match (self, *direction) {
             (CardinalDirection::North, Direction::Left(_)) => CardinalDirection::West,
@@ -135,3 +125,36 @@ fn left(i: &str) -> IResult<&str, Direction> {
 fn right(i: &str) -> IResult<&str, Direction> {
     map(preceded(tag("R"), unsigned_number), Direction::Right)(i)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_one_examples() {
+        assert_eq!(walk(&parse("R2, L3")).position().manhattan(), 5);
+        assert_eq!(walk(&parse("R2, R2, R2")).position().manhattan(), 2);
+        assert_eq!(walk(&parse("R5, L5, R5, R3")).position().manhattan(), 12);
+    }
+
+    #[test]
+    fn test_first_revisit() {
+        assert_eq!(
+            walk(&parse("R8, R4, R4, R8")).first_revisit(),
+            Some(Vector2::new(4, 0))
+        );
+        assert_eq!(walk(&parse("R2, L3")).first_revisit(), None);
+        assert_eq!(
+            walk(&parse("R8, R0, L0, R4, R4, R8")).first_revisit(),
+            Some(Vector2::new(4, 0))
+        );
+    }
+
+    #[test]
+    fn test_part_two_without_revisit() {
+        assert!(matches!(
+            part_two(&parse("R2, L3")),
+            Err(AdventOfCodeError::NoSolution(_))
+        ));
+    }
+}
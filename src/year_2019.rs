@@ -1,4 +1,4 @@
-use crate::common::{answer::AdventOfCodeError, result_logger};
+use crate::common::{answer::AdventOfCodeError, output::OutputSettings, result_logger};
 
 extern crate multimap;
 extern crate multiset;
@@ -23,15 +23,15 @@ mod day_three;
 mod day_twelve;
 mod day_two;
 
-pub fn run_all() -> Result<(), AdventOfCodeError> {
+pub fn run_all(output: &OutputSettings) -> Result<(), AdventOfCodeError> {
     for i in 1..=17 {
-        run_day(i)?;
+        run_day(i, output)?;
     }
 
     Ok(())
 }
 
-pub fn run_day(day: u8) -> Result<(), AdventOfCodeError> {
+pub fn run_day(day: u8, output: &OutputSettings) -> Result<(), AdventOfCodeError> {
     let result = match day {
         1 => day_one::run()?,
         2 => day_two::run()?,
@@ -45,11 +45,11 @@ pub fn run_day(day: u8) -> Result<(), AdventOfCodeError> {
         10 => day_ten::run()?,
         11 => day_eleven::run()?,
         12 => day_twelve::run()?,
-        13 => day_thirteen::run()?,
-        14 => day_fourteen::run()?,
+        13 => day_thirteen::run(output)?,
+        14 => day_fourteen::run(output)?,
         15 => day_fifteen::run()?,
         16 => day_sixteen::run()?,
-        17 => day_seventeen::run()?,
+        17 => day_seventeen::run(output)?,
         _ => panic!("unimplemented"),
     };
 
@@ -1,10 +1,12 @@
-use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::fmt::{Debug, Display};
-use std::ops::{Add, Mul, Sub};
+use std::fmt::Display;
+use std::iter;
+use std::ops::Mul;
 use std::time::SystemTime;
 use crate::common::parse::unsigned_number;
 use crate::common::answer::*;
+use crate::common::graph::Graph;
+use crate::common::output::OutputSettings;
 
 use log::debug;
 use nom::bytes::complete::tag;
@@ -13,300 +15,270 @@ use nom::combinator::{all_consuming, into, value};
 use nom::multi::separated_list1;
 use nom::sequence::{separated_pair, terminated};
 use nom::IResult;
+use petgraph::algo::toposort;
+use petgraph::graph::DiGraph;
 
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-14.txt");
 
     let reactions = parse_reactions(input);
 
-    let part_one = part_one(&reactions);
-    let part_two = part_two(&reactions);
+    if output.is_visualizing() {
+        output.write_graph(
+            "2019-14",
+            &Graph::from_petgraph("Reactions", &chemical_graph(&reactions)),
+        );
+    }
+
+    let part_one = part_one(&reactions)?;
+    let part_two = part_two(&reactions)?;
     Ok((part_one, part_two))
 }
 
-fn part_one(reactions: &[Reaction]) -> PartAnswer {
+const ONE_TRILLION: usize = 1_000_000_000_000;
+
+fn part_one(reactions: &[Reaction]) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut reactor = Reactor::with_initial_ore(reactions, Quantity::Unlimited);
+    let graph = ReactionGraph::new(reactions)?;
+
+    debug!("requirements for 1 FUEL:\n{}", graph.report(1));
+
+    Ok(PartAnswer::new(
+        graph.ore_for_fuel(1)?,
+        start.elapsed().unwrap(),
+    ))
+}
+
+fn part_two(reactions: &[Reaction]) -> Result<PartAnswer, AdventOfCodeError> {
+    let start = SystemTime::now();
 
-    reactor.produce_fuel(1);
+    let graph = ReactionGraph::new(reactions)?;
 
-    PartAnswer::new(reactor.used_reactants["ORE"], start.elapsed().unwrap())
+    Ok(PartAnswer::new(
+        graph.max_fuel(ONE_TRILLION)?,
+        start.elapsed().unwrap(),
+    ))
 }
 
-fn index_reactions_by_output_name(reactions: &[Reaction]) -> HashMap<String, Reaction> {
+fn index_reactions_by_output_name(
+    reactions: &[Reaction],
+) -> Result<HashMap<String, Reaction>, AdventOfCodeError> {
     let mut reactions_by_output_name = HashMap::new();
 
     for reaction in reactions {
         if reactions_by_output_name.contains_key(&reaction.output.name) {
-            panic!("index already contains {:?}", reaction);
+            return Err(AdventOfCodeError::InvalidInput(format!(
+                "more than one reaction makes {}",
+                reaction.output.name
+            )));
         }
 
         reactions_by_output_name.insert(reaction.output.name.clone(), reaction.clone());
     }
 
-    reactions_by_output_name
+    Ok(reactions_by_output_name)
 }
 
-fn part_two(reactions: &[Reaction]) -> PartAnswer {
-    let start = SystemTime::now();
-
-    let mut upper_bound = 10_000_000;
-    let mut lower_bound = 5_000_000;
+/// An edge from each reaction's output to each of its inputs, weighted by how much it consumes
+fn chemical_graph(reactions: &[Reaction]) -> DiGraph<&str, usize> {
+    let mut graph = DiGraph::new();
+    let mut nodes = HashMap::new();
 
-    loop {
-        if upper_bound - lower_bound == 1 {
-            break;
+    for reaction in reactions {
+        for chemical in reaction.inputs.iter().chain(iter::once(&reaction.output)) {
+            nodes
+                .entry(chemical.name.as_str())
+                .or_insert_with(|| graph.add_node(chemical.name.as_str()));
         }
 
-        let midpoint = ((upper_bound - lower_bound) / 2) + lower_bound;
-
-        debug!(
-            "lower: {},  mid: {}, upper: {}",
-            lower_bound, midpoint, upper_bound
-        );
-
-        let mut reactor =
-            Reactor::with_initial_ore(reactions, Quantity::Limited(1_000_000_000_000));
-
-        let was_produced_midpoint = reactor.produce_fuel(midpoint);
-
-        debug!("can produce {} fuel: {}", midpoint, was_produced_midpoint);
-
-        if was_produced_midpoint {
-            lower_bound = midpoint;
-        } else {
-            upper_bound = midpoint;
+        for input in reaction.inputs.iter() {
+            graph.add_edge(
+                nodes[reaction.output.name.as_str()],
+                nodes[input.name.as_str()],
+                input.quantity,
+            );
         }
     }
 
-    debug!("upper: {}, lower: {}", upper_bound, lower_bound);
-
-    debug!(
-        "can produce for {}: {}",
-        lower_bound,
-        can_produce_with_one_trillion_ore(reactions, lower_bound)
-    );
-    debug!(
-        "can produce for {}: {}",
-        upper_bound,
-        can_produce_with_one_trillion_ore(reactions, upper_bound)
-    );
-
-    PartAnswer::new(lower_bound, start.elapsed().unwrap())
+    graph
 }
 
-fn can_produce_with_one_trillion_ore(reactions: &[Reaction], amount_of_fuel: usize) -> bool {
-    let mut reactor = Reactor::with_initial_ore(reactions, Quantity::Limited(1_000_000_000_000));
-
-    reactor.produce_fuel(amount_of_fuel)
-}
-
-struct Reactor {
+/**
+ * The reactions as a DAG from each chemical to the chemicals it's made from
+ *
+ * Every chemical that consumes another comes before it in the topological order, so by the time a
+ * chemical is reached everything that needs it has already asked for it. A single pass in that
+ * order finds how much of everything is needed, running each reaction just enough times.
+ */
+struct ReactionGraph {
     reactions_by_output_name: HashMap<String, Reaction>,
-    available_reactants: HashMap<String, Quantity>,
-    used_reactants: HashMap<String, usize>,
+    production_order: Vec<String>,
 }
 
-impl Reactor {
-    fn with_initial_ore(reactions: &[Reaction], ore: Quantity) -> Reactor {
-        let mut available_reactants = HashMap::new();
-        available_reactants.insert("ORE".to_string(), ore);
+impl ReactionGraph {
+    fn new(reactions: &[Reaction]) -> Result<ReactionGraph, AdventOfCodeError> {
+        let reactions_by_output_name = index_reactions_by_output_name(reactions)?;
+
+        let graph = chemical_graph(reactions);
 
-        let reactions_by_output_name = index_reactions_by_output_name(reactions);
+        let production_order = toposort(&graph, None)
+            .map_err(|cycle| {
+                AdventOfCodeError::InvalidInput(format!(
+                    "reactions contain a cycle through {}",
+                    graph[cycle.node_id()]
+                ))
+            })?
+            .into_iter()
+            .map(|node| graph[node].to_string())
+            .collect();
 
-        Reactor {
+        Ok(ReactionGraph {
             reactions_by_output_name,
-            available_reactants,
-            used_reactants: HashMap::new(),
-        }
+            production_order,
+        })
     }
 
-    /*
-     * returns true if fuel can be produced, false otherwise
-     */
-    fn produce_fuel(&mut self, amount: usize) -> bool {
-        let amount_before_reaction = self
-            .available_reactants
-            .get("FUEL")
-            .cloned()
-            .unwrap_or(Quantity::Limited(0));
-
-        self.produce("FUEL", amount);
-
-        let amount_after_reaction = match self.available_reactants.get("FUEL") {
-            Some(amount) => *amount,
-            None => Quantity::Limited(0),
-        };
+    fn requirements(&self, fuel: usize) -> Vec<Requirement> {
+        let mut needed: HashMap<&str, usize> = HashMap::new();
+        needed.insert("FUEL", fuel);
+
+        let mut requirements = vec![];
+
+        for name in self.production_order.iter() {
+            let amount_needed = needed.get(name.as_str()).cloned().unwrap_or(0);
+
+            let requirement = match self.reactions_by_output_name.get(name) {
+                Some(reaction) => {
+                    let batch = reaction.output.quantity;
+
+                    // any leftover output is wasted, but the reaction still has to run
+                    let reactions_run = if amount_needed % batch == 0 {
+                        amount_needed / batch
+                    } else {
+                        (amount_needed / batch) + 1
+                    };
+
+                    for input in reaction.inputs.iter() {
+                        *needed.entry(input.name.as_str()).or_insert(0) +=
+                            (input * reactions_run).quantity;
+                    }
+
+                    Requirement {
+                        name: name.clone(),
+                        needed: amount_needed,
+                        reactions_run,
+                        produced: (&reaction.output * reactions_run).quantity,
+                    }
+                }
+                // there's no reaction for ORE, it's just there
+                None => Requirement {
+                    name: name.clone(),
+                    needed: amount_needed,
+                    reactions_run: 0,
+                    produced: amount_needed,
+                },
+            };
+
+            requirements.push(requirement);
+        }
 
-        &amount_after_reaction > &amount_before_reaction
+        requirements
     }
 
-    fn produce(&mut self, output_name: &str, amount: usize) -> bool {
-        // do we already have some output
-        let available_quantity = self
-            .available_reactants
-            .get(output_name)
-            .cloned()
-            .unwrap_or(Quantity::Limited(0));
-
-        if available_quantity.has_required_amount(amount) {
-            debug!("{} {} already available", amount, output_name);
-            return true;
-        }
-
-        // if not enough ore, terminate recursion
-        if output_name == "ORE" {
-            debug!("not enough ORE");
-            return false;
+    /// Fails if the reactions don't make FUEL out of ORE, as no amount of ore would be enough
+    fn ore_for_fuel(&self, fuel: usize) -> Result<usize, AdventOfCodeError> {
+        let ore = self
+            .requirements(fuel)
+            .into_iter()
+            .find(|requirement| requirement.name == "ORE")
+            .map(|requirement| requirement.needed)
+            .unwrap_or(0);
+
+        if ore == 0 && fuel > 0 {
+            return Err(AdventOfCodeError::InvalidInput(
+                "the reactions don't make FUEL from ORE".to_string(),
+            ));
         }
 
-        // todo - this is safe, but it should be more obvious
-        let amount_needed = available_quantity.get_amount_missing(amount).unwrap();
-
-        debug!(
-            "{} {} needed and {:?} {} already exists - need {} {}",
-            amount, output_name, available_quantity, output_name, amount_needed, output_name
-        );
-
-        let reaction = self
-            .reactions_by_output_name
-            .get(output_name)
-            .unwrap_or_else(|| panic!("could not get reaction for {}", output_name))
-            .ensure_output(amount_needed);
-
-        for input in reaction.inputs.iter() {
-            debug!("producing {} as input for {}", input.name, output_name);
-            let enough_input = self.produce(&input.name, input.quantity);
+        Ok(ore)
+    }
 
-            if !enough_input {
-                return false;
-            }
+    /**
+     * The most fuel that can be made from `ore`
+     *
+     * Leftovers from making one unit of fuel can go towards the next, so the ore needed per unit of
+     * fuel only goes down as more is made. That makes making `ore / ore_for_fuel(1)` fuel always
+     * possible, and the exact answer is found by doubling up from there and then bisecting.
+     */
+    fn max_fuel(&self, ore: usize) -> Result<usize, AdventOfCodeError> {
+        let mut lower_bound = ore / self.ore_for_fuel(1)?;
+        let mut upper_bound = lower_bound.max(1) * 2;
+
+        while self.ore_for_fuel(upper_bound)? <= ore {
+            lower_bound = upper_bound;
+            upper_bound = upper_bound.checked_mul(2).ok_or_else(|| {
+                AdventOfCodeError::Overflow(format!("more than {} FUEL", lower_bound))
+            })?;
         }
 
-        // TODO - make this more robust
-        // we may have accidentally consumed some intermediate resource when producing above - ensure again
-        for input in reaction.inputs.iter() {
-            debug!("producing {} as input for {}", input.name, output_name);
-            let enough_input = self.produce(&input.name, input.quantity);
+        // ore_for_fuel(lower_bound) <= ore < ore_for_fuel(upper_bound)
+        while upper_bound - lower_bound > 1 {
+            let midpoint = lower_bound + (upper_bound - lower_bound) / 2;
 
-            if !enough_input {
-                return false;
-            }
-        }
+            debug!(
+                "lower: {}, mid: {}, upper: {}",
+                lower_bound, midpoint, upper_bound
+            );
 
-        for input in reaction.inputs.iter() {
-            self.consume(&input.name, input.quantity);
+            if self.ore_for_fuel(midpoint)? <= ore {
+                lower_bound = midpoint;
+            } else {
+                upper_bound = midpoint;
+            }
         }
 
-        // this is wrong - we may produce more than what we need
-        let new_available_reactant = &available_quantity + reaction.output.quantity;
-        debug!(
-            "{:?} {} was just produced",
-            new_available_reactant, output_name
-        );
-        self.available_reactants
-            .insert(output_name.to_string(), new_available_reactant);
-
-        true
-    }
-
-    fn consume(&mut self, name: &str, amount: usize) {
-        let available_quantity = self.available_reactants.get(name).cloned().unwrap();
-
-        debug!(
-            "consuming {} {}, {:?} {} available",
-            amount, name, available_quantity, name
-        );
-
-        let new_available_reactant = &available_quantity - amount;
-        let new_used_reactant = self.used_reactants.get(name).cloned().unwrap_or(0) + amount;
-
-        self.available_reactants
-            .insert(name.to_string(), new_available_reactant);
-        self.used_reactants
-            .insert(name.to_string(), new_used_reactant);
+        Ok(lower_bound)
     }
-}
-
-#[derive(PartialEq, Eq, Clone, Copy)]
-enum Quantity {
-    Unlimited,
-    Limited(usize),
-}
 
-impl PartialOrd for &Quantity {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for &Quantity {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            (Quantity::Unlimited, Quantity::Unlimited) => Ordering::Equal,
-            (Quantity::Unlimited, Quantity::Limited(_)) => Ordering::Greater,
-            (Quantity::Limited(_), Quantity::Unlimited) => Ordering::Less,
-            (Quantity::Limited(first), Quantity::Limited(second)) => first.cmp(second),
-        }
+    /// One line per chemical, in production order, describing how much of it making `fuel` takes
+    fn report(&self, fuel: usize) -> String {
+        self.requirements(fuel)
+            .iter()
+            .map(Requirement::to_string)
+            .collect::<Vec<String>>()
+            .join("\n")
     }
 }
 
-impl Add<usize> for &Quantity {
-    type Output = Quantity;
-
-    fn add(self, rhs: usize) -> Self::Output {
-        match self {
-            Quantity::Unlimited => Quantity::Unlimited,
-            Quantity::Limited(amount) => Quantity::Limited(*amount + rhs),
-        }
-    }
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Requirement {
+    name: String,
+    needed: usize,
+    reactions_run: usize,
+    produced: usize,
 }
 
-impl Sub<usize> for &Quantity {
-    type Output = Quantity;
-    fn sub(self, rhs: usize) -> Self::Output {
-        match self {
-            Quantity::Unlimited => Quantity::Unlimited,
-            Quantity::Limited(amount) => Quantity::Limited(amount - rhs),
-        }
+impl Requirement {
+    fn leftover(&self) -> usize {
+        self.produced - self.needed
     }
 }
 
-impl Debug for Quantity {
+impl Display for Requirement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Quantity::Unlimited => write!(f, "Unlimited"),
-            Quantity::Limited(amount) => write!(f, "{}", amount),
-        }
-    }
-}
-
-impl Quantity {
-    fn has_required_amount(&self, required: usize) -> bool {
-        match self {
-            Quantity::Unlimited => true,
-            Quantity::Limited(available) => *available >= required,
-        }
-    }
-
-    fn get_amount_missing(&self, required: usize) -> Option<usize> {
-        if self.has_required_amount(required) {
-            None
+        if self.reactions_run == 0 {
+            write!(f, "{} {}", self.needed, self.name)
         } else {
-            // we always have enough when we have unlimited
-            // if we don't have enough, it must be limited
-
-            if let Quantity::Limited(current) = self {
-                let current = *current;
-                if required <= current {
-                    None
-                } else {
-                    Some(required - current)
-                }
-            } else {
-                panic!()
-            }
+            write!(
+                f,
+                "{} {}, from {} reactions making {} with {} left over",
+                self.needed,
+                self.name,
+                self.reactions_run,
+                self.produced,
+                self.leftover()
+            )
         }
     }
 }
@@ -321,19 +293,6 @@ impl Reaction {
     fn new(inputs: Vec<Chemical>, output: Chemical) -> Reaction {
         Reaction { inputs, output }
     }
-
-    fn ensure_output(&self, min_output: usize) -> Reaction {
-        let multiplier = (min_output as f64 / self.output.quantity as f64).ceil() as usize;
-
-        if multiplier <= 1 {
-            return self.clone();
-        }
-
-        let inputs = self.inputs.iter().map(|c| c * multiplier).collect();
-        let output = &self.output * multiplier;
-
-        Reaction::new(inputs, output)
-    }
 }
 
 impl Display for Reaction {
@@ -438,95 +397,104 @@ mod tests {
     }
 
     #[test]
-    fn test_scale_reaction() {
-        let reaction = Reaction::new(
-            vec![Chemical::new(1, "A"), Chemical::new(2, "B")],
-            Chemical::new(3, "C"),
-        );
+    fn test_reaction_one() {
+        let reactions = "9 ORE => 2 A\n8 ORE => 3 B\n7 ORE => 5 C\n3 A, 4 B => 1 AB\n5 B, 7 C => 1 BC\n4 C, 1 A => 1 CA\n2 AB, 3 BC, 4 CA => 1 FUEL\n";
+        let reactions = parse_reactions(reactions);
 
-        let scaled = reaction.ensure_output(4);
+        let required_ore = part_one(&reactions).unwrap();
 
-        assert_eq!(
-            scaled,
-            Reaction::new(
-                vec![Chemical::new(2, "A"), Chemical::new(4, "B")],
-                Chemical::new(6, "C")
-            )
-        );
-
-        assert_eq!(reaction.ensure_output(1), reaction);
+        assert_eq!(required_ore.get_answer(), "165");
     }
 
     #[test]
-    fn test_amount_missing() {
-        let quantity = Quantity::Limited(10);
-
-        assert_eq!(quantity.get_amount_missing(11), Some(1));
-        assert_eq!(quantity.get_amount_missing(9), None);
+    fn test_reaction_two() {
+        let reactions = "157 ORE => 5 NZVS\n165 ORE => 6 DCFZ\n44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL\n12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ\n179 ORE => 7 PSHF\n177 ORE => 5 HKGWZ\n7 DCFZ, 7 PSHF => 2 XJWVT\n165 ORE => 2 GPVTF\n3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT\n";
+        let reactions = parse_reactions(reactions);
 
-        let quantity = Quantity::Unlimited;
+        let required_ore = part_one(&reactions).unwrap();
 
-        assert_eq!(quantity.get_amount_missing(1), None);
+        assert_eq!(required_ore.get_answer(), "13312");
     }
 
     #[test]
-    fn test_produce() {
-        let reactions = vec![Reaction::new(
-            vec![Chemical::new(10, "ORE")],
-            Chemical::new(1, "A"),
-        )];
-
-        let mut reactor = Reactor::with_initial_ore(&reactions, Quantity::Limited(11));
-
-        assert!(reactor.produce("A", 1));
-        assert_eq!(reactor.available_reactants["ORE"], Quantity::Limited(1));
-        assert_eq!(reactor.available_reactants["A"], Quantity::Limited(1));
-        assert_eq!(reactor.used_reactants["ORE"], 10);
-        assert_eq!(reactor.used_reactants.get("A"), None);
-        assert!(reactor.produce("A", 1));
+    fn test_reaction_three() {
+        let reactions = "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG\n17 NVRVD, 3 JNWZP => 8 VPVL\n53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL\n22 VJHF, 37 MNCFX => 5 FWMGM\n139 ORE => 4 NVRVD\n144 ORE => 7 JNWZP\n5 MNCFX, 7 RFSQX, 2 FWMGM, 2 VPVL, 19 CXFTF => 3 HVMC\n5 VJHF, 7 MNCFX, 9 VPVL, 37 CXFTF => 6 GNMV\n145 ORE => 6 MNCFX\n1 NVRVD => 8 CXFTF\n1 VJHF, 6 MNCFX => 4 RFSQX\n176 ORE => 6 VJHF\n";
+        let reactions = parse_reactions(reactions);
 
-        reactor.consume("A", 1);
+        let required_ore = part_one(&reactions).unwrap();
 
-        assert!(!reactor.produce("A", 1));
-        assert_eq!(
-            reactor.available_reactants.get("ORE").cloned(),
-            Some(Quantity::Limited(1))
-        );
-        assert_eq!(
-            reactor.available_reactants.get("A").cloned(),
-            Some(Quantity::Limited(0))
-        );
-        assert_eq!(reactor.used_reactants.get("ORE").cloned(), Some(10));
-        assert_eq!(reactor.used_reactants.get("A").cloned(), Some(1));
+        assert_eq!(required_ore.get_answer(), "180697");
     }
 
     #[test]
-    fn test_reaction_one() {
-        let reactions = "9 ORE => 2 A\n8 ORE => 3 B\n7 ORE => 5 C\n3 A, 4 B => 1 AB\n5 B, 7 C => 1 BC\n4 C, 1 A => 1 CA\n2 AB, 3 BC, 4 CA => 1 FUEL\n";
-        let reactions = parse_reactions(reactions);
+    fn test_max_fuel() {
+        let reactions = "157 ORE => 5 NZVS\n165 ORE => 6 DCFZ\n44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL\n12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ\n179 ORE => 7 PSHF\n177 ORE => 5 HKGWZ\n7 DCFZ, 7 PSHF => 2 XJWVT\n165 ORE => 2 GPVTF\n3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT\n";
+        let graph = ReactionGraph::new(&parse_reactions(reactions)).unwrap();
 
-        let required_ore = part_one(&reactions);
+        assert_eq!(graph.max_fuel(ONE_TRILLION), Ok(82892753));
+        assert!(graph.ore_for_fuel(82892753).unwrap() <= ONE_TRILLION);
+        assert!(graph.ore_for_fuel(82892754).unwrap() > ONE_TRILLION);
 
-        assert_eq!(required_ore.get_answer(), "165");
+        let reactions = "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG\n17 NVRVD, 3 JNWZP => 8 VPVL\n53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL\n22 VJHF, 37 MNCFX => 5 FWMGM\n139 ORE => 4 NVRVD\n144 ORE => 7 JNWZP\n5 MNCFX, 7 RFSQX, 2 FWMGM, 2 VPVL, 19 CXFTF => 3 HVMC\n5 VJHF, 7 MNCFX, 9 VPVL, 37 CXFTF => 6 GNMV\n145 ORE => 6 MNCFX\n1 NVRVD => 8 CXFTF\n1 VJHF, 6 MNCFX => 4 RFSQX\n176 ORE => 6 VJHF\n";
+        let graph = ReactionGraph::new(&parse_reactions(reactions)).unwrap();
+
+        assert_eq!(graph.max_fuel(ONE_TRILLION), Ok(5586022));
     }
 
     #[test]
-    fn test_reaction_two() {
-        let reactions = "157 ORE => 5 NZVS\n165 ORE => 6 DCFZ\n44 XJWVT, 5 KHKGT, 1 QDVJ, 29 NZVS, 9 GPVTF, 48 HKGWZ => 1 FUEL\n12 HKGWZ, 1 GPVTF, 8 PSHF => 9 QDVJ\n179 ORE => 7 PSHF\n177 ORE => 5 HKGWZ\n7 DCFZ, 7 PSHF => 2 XJWVT\n165 ORE => 2 GPVTF\n3 DCFZ, 7 NZVS, 5 HKGWZ, 10 PSHF => 8 KHKGT\n";
-        let reactions = parse_reactions(reactions);
+    fn test_requirements() {
+        let reactions = "10 ORE => 10 A\n1 ORE => 1 B\n7 A, 1 B => 1 C\n7 A, 1 C => 1 D\n7 A, 1 D => 1 E\n7 A, 1 E => 1 FUEL\n";
+        let graph = ReactionGraph::new(&parse_reactions(reactions)).unwrap();
 
-        let required_ore = part_one(&reactions);
+        assert_eq!(
+            graph.production_order.first().map(String::as_str),
+            Some("FUEL")
+        );
+        assert_eq!(
+            graph.production_order.last().map(String::as_str),
+            Some("ORE")
+        );
 
-        assert_eq!(required_ore.get_answer(), "13312");
+        let requirements = graph.requirements(1);
+        let a = requirements.iter().find(|r| r.name == "A").unwrap();
+
+        assert_eq!(a.needed, 28);
+        assert_eq!(a.reactions_run, 3);
+        assert_eq!(a.leftover(), 2);
+        assert_eq!(
+            a.to_string(),
+            "28 A, from 3 reactions making 30 with 2 left over"
+        );
+        assert_eq!(graph.ore_for_fuel(1), Ok(31));
+        assert!(graph.report(1).ends_with("\n31 ORE"));
     }
 
     #[test]
-    fn test_reaction_three() {
-        let reactions = "2 VPVL, 7 FWMGM, 2 CXFTF, 11 MNCFX => 1 STKFG\n17 NVRVD, 3 JNWZP => 8 VPVL\n53 STKFG, 6 MNCFX, 46 VJHF, 81 HVMC, 68 CXFTF, 25 GNMV => 1 FUEL\n22 VJHF, 37 MNCFX => 5 FWMGM\n139 ORE => 4 NVRVD\n144 ORE => 7 JNWZP\n5 MNCFX, 7 RFSQX, 2 FWMGM, 2 VPVL, 19 CXFTF => 3 HVMC\n5 VJHF, 7 MNCFX, 9 VPVL, 37 CXFTF => 6 GNMV\n145 ORE => 6 MNCFX\n1 NVRVD => 8 CXFTF\n1 VJHF, 6 MNCFX => 4 RFSQX\n176 ORE => 6 VJHF\n";
-        let reactions = parse_reactions(reactions);
-
-        let required_ore = part_one(&reactions);
-
-        assert_eq!(required_ore.get_answer(), "180697");
+    fn test_invalid_reactions() {
+        let cycle = parse_reactions("1 ORE, 1 B => 1 A\n1 A => 1 B\n1 B => 1 FUEL\n");
+
+        assert!(matches!(
+            ReactionGraph::new(&cycle),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+
+        let duplicate = parse_reactions("1 ORE => 1 A\n2 ORE => 1 A\n1 A => 1 FUEL\n");
+
+        assert!(matches!(
+            ReactionGraph::new(&duplicate),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+
+        let no_ore = parse_reactions("1 ORE => 1 A\n1 B => 1 FUEL\n");
+        let graph = ReactionGraph::new(&no_ore).unwrap();
+
+        assert!(matches!(
+            graph.ore_for_fuel(1),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            graph.max_fuel(ONE_TRILLION),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
     }
 }
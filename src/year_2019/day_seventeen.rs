@@ -1,15 +1,28 @@
 use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::time::SystemTime;
+use crate::common::animation::Frame;
 use crate::common::answer::{AdventOfCodeResult, PartAnswer};
+use crate::common::output::OutputSettings;
 
 use itertools::Itertools;
 use log::debug;
 
 use crate::year_2019::computer::Computer;
 
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-17.txt");
 
+    if output.is_animating() {
+        let scaffold_map = read_scaffold_map(input);
+        let intersections = find_intersections(&scaffold_map);
+
+        output.write_animation(
+            "2019-17",
+            iter::once(scaffold_map.render(&intersections)).chain(video_feed_frames(input)),
+        );
+    }
+
     let part_one = part_one(input);
     let part_two = part_two(input);
 
@@ -19,82 +32,9 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(program: &str) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut computer = Computer::from_program(program);
-
-    computer.step_until_halt();
-
-    let mut map = HashMap::new();
-
-    /*
-     * Coordinate system
-     * top-left is (0,0)
-     * Moving right is positive x
-     * Moving down is positive y
-     */
-
-    let mut x: usize = 0;
-    let mut y: usize = 0;
-
-    let mut max_x = 0;
-    let mut max_y = 0;
-
-    while let Some(output) = computer.get_output() {
-        let status = output as u8 as char;
-
-        if status == '\n' {
-            y += 1;
-            x = 0;
-            continue;
-        } else {
-            map.insert((x, y), status);
-            x += 1;
-        }
-
-        max_x = max_x.max(x);
-        max_y = max_y.max(y);
-    }
-
-    let mut intersections = HashSet::new();
-
-    // intersections cannot be at borders
-    for x in 1..max_x - 1 {
-        for y in 1..max_y - 1 {
-            let status = map.get(&(x, y)).unwrap();
-
-            if *status != '#' {
-                continue;
-            }
+    let scaffold_map = read_scaffold_map(program);
 
-            let neighbors = &[(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
-                .iter()
-                .filter_map(|c| map.get(c))
-                .filter(|c| **c == '#')
-                .count();
-
-            if *neighbors == 4 {
-                debug!("({x}, {y}) -> {}", x * y);
-                intersections.insert((x, y));
-            }
-        }
-    }
-
-    // for y in 0..=max_y {
-    //     for x in 0..max_x {
-    //         if intersections.contains(&(x, y)) {
-    //             print!("O");
-    //         } else {
-    //             let status = map.get(&(x, y)).unwrap();
-    //             let out = match status {
-    //                 '#' => "\u{2588}",
-    //                 '.' => " ",
-    //                 '^' => "^",
-    //                 _ => panic!("{}", status),
-    //             };
-    //             print!("{}", status);
-    //         }
-    //     }
-    //     println!();
-    // }
+    let intersections = find_intersections(&scaffold_map);
 
     let alignment_sum = sum_alignment_parameters(intersections);
 
@@ -134,10 +74,25 @@ fn part_two(program: &str) -> PartAnswer {
 
     computer.set(0, 2);
 
+    push_movement_routines(&mut computer, false);
+
+    computer.step_until_halt();
+
+    while let Some(output) = computer.get_output() {
+        // not 46
+        if output > 127 {
+            return PartAnswer::new(output, start.elapsed().unwrap());
+        }
+    }
+
+    PartAnswer::default()
+}
+
+fn push_movement_routines(computer: &mut Computer, continuous_video_feed: bool) {
     let inputs = [65, 66, 65, 65, 66, 67, 66, 67, 67, 66];
-    itertools::Itertools::intersperse(inputs.iter().copied(), 44).collect_vec();
 
-    for input in inputs {
+    // the main routine needs commas between each movement function
+    for input in Itertools::intersperse(inputs.iter().copied(), 44) {
         computer.push_input(input);
     }
     computer.push_input(10);
@@ -200,19 +155,151 @@ fn part_two(program: &str) -> PartAnswer {
     computer.push_input(50); // 2
     computer.push_input(10); // \n
 
-    computer.push_input(110); // y
+    if continuous_video_feed {
+        computer.push_input(121); // y
+    } else {
+        computer.push_input(110); // n
+    }
     computer.push_input(10); // \n
+}
+
+/// Runs the movement routines with the continuous video feed on, yielding each camera image
+fn video_feed_frames(program: &str) -> impl Iterator<Item = Frame> {
+    let mut computer = Computer::from_program(program);
+
+    computer.set(0, 2);
+
+    push_movement_routines(&mut computer, true);
 
     computer.step_until_halt();
 
+    let mut text = String::new();
+
     while let Some(output) = computer.get_output() {
-        // not 46
-        if output > 127 {
-            return PartAnswer::new(output, start.elapsed().unwrap());
+        // the last output is the amount of dust collected, not an ASCII character
+        if output <= 127 {
+            text.push(output as u8 as char);
         }
     }
 
-    PartAnswer::default()
+    camera_images(&text).into_iter()
+}
+
+/// Splits the robot's ASCII output into camera images, which are separated by blank lines
+fn camera_images(text: &str) -> Vec<Frame> {
+    text.split("\n\n")
+        .map(|image| {
+            // the prompts for the routines are not part of any image
+            image
+                .lines()
+                .filter(|line| line.starts_with(['#', '.', '^', 'v', '<', '>', 'X']))
+                .collect::<Vec<&str>>()
+        })
+        .filter(|lines| lines.len() > 1)
+        .map(|lines| {
+            Frame::from_cells(lines.iter().enumerate().flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .map(move |(x, c)| ((x as isize, y as isize), c))
+            }))
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ScaffoldMap {
+    cells: HashMap<(usize, usize), char>,
+    max_x: usize,
+    max_y: usize,
+}
+
+impl ScaffoldMap {
+    fn render(&self, intersections: &HashSet<(usize, usize)>) -> Frame {
+        let mut frame = Frame::new(self.max_x, self.max_y + 1);
+
+        for ((x, y), status) in &self.cells {
+            if intersections.contains(&(*x, *y)) {
+                frame.set(*x, *y, 'O');
+            } else {
+                frame.set(*x, *y, *status);
+            }
+        }
+
+        frame
+    }
+}
+
+fn read_scaffold_map(program: &str) -> ScaffoldMap {
+    let mut computer = Computer::from_program(program);
+
+    computer.step_until_halt();
+
+    let mut cells = HashMap::new();
+
+    /*
+     * Coordinate system
+     * top-left is (0,0)
+     * Moving right is positive x
+     * Moving down is positive y
+     */
+
+    let mut x: usize = 0;
+    let mut y: usize = 0;
+
+    let mut max_x = 0;
+    let mut max_y = 0;
+
+    while let Some(output) = computer.get_output() {
+        let status = output as u8 as char;
+
+        if status == '\n' {
+            y += 1;
+            x = 0;
+            continue;
+        } else {
+            cells.insert((x, y), status);
+            x += 1;
+        }
+
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+
+    ScaffoldMap {
+        cells,
+        max_x,
+        max_y,
+    }
+}
+
+fn find_intersections(scaffold_map: &ScaffoldMap) -> HashSet<(usize, usize)> {
+    let map = &scaffold_map.cells;
+
+    let mut intersections = HashSet::new();
+
+    // intersections cannot be at borders
+    for x in 1..scaffold_map.max_x - 1 {
+        for y in 1..scaffold_map.max_y - 1 {
+            let status = map.get(&(x, y)).unwrap();
+
+            if *status != '#' {
+                continue;
+            }
+
+            let neighbors = &[(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]
+                .iter()
+                .filter_map(|c| map.get(c))
+                .filter(|c| **c == '#')
+                .count();
+
+            if *neighbors == 4 {
+                debug!("({x}, {y}) -> {}", x * y);
+                intersections.insert((x, y));
+            }
+        }
+    }
+
+    intersections
 }
 
 fn sum_alignment_parameters<I>(intersections: I) -> usize
@@ -233,4 +320,36 @@ mod tests {
             76
         );
     }
+
+    #[test]
+    fn test_camera_images() {
+        let text = "\
+..#..
+..#..
+^.#..
+
+Main:
+Function A:
+Continuous video feed?
+
+..#..
+..#..
+..>..
+
+";
+
+        let images = camera_images(text);
+
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].to_string(), "..#..\n..#..\n^.#..");
+        assert_eq!(images[1].get(2, 2), Some('>'));
+    }
+
+    #[test]
+    fn test_answers() {
+        let (part_one, part_two) = run(&OutputSettings::default()).unwrap();
+
+        assert_eq!(*part_one.get_answer(), "5068".to_string());
+        assert_eq!(*part_two.get_answer(), "1415975".to_string());
+    }
 }
@@ -1,10 +1,21 @@
+use std::collections::HashMap;
+use std::iter;
 use std::time::SystemTime;
+use crate::common::animation::Frame;
 use crate::common::answer::*;
+use crate::common::output::OutputSettings;
 
 use crate::year_2019::computer::Computer;
 
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-13.txt");
+
+    if output.is_animating() {
+        let mut arcade_cabinet = ArcadeCabinet::new(input);
+        arcade_cabinet.insert_quarters();
+        output.write_animation("2019-13", arcade_cabinet.into_frames());
+    }
+
     let part_one = part_one(input);
     let part_two = part_two(input);
     Ok((part_one, part_two))
@@ -37,6 +48,7 @@ struct ArcadeCabinet {
     last_score: i128,
     last_ball_position: (i128, i128),
     last_paddle_position: (i128, i128),
+    screen: HashMap<(i128, i128), TileType>,
 }
 
 impl ArcadeCabinet {
@@ -48,6 +60,7 @@ impl ArcadeCabinet {
             last_score: 0,
             last_ball_position: (0, 0),
             last_paddle_position: (0, 0),
+            screen: HashMap::new(),
         }
     }
 
@@ -57,38 +70,88 @@ impl ArcadeCabinet {
 
     fn play(&mut self) {
         while !self.computer.is_halted() {
-            self.computer.step();
-
-            if self.computer.get_number_of_outputs() >= 3 {
-                let x = self.computer.get_output().unwrap();
-                let y = self.computer.get_output().unwrap();
-                let tile_id = self.computer.get_output().unwrap();
-
-                if (x, y) == (-1, 0) {
-                    self.last_score = tile_id;
-                } else {
-                    let tile_type = tile_id.into();
-
-                    match tile_type {
-                        TileType::Ball => {
-                            self.last_ball_position = (x, y);
-                        }
-                        TileType::HorizontalPaddle => {
-                            self.last_paddle_position = (x, y);
-                        }
-                        _ => {}
-                    };
-                }
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        self.computer.step();
+
+        if self.computer.get_number_of_outputs() >= 3 {
+            let x = self.computer.get_output().unwrap();
+            let y = self.computer.get_output().unwrap();
+            let tile_id = self.computer.get_output().unwrap();
+
+            if (x, y) == (-1, 0) {
+                self.last_score = tile_id;
+            } else {
+                let tile_type = tile_id.into();
+
+                match tile_type {
+                    TileType::Ball => {
+                        self.last_ball_position = (x, y);
+                    }
+                    TileType::HorizontalPaddle => {
+                        self.last_paddle_position = (x, y);
+                    }
+                    _ => {}
+                };
+
+                self.screen.insert((x, y), tile_type);
             }
+        }
 
-            if self.computer.is_blocked_on_input() {
-                let last_ball_position = self.last_ball_position;
-                let last_paddle_position = self.last_paddle_position;
+        if self.computer.is_blocked_on_input() {
+            let last_ball_position = self.last_ball_position;
+            let last_paddle_position = self.last_paddle_position;
 
-                let input = get_arcade_input(&last_ball_position, &last_paddle_position);
-                self.computer.push_input(input);
+            let input = get_arcade_input(&last_ball_position, &last_paddle_position);
+            self.computer.push_input(input);
+        }
+    }
+
+    /// Yields the screen every time the ball is drawn somewhere new
+    fn into_frames(mut self) -> impl Iterator<Item = Frame> {
+        iter::from_fn(move || {
+            let last_ball_position = self.last_ball_position;
+
+            while !self.computer.is_halted() {
+                self.step();
+
+                if self.last_ball_position != last_ball_position {
+                    return Some(self.render());
+                }
             }
+
+            None
+        })
+    }
+
+    fn render(&self) -> Frame {
+        let width = self.screen.keys().map(|(x, _)| *x).max().unwrap_or(0) as usize + 1;
+        let height = self.screen.keys().map(|(_, y)| *y).max().unwrap_or(0) as usize + 1;
+
+        let score = format!("Score: {}", self.last_score);
+
+        let mut frame = Frame::new(width.max(score.len()), height + 1);
+
+        for (x, c) in score.chars().enumerate() {
+            frame.set(x, 0, c);
+        }
+
+        for ((x, y), tile_type) in &self.screen {
+            let c = match tile_type {
+                TileType::Empty => ' ',
+                TileType::Wall => '#',
+                TileType::Block => '=',
+                TileType::HorizontalPaddle => '-',
+                TileType::Ball => 'o',
+            };
+
+            frame.set(*x as usize, *y as usize + 1, c);
         }
+
+        frame
     }
 
     fn count_number_of_blocks(&mut self) -> usize {
@@ -154,4 +217,26 @@ mod tests {
         let input = get_arcade_input(&ball_position, &paddle_position);
         assert_eq!(input, 0);
     }
+
+    #[test]
+    fn test_render() {
+        let mut arcade_cabinet = ArcadeCabinet::new("99");
+        arcade_cabinet.last_score = 12;
+
+        for (x, tile_type) in [
+            TileType::Wall,
+            TileType::Block,
+            TileType::Ball,
+            TileType::HorizontalPaddle,
+            TileType::Empty,
+            TileType::Wall,
+        ]
+        .iter()
+        .enumerate()
+        {
+            arcade_cabinet.screen.insert((x as i128, 0), *tile_type);
+        }
+
+        assert_eq!(arcade_cabinet.render().to_string(), "Score: 12\n#=o- #   ");
+    }
 }
@@ -1,6 +1,8 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 use std::time::SystemTime;
 use crate::common::answer::*;
+use crate::common::geometry::Vector2;
+use crate::common::segment::Path;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -27,8 +29,8 @@ fn run_part_one(first: &[Step], second: &[Step]) -> PartAnswer {
     let intersections = get_intersections(first, second);
 
     let solution = intersections
-        .iter()
-        .map(|(x, y)| (x.abs() + y.abs()) as u32)
+        .keys()
+        .map(|point| point.manhattan() as u32)
         .min()
         .unwrap();
 
@@ -39,65 +41,33 @@ fn run_part_two(first: &[Step], second: &[Step]) -> PartAnswer {
     let start = SystemTime::now();
     let intersections = get_intersections(first, second);
 
-    let first_path = expand_points(first);
-    let second_path = expand_points(second);
-
-    let mut min_number_of_combined_steps = u32::MAX;
-
-    for intersection in intersections {
-        let steps_along_first_path = get_number_of_steps_to_point(&intersection, &first_path);
-        let steps_along_second_path = get_number_of_steps_to_point(&intersection, &second_path);
-
-        min_number_of_combined_steps =
-            min_number_of_combined_steps.min(steps_along_first_path + steps_along_second_path);
-    }
+    let min_number_of_combined_steps = intersections
+        .values()
+        .map(|(first_steps, second_steps)| (first_steps + second_steps) as u32)
+        .min()
+        .unwrap();
 
     PartAnswer::new(min_number_of_combined_steps, start.elapsed().unwrap())
 }
 
-fn get_number_of_steps_to_point(point: &(i32, i32), path: &[(i32, i32)]) -> u32 {
-    let mut counter = 0;
-    for step in path {
-        if step == point {
-            return counter;
-        } else {
-            counter += 1;
-        }
-    }
+/// Where the wires cross, apart from where they start, and how far along each wire it is
+fn get_intersections(first: &[Step], second: &[Step]) -> HashMap<Vector2, (i64, i64)> {
+    let mut crossings = trace(first).crossings(&trace(second));
 
-    panic!()
-}
+    crossings.remove(&Vector2::ZERO);
 
-// todo @nhirakawa - solve this more elegantly using line segment intersection?
-fn get_intersections(first: &[Step], second: &[Step]) -> HashSet<(i32, i32)> {
-    let first_points: HashSet<(i32, i32)> = expand_points(first).into_iter().collect();
-    let second_points = expand_points(second).into_iter().collect();
-
-    let intersections = first_points.intersection(&second_points);
-
-    intersections
-        .filter_map(|(x, y)| {
-            if *x == 0 && *y == 0 {
-                None
-            } else {
-                Some((*x, *y))
-            }
-        })
-        .collect()
+    crossings
 }
 
-fn expand_points(first: &[Step]) -> Vec<(i32, i32)> {
-    let mut start = (0, 0);
-    let mut all_points = Vec::new();
-    for path in first {
-        let points = path.expand(start);
-        all_points.extend(points);
-        start = path.get_endpoint(start, path.length as i32);
+fn trace(steps: &[Step]) -> Path {
+    let mut path = Path::new(Vector2::ZERO);
+
+    for step in steps {
+        path.walk(step.offset());
     }
-    all_points
-}
 
-type Coordinate = (i32, i32);
+    path
+}
 
 #[derive(Copy, Clone, Debug)]
 enum Direction {
@@ -118,26 +88,14 @@ impl Step {
         Step { direction, length }
     }
 
-    fn expand(&self, start: Coordinate) -> Vec<Coordinate> {
-        let mut coordinates = Vec::with_capacity(self.length as usize);
-
-        for delta in 0..self.length {
-            let delta = delta as i32;
-
-            let next = self.get_endpoint(start, delta);
-
-            coordinates.push(next);
-        }
-
-        coordinates
-    }
+    fn offset(&self) -> Vector2 {
+        let length = self.length as i64;
 
-    fn get_endpoint(&self, coordinate: Coordinate, delta: i32) -> Coordinate {
         match self.direction {
-            Direction::Up => (coordinate.0, coordinate.1 + delta),
-            Direction::Right => (coordinate.0 + delta, coordinate.1),
-            Direction::Down => (coordinate.0, coordinate.1 - delta),
-            Direction::Left => (coordinate.0 - delta, coordinate.1),
+            Direction::Up => Vector2::new(0, length),
+            Direction::Right => Vector2::new(length, 0),
+            Direction::Down => Vector2::new(0, -length),
+            Direction::Left => Vector2::new(-length, 0),
         }
     }
 }
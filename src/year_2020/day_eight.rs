@@ -1,6 +1,6 @@
-use std::collections::HashSet;
-use std::time::{Duration, SystemTime};
 use crate::common::answer::*;
+use crate::common::vm::{find_repair, InstructionSet, Machine, Outcome, Step};
+use log::debug;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -10,6 +10,7 @@ use nom::{
     sequence::preceded,
     IResult,
 };
+use std::time::{Duration, SystemTime};
 
 type Instructions = Vec<Op>;
 
@@ -22,7 +23,7 @@ pub fn run() -> AdventOfCodeResult {
     let parse_ms = start.elapsed().unwrap().as_millis();
 
     let part_one = part_one(&instructions, parse_ms);
-    let part_two = part_two(&instructions, parse_ms);
+    let part_two = part_two(&instructions, parse_ms)?;
 
     Ok((part_one, part_two))
 }
@@ -30,81 +31,92 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(instructions: &[Op], parse_ms: u128) -> PartAnswer {
     let start = SystemTime::now();
 
-    let (_, counter) = execute(instructions);
+    let (_, accumulator) = execute(instructions);
 
     let elapsed = start.elapsed().unwrap().as_millis() + parse_ms;
     let elapsed = Duration::from_millis(elapsed as u64);
 
-    (counter, elapsed).into()
+    (accumulator as u32, elapsed).into()
 }
 
-fn part_two(instructions: &[Op], parse_ms: u128) -> PartAnswer {
+fn part_two(instructions: &[Op], parse_ms: u128) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut copy = instructions.to_owned();
+    let (address, replacement) = find_repair(&Handheld, instructions, swap_jump_and_nop)
+        .ok_or_else(|| {
+            AdventOfCodeError::NoSolution("no single swap makes the boot code finish".to_string())
+        })?;
 
-    for (index, instruction) in instructions.iter().enumerate() {
-        match instruction {
-            Op::Jmp { value } => {
-                let updated = Op::Nop { value: *value };
-                copy[index] = updated;
-            }
-            Op::Nop { value } => {
-                let updated = Op::Jmp { value: *value };
-                copy[index] = updated;
-            }
-            _ => {}
-        }
+    let mut repaired = instructions.to_owned();
+    repaired[address] = replacement;
 
-        let (result, counter) = execute(&copy);
-        match result {
-            OperationResult::Success => {
-                let elapsed = start.elapsed().unwrap().as_millis() + parse_ms;
-                let elapsed = Duration::from_millis(elapsed as u64);
-                return (counter as u64, elapsed).into();
-            }
-            OperationResult::InfiniteLoop => {
-                copy[index] = *instruction;
-            }
-        }
+    // every jump is fixed, so boot code that finishes runs each instruction at most once
+    let mut machine = Machine::new(Handheld, repaired).with_step_limit(instructions.len());
+
+    let outcome = machine.run();
+
+    if outcome != Outcome::Terminated {
+        return Err(AdventOfCodeError::NoSolution(format!(
+            "swapping the instruction at {} doesn't make the boot code finish: {:?}",
+            address, outcome
+        )));
     }
 
-    PartAnswer::new(0, start.elapsed().unwrap())
+    debug!(
+        "the repaired boot code finished after {} steps",
+        machine.steps()
+    );
+
+    let accumulator = machine.registers()[ACCUMULATOR];
+
+    let elapsed = start.elapsed().unwrap().as_millis() + parse_ms;
+    let elapsed = Duration::from_millis(elapsed as u64);
+
+    Ok((accumulator as u64, elapsed).into())
 }
 
-fn execute(instructions: &[Op]) -> (OperationResult, u32) {
-    let mut program_counter = 0;
-    let mut accumulator = 0;
-    let mut seen = HashSet::new();
+/// Runs the boot code until it finishes or an instruction comes round again
+fn execute(instructions: &[Op]) -> (Outcome, i64) {
+    let mut machine = Machine::new(Handheld, instructions.to_owned()).with_loop_detection();
 
-    loop {
-        if !seen.insert(program_counter) {
-            return (OperationResult::InfiniteLoop, accumulator as u32);
-        }
+    let outcome = machine.run();
 
-        if program_counter >= instructions.len() {
-            return (OperationResult::Success, accumulator as u32);
-        }
+    (outcome, machine.registers()[ACCUMULATOR])
+}
 
-        let current = &instructions[program_counter];
-
-        match current {
-            Op::Acc { value } => {
-                accumulator += value;
-                program_counter += 1
-            }
-            Op::Jmp { value } => {
-                program_counter = ((program_counter as i32) + value) as usize;
-            }
-            Op::Nop { value: _ } => program_counter += 1,
-        }
+/// The corrupted instruction is a `jmp` that should be a `nop`, or the other way round
+fn swap_jump_and_nop(op: &Op) -> Option<Op> {
+    match op {
+        Op::Jmp { value } => Some(Op::Nop { value: *value }),
+        Op::Nop { value } => Some(Op::Jmp { value: *value }),
+        Op::Acc { .. } => None,
     }
 }
 
-#[derive(Debug, PartialEq)]
-enum OperationResult {
-    InfiniteLoop,
-    Success,
+const ACCUMULATOR: usize = 0;
+
+/// The handheld console's boot code, which has one register and only ever jumps by a fixed offset
+struct Handheld;
+
+impl InstructionSet for Handheld {
+    type Instruction = Op;
+
+    const REGISTERS: usize = 1;
+
+    fn execute(&self, instruction: &Op, registers: &mut [i64]) -> Step {
+        if let Op::Acc { value } = instruction {
+            registers[ACCUMULATOR] += *value as i64;
+        }
+
+        self.successor(instruction).unwrap()
+    }
+
+    fn successor(&self, instruction: &Op) -> Option<Step> {
+        match instruction {
+            Op::Jmp { value } => Some(Step::Jump(*value as i64)),
+            Op::Acc { .. } | Op::Nop { .. } => Some(Step::Next),
+        }
+    }
 }
 
 fn parse_instructions(i: &str) -> Result<Instructions, AdventOfCodeError> {
@@ -148,6 +160,37 @@ fn number(i: &str) -> IResult<&str, i32> {
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6";
+
+    #[test]
+    fn test_execute() {
+        let instructions = parse_instructions(EXAMPLE).unwrap();
+
+        assert_eq!(execute(&instructions), (Outcome::Looped { address: 1 }, 5));
+    }
+
+    #[test]
+    fn test_find_repair() {
+        let instructions = parse_instructions(EXAMPLE).unwrap();
+
+        let repair = find_repair(&Handheld, &instructions, swap_jump_and_nop);
+
+        assert_eq!(repair, Some((7, Op::Nop { value: -4 })));
+
+        let mut repaired = instructions.clone();
+        repaired[7] = Op::Nop { value: -4 };
+
+        assert_eq!(execute(&repaired), (Outcome::Terminated, 8));
+    }
+
     #[test]
     fn test_answers() {
         let (part_one, part_two) = run().unwrap();
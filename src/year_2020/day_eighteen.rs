@@ -1,251 +1,93 @@
-use std::time::{Duration, SystemTime};
+use std::convert::TryFrom;
+use std::time::SystemTime;
 use crate::common::answer::*;
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::{digit1, multispace0},
-    combinator::{map, map_res, value},
-    multi::many1,
-    sequence::{preceded, terminated},
-    IResult,
-};
+use crate::common::expr::{BinaryOperator, Environment, Expr, OperatorTable};
+use crate::common::parse::finish;
+use nom::{character::complete::multispace0, multi::many1, sequence::terminated, IResult};
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-18.txt");
-    let parse_start = SystemTime::now();
-    let tokens = parse_tokenized_expressions(input);
-    let parse_elapsed = parse_start.elapsed().unwrap();
 
-    let part_one = part_one(&tokens, parse_elapsed);
-    let part_two = part_two(&tokens, parse_elapsed);
+    let part_one = part_one(input)?;
+    let part_two = part_two(input)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(expressions: &[TokenizedExpression], parse_duration: Duration) -> PartAnswer {
+fn part_one(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut sum = 0;
-    for expression in expressions {
-        let evaluated = evaluate_expression(expression, false);
-        sum += evaluated;
-    }
-
-    let elapsed = start.elapsed().unwrap();
-
-    (sum, elapsed + parse_duration).into()
-}
-
-fn part_two(expressions: &[TokenizedExpression], parse_duration: Duration) -> PartAnswer {
-    let start = SystemTime::now();
+    let table = left_to_right();
 
-    let mut sum = 0;
-    for expression in expressions {
-        let evaluated = evaluate_expression(expression, true);
-        sum += evaluated;
-    }
+    let sum = sum_expressions(&parse_expressions(input, &table)?)?;
 
     let elapsed = start.elapsed().unwrap();
 
-    (sum, elapsed + parse_duration).into()
-}
-
-type ReversePolishExpression = Vec<StackElement>;
-type TokenizedExpression = Vec<Token>;
-
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum StackElement {
-    Operand(u64),
-    Operator(Operator),
-}
-
-fn evaluate_expression(expression: &[Token], addition_has_higher_precedence: bool) -> u64 {
-    evaluate_reverse_polish_expression(shunting_yard(expression, addition_has_higher_precedence))
-}
-
-#[allow(clippy::branches_sharing_code)]
-fn shunting_yard(
-    tokens: &[Token],
-    addition_has_higher_precedence: bool,
-) -> ReversePolishExpression {
-    let mut output = Vec::new();
-    let mut operator_stack = Vec::new();
-
-    for token in tokens {
-        match token {
-            Token::Number(number) => {
-                output.push(StackElement::Operand(*number));
-            }
-            Token::Add | Token::Multiply => {
-                if addition_has_higher_precedence
-                    && operator_stack.last() == Some(&Token::Multiply)
-                    && *token == Token::Add
-                {
-                    operator_stack.push(*token);
-                } else {
-                    while !operator_stack.is_empty()
-                        && (!addition_has_higher_precedence
-                            || operator_stack.last() == Some(&Token::Add)
-                                && *token == Token::Multiply)
-                    {
-                        let last_operator = operator_stack[operator_stack.len() - 1];
-                        if last_operator == Token::OpenParens {
-                            break;
-                        }
-
-                        let last_operator = operator_stack.pop();
-
-                        let last_operator = last_operator.map(|operator| match operator {
-                            Token::Add => Operator::Add,
-                            Token::Multiply => Operator::Multiply,
-                            _ => unreachable!(),
-                        });
-                        let last_operator = last_operator.map(StackElement::Operator);
-
-                        if let Some(last_operator) = last_operator {
-                            output.push(last_operator);
-                        }
-                    }
-                    operator_stack.push(*token);
-                }
-            }
-            Token::OpenParens => {
-                operator_stack.push(Token::OpenParens);
-            }
-            Token::CloseParens => {
-                while operator_stack.last() != Some(&Token::OpenParens) {
-                    let operator_to_move_to_output = operator_stack.pop().unwrap();
-
-                    match operator_to_move_to_output {
-                        Token::Add => output.push(StackElement::Operator(Operator::Add)),
-                        Token::Multiply => output.push(StackElement::Operator(Operator::Multiply)),
-                        _ => unreachable!(),
-                    }
-                }
-
-                if operator_stack.last() == Some(&Token::OpenParens) {
-                    operator_stack.pop();
-                }
-            }
-        }
-    }
-
-    while !operator_stack.is_empty() {
-        let element = operator_stack.pop().unwrap();
-        let element = match element {
-            Token::Add => StackElement::Operator(Operator::Add),
-            Token::Multiply => StackElement::Operator(Operator::Multiply),
-            _ => unreachable!(),
-        };
-
-        output.push(element);
-    }
-
-    output
-}
-
-fn evaluate_reverse_polish_expression(expression: ReversePolishExpression) -> u64 {
-    let mut evaluation_stack = Vec::new();
-
-    // todo terminology
-    for stack_element in expression {
-        match stack_element {
-            StackElement::Operand(number) => evaluation_stack.push(number),
-            StackElement::Operator(operator) => {
-                let first = evaluation_stack.pop().unwrap();
-                let second = evaluation_stack.pop().unwrap();
-
-                let result = match operator {
-                    Operator::Add => first + second,
-                    Operator::Multiply => first * second,
-                };
-
-                evaluation_stack.push(result);
-            }
-        }
-    }
-
-    evaluation_stack.pop().unwrap()
+    Ok((sum, elapsed).into())
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Operator {
-    Add,
-    Multiply,
-}
-
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum Token {
-    Number(u64),
-    Add,
-    Multiply,
-    OpenParens,
-    CloseParens,
-}
-
-fn parse_tokenized_expressions(input: &str) -> Vec<TokenizedExpression> {
-    input
-        .split('\n')
-        .filter(|s| !s.is_empty())
-        .map(tokenize)
-        .collect()
-}
-
-fn tokenize(i: &str) -> TokenizedExpression {
-    let (_, tokens) = tokens(i).unwrap();
+fn part_two(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
+    let start = SystemTime::now();
 
-    tokens
-}
+    let table = addition_first();
 
-fn tokens(i: &str) -> IResult<&str, TokenizedExpression> {
-    many1(token_stripping_whitespace)(i)
-}
+    let sum = sum_expressions(&parse_expressions(input, &table)?)?;
 
-fn token_stripping_whitespace(i: &str) -> IResult<&str, Token> {
-    let parser = preceded(space, token);
-    let mut parser = terminated(parser, space);
+    let elapsed = start.elapsed().unwrap();
 
-    parser(i)
+    Ok((sum, elapsed).into())
 }
 
-fn token(i: &str) -> IResult<&str, Token> {
-    alt((number, add, multiply, open_parens, close_parens))(i)
+/// Addition and multiplication have the same precedence and are evaluated left to right
+fn left_to_right() -> OperatorTable {
+    OperatorTable::new()
+        .with(BinaryOperator::Add, 1)
+        .with(BinaryOperator::Multiply, 1)
 }
 
-fn number(i: &str) -> IResult<&str, Token> {
-    map(map_res(digit1, |s: &str| s.parse::<u64>()), Token::Number)(i)
+/// Addition is evaluated before multiplication
+fn addition_first() -> OperatorTable {
+    OperatorTable::new()
+        .with(BinaryOperator::Add, 2)
+        .with(BinaryOperator::Multiply, 1)
 }
 
-fn add(i: &str) -> IResult<&str, Token> {
-    value(Token::Add, tag("+"))(i)
-}
+fn sum_expressions(expressions: &[Expr]) -> Result<u64, AdventOfCodeError> {
+    let environment = Environment::new();
 
-fn multiply(i: &str) -> IResult<&str, Token> {
-    value(Token::Multiply, tag("*"))(i)
-}
+    expressions.iter().try_fold(0u64, |sum, expression| {
+        let value = environment.evaluate(expression)?;
 
-fn open_parens(i: &str) -> IResult<&str, Token> {
-    value(Token::OpenParens, tag("("))(i)
+        u64::try_from(value)
+            .ok()
+            .and_then(|value| sum.checked_add(value))
+            .ok_or_else(|| AdventOfCodeError::Overflow(format!("{} = {}", expression, value)))
+    })
 }
 
-fn close_parens(i: &str) -> IResult<&str, Token> {
-    value(Token::CloseParens, tag(")"))(i)
+fn parse_expressions(input: &str, table: &OperatorTable) -> Result<Vec<Expr>, AdventOfCodeError> {
+    finish(|i| expressions(i, table))(input)
+        .map(|(_, expressions)| expressions)
+        .map_err(|_| AdventOfCodeError::NomParseError)
 }
 
-fn space(i: &str) -> IResult<&str, ()> {
-    value((), multispace0)(i)
+fn expressions<'a>(i: &'a str, table: &OperatorTable) -> IResult<&'a str, Vec<Expr>> {
+    many1(terminated(|i| table.parse_expression(i), multispace0))(i)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn evaluate(input: &str, table: &OperatorTable) -> u64 {
+        sum_expressions(&parse_expressions(input, table).unwrap()).unwrap()
+    }
+
     #[test]
     fn test_evaluate_part_one() {
-        let parse_and_evaluate = |input: &str| {
-            let expression = tokenize(input);
-            evaluate_expression(&expression, false)
-        };
+        let table = left_to_right();
+
+        let parse_and_evaluate = |input: &str| evaluate(input, &table);
 
         assert_eq!(parse_and_evaluate("1 + 2 * 3 + 4 * 5 + 6"), 71);
         assert_eq!(parse_and_evaluate("1 + (2 * 3) + (4 * (5 + 6))"), 51);
@@ -263,10 +105,9 @@ mod tests {
 
     #[test]
     fn test_evaluate_part_two() {
-        let parse_and_evaluate = |input: &str| {
-            let expression = tokenize(input);
-            evaluate_expression(&expression, true)
-        };
+        let table = addition_first();
+
+        let parse_and_evaluate = |input: &str| evaluate(input, &table);
 
         assert_eq!(parse_and_evaluate("1 + 2 * 3 + 4 * 5 + 6"), 231);
         assert_eq!(parse_and_evaluate("1 + (2 * 3) + (4 * (5 + 6))"), 51);
@@ -283,61 +124,36 @@ mod tests {
     }
 
     #[test]
-    fn test_evaluate_reverse_polish_notation() {
-        let expression = vec![
-            StackElement::Operand(2),
-            StackElement::Operand(3),
-            StackElement::Operand(4),
-            StackElement::Operator(Operator::Add),
-            StackElement::Operator(Operator::Add),
-        ];
-
-        assert_eq!(evaluate_reverse_polish_expression(expression), 9);
-    }
-
-    #[test]
-    fn test_shunting_yard() {
+    fn test_parse_expressions() {
         assert_eq!(
-            shunting_yard(&[Token::Number(3), Token::Add, Token::Number(4)], false),
-            vec![
-                StackElement::Operand(3),
-                StackElement::Operand(4),
-                StackElement::Operator(Operator::Add)
-            ]
+            parse_expressions("1 + 2\n(3 * 4)\n", &addition_first()),
+            Ok(vec![
+                Expr::binary(Expr::Number(1), BinaryOperator::Add, Expr::Number(2)),
+                Expr::binary(Expr::Number(3), BinaryOperator::Multiply, Expr::Number(4)),
+            ])
         );
 
         assert_eq!(
-            shunting_yard(
-                &[
-                    Token::Number(3),
-                    Token::Add,
-                    Token::Number(4),
-                    Token::Multiply,
-                    Token::Number(2)
-                ],
-                false
-            ),
-            vec![
-                StackElement::Operand(3),
-                StackElement::Operand(4),
-                StackElement::Operator(Operator::Add),
-                StackElement::Operand(2),
-                StackElement::Operator(Operator::Multiply)
-            ]
+            parse_expressions("1 +\n", &addition_first()),
+            Err(AdventOfCodeError::NomParseError)
         );
     }
 
     #[test]
-    fn test_tokenize() {
-        assert_eq!(
-            tokenize("3 + 4 * 2"),
-            vec![
-                Token::Number(3),
-                Token::Add,
-                Token::Number(4),
-                Token::Multiply,
-                Token::Number(2)
-            ]
-        );
+    fn test_sum_expressions_errors() {
+        let negative = Expr::binary(Expr::Number(1), BinaryOperator::Subtract, Expr::Number(2));
+
+        assert!(matches!(
+            sum_expressions(&[negative]),
+            Err(AdventOfCodeError::Overflow(_))
+        ));
+
+        let division_by_zero =
+            Expr::binary(Expr::Number(1), BinaryOperator::Divide, Expr::Number(0));
+
+        assert!(matches!(
+            sum_expressions(&[division_by_zero]),
+            Err(AdventOfCodeError::CannotEvaluate(_))
+        ));
     }
 }
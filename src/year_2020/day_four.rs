@@ -1,16 +1,18 @@
 use crate::common::answer::*;
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_till, take_while_m_n},
-    character::complete::{digit1, hex_digit1},
-    character::{is_digit, is_newline, is_space},
-    combinator::{all_consuming, map, success, value},
+    bytes::complete::{tag, take_till1},
+    character::complete::alpha1,
+    character::{is_newline, is_space},
+    combinator::{all_consuming, map, value},
     multi::{many0, separated_list1},
-    sequence::{preceded, separated_pair, terminated},
+    sequence::{separated_pair, terminated},
     IResult,
 };
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    ops::RangeInclusive,
     time::{Duration, SystemTime},
 };
 
@@ -27,7 +29,8 @@ pub fn run() -> AdventOfCodeResult {
 
 fn part_one(passports: &[Passport], parse_duration: u128) -> PartAnswer {
     let start = SystemTime::now();
-    let answer = passports.len();
+    let schema = Schema::required_keys();
+    let answer = count_valid(&schema, passports);
     let elapsed_ms = start.elapsed().unwrap().as_millis();
 
     let total_elapsed = Duration::from_millis((elapsed_ms + parse_duration) as u64);
@@ -37,12 +40,8 @@ fn part_one(passports: &[Passport], parse_duration: u128) -> PartAnswer {
 
 fn part_two(passports: &[Passport], parse_duration: u128) -> PartAnswer {
     let start = SystemTime::now();
-    let mut counter: u32 = 0;
-    for passport in passports {
-        if passport.is_valid() {
-            counter += 1;
-        }
-    }
+    let schema = Schema::strict();
+    let counter = count_valid(&schema, passports) as u32;
     let elapsed = start.elapsed().unwrap().as_millis();
 
     let total_elapsed = elapsed + parse_duration;
@@ -52,6 +51,13 @@ fn part_two(passports: &[Passport], parse_duration: u128) -> PartAnswer {
     (counter, total_elapsed).into()
 }
 
+fn count_valid(schema: &Schema, passports: &[Passport]) -> usize {
+    passports
+        .iter()
+        .filter(|passport| schema.validate(passport).is_empty())
+        .count()
+}
+
 fn parse_passports() -> Result<Vec<Passport>, AdventOfCodeError> {
     let input = include_str!("input/day-4.txt");
 
@@ -60,308 +66,326 @@ fn parse_passports() -> Result<Vec<Passport>, AdventOfCodeError> {
     Ok(passports)
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Copy, Clone)]
-enum KeyType {
-    BirthYear,
-    IssueYear,
-    ExpirationYear,
-    Height,
-    HairColor,
-    EyeColor,
-    PassportId,
-    CountryId,
+/// The fields of a passport as they were written, before anything has checked them
+type Passport = BTreeMap<String, String>;
+
+/// What a field's value has to look like
+#[derive(Debug, Clone)]
+enum Rule {
+    Any,
+    Integer(RangeInclusive<u32>),
+    /// An integer followed by one of the units, with each unit having its own range
+    Measurement(Vec<(&'static str, RangeInclusive<u32>)>),
+    Pattern(Pattern),
+    OneOf(Vec<&'static str>),
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum Value {
-    Valid,
-    Invalid,
-}
+impl Rule {
+    fn check(&self, key: &'static str, value: &str) -> Option<Violation> {
+        self.problem(value).map(|problem| Violation::Invalid {
+            key,
+            value: value.to_string(),
+            problem,
+        })
+    }
 
-#[derive(Debug, PartialEq)]
-struct KeyValue {
-    key_type: KeyType,
-    value: Value,
+    fn problem(&self, value: &str) -> Option<Problem> {
+        match self {
+            Rule::Any => None,
+            Rule::Integer(range) => match value.parse::<u32>() {
+                Ok(integer) if range.contains(&integer) => None,
+                Ok(_) => Some(Problem::OutOfRange(range.clone())),
+                Err(_) => Some(Problem::NotAnInteger),
+            },
+            Rule::Measurement(units) => {
+                let unit = units.iter().find(|(unit, _)| value.ends_with(unit));
+
+                match unit {
+                    Some((unit, range)) => {
+                        let magnitude = &value[..value.len() - unit.len()];
+
+                        Rule::Integer(range.clone()).problem(magnitude)
+                    }
+                    None => Some(Problem::UnknownUnit),
+                }
+            }
+            Rule::Pattern(pattern) => {
+                if pattern.matches(value) {
+                    None
+                } else {
+                    Some(Problem::NoMatch(pattern.clone()))
+                }
+            }
+            Rule::OneOf(options) => {
+                if options.contains(&value) {
+                    None
+                } else {
+                    Some(Problem::NotOneOf(options.clone()))
+                }
+            }
+        }
+    }
 }
 
-impl From<(KeyType, Value)> for KeyValue {
-    fn from(kv: (KeyType, Value)) -> KeyValue {
-        let (key_type, value) = kv;
-        KeyValue { key_type, value }
-    }
+/**
+ * A regular expression cut down to what passport fields need: literal characters and bracketed
+ * classes of characters and ranges, each optionally repeated with `{n}` or `{n,m}`
+ *
+ * A pattern has to match the whole value.
+ */
+#[derive(Debug, PartialEq, Clone)]
+struct Pattern {
+    source: &'static str,
+    tokens: Vec<Token>,
 }
 
-#[derive(Debug, PartialEq)]
-struct Passport {
-    birth_year: Value,
-    issue_year: Value,
-    expiration_year: Value,
-    height: Value,
-    hair_color: Value,
-    eye_color: Value,
-    passport_id: Value,
+#[derive(Debug, PartialEq, Clone)]
+struct Token {
+    class: Vec<RangeInclusive<char>>,
+    repeats: RangeInclusive<usize>,
 }
 
-impl Passport {
-    #[allow(clippy::needless_bool, clippy::if_same_then_else)]
-    pub fn is_valid(&self) -> bool {
-        if self.birth_year == Value::Invalid {
-            false
-        } else if self.issue_year == Value::Invalid {
-            false
-        } else if self.expiration_year == Value::Invalid {
-            false
-        } else if self.height == Value::Invalid {
-            false
-        } else if self.hair_color == Value::Invalid {
-            false
-        } else if self.eye_color == Value::Invalid {
-            false
-        } else if self.passport_id == Value::Invalid {
-            false
-        } else {
-            true
+impl Pattern {
+    fn new(source: &'static str) -> Pattern {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '[' => {
+                    let mut class = Vec::new();
+
+                    loop {
+                        let start = chars.next().expect("unclosed character class");
+
+                        if start == ']' {
+                            break;
+                        }
+
+                        if chars.peek() == Some(&'-') {
+                            chars.next();
+                            let end = chars.next().expect("unfinished character range");
+                            class.push(start..=end);
+                        } else {
+                            class.push(start..=start);
+                        }
+                    }
+
+                    tokens.push(Token::once(class));
+                }
+                '{' => {
+                    let bounds: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                    let bounds: Vec<usize> = bounds
+                        .split(',')
+                        .map(|bound| bound.parse().expect("repeat bounds should be numbers"))
+                        .collect();
+
+                    let token = tokens.last_mut().expect("nothing to repeat");
+                    token.repeats = bounds[0]..=*bounds.last().unwrap();
+                }
+                _ => tokens.push(Token::once(vec![c..=c])),
+            }
         }
-    }
-}
 
-fn passports(i: &str) -> IResult<&str, Vec<Passport>> {
-    let (remaining, passports) =
-        terminated(separated_list1(tag("\n\n"), passport), many0(tag("\n")))(i)?;
+        Pattern { source, tokens }
+    }
 
-    let passports = passports.into_iter().flatten().collect();
+    fn matches(&self, value: &str) -> bool {
+        let chars: Vec<char> = value.chars().collect();
 
-    Ok((remaining, passports))
+        matches_from(&self.tokens, &chars)
+    }
 }
 
-fn passport(i: &str) -> IResult<&str, Option<Passport>> {
-    let key_values = separated_list1(space_or_newline, key_value);
+/// Tries every number of repeats the first token allows against the start of `chars`
+fn matches_from(tokens: &[Token], chars: &[char]) -> bool {
+    let (token, rest) = match tokens.split_first() {
+        Some(split) => split,
+        None => return chars.is_empty(),
+    };
 
-    map(key_values, to_passport)(i)
-}
+    let matching = chars
+        .iter()
+        .take(*token.repeats.end())
+        .take_while(|c| token.class.iter().any(|range| range.contains(c)))
+        .count();
 
-fn to_passport(kvs: Vec<KeyValue>) -> Option<Passport> {
-    let mut map = kvs
-        .into_iter()
-        .map(|kv| (kv.key_type, kv.value))
-        .collect::<HashMap<KeyType, Value>>();
-
-    let birth_year = map.remove(&KeyType::BirthYear)?;
-    let issue_year = map.remove(&KeyType::IssueYear)?;
-    let expiration_year = map.remove(&KeyType::ExpirationYear)?;
-    let height = map.remove(&KeyType::Height)?;
-    let hair_color = map.remove(&KeyType::HairColor)?;
-    let eye_color = map.remove(&KeyType::EyeColor)?;
-    let passport_id = map.remove(&KeyType::PassportId)?;
-
-    Some(Passport {
-        birth_year,
-        issue_year,
-        expiration_year,
-        height,
-        hair_color,
-        eye_color,
-        passport_id,
-    })
+    (*token.repeats.start()..=matching)
+        .rev()
+        .any(|repeats| matches_from(rest, &chars[repeats..]))
 }
 
-fn key_value(i: &str) -> IResult<&str, KeyValue> {
-    alt((
-        birth_year,
-        issue_year,
-        expiration_year,
-        height,
-        hair_color,
-        eye_color,
-        passport_id,
-        country_id,
-    ))(i)
+impl Token {
+    fn once(class: Vec<RangeInclusive<char>>) -> Token {
+        Token {
+            class,
+            repeats: 1..=1,
+        }
+    }
 }
 
-fn birth_year(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, value) = unvalidated_value("byr")(i)?;
-
-    let value = value
-        .parse::<u32>()
-        .map(|year| {
-            if !(1920..=2002).contains(&year) {
-                Value::Invalid
-            } else {
-                Value::Valid
-            }
-        })
-        .unwrap_or(Value::Invalid);
-
-    Ok((
-        remaining,
-        KeyValue {
-            key_type: KeyType::BirthYear,
-            value,
-        },
-    ))
+impl Display for Pattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
 }
 
-fn issue_year(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, value) = unvalidated_value("iyr")(i)?;
-
-    let value = value
-        .parse::<u32>()
-        .map(|year| {
-            if !(2010..=2020).contains(&year) {
-                Value::Invalid
-            } else {
-                Value::Valid
-            }
-        })
-        .unwrap_or(Value::Invalid);
-
-    let key_value = KeyValue {
-        key_type: KeyType::IssueYear,
-        value,
-    };
-    Ok((remaining, key_value))
+#[derive(Debug, Clone)]
+struct FieldRule {
+    key: &'static str,
+    required: bool,
+    rule: Rule,
 }
 
-fn expiration_year(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, value) = unvalidated_value("eyr")(i)?;
-
-    let value = value
-        .parse::<u32>()
-        .map(|year| {
-            if !(2020..=2030).contains(&year) {
-                Value::Invalid
-            } else {
-                Value::Valid
-            }
-        })
-        .unwrap_or(Value::Invalid);
+/// The fields a passport can have, and the rules for each of them
+#[derive(Debug, Clone, Default)]
+struct Schema {
+    fields: Vec<FieldRule>,
+}
 
-    let key_value = KeyValue {
-        key_type: KeyType::ExpirationYear,
-        value,
-    };
+impl Schema {
+    fn new() -> Schema {
+        Schema { fields: Vec::new() }
+    }
 
-    Ok((remaining, key_value))
-}
+    fn required(mut self, key: &'static str, rule: Rule) -> Schema {
+        self.fields.push(FieldRule {
+            key,
+            required: true,
+            rule,
+        });
+        self
+    }
 
-fn height(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, value) = unvalidated_value("hgt")(i)?;
+    fn optional(mut self, key: &'static str, rule: Rule) -> Schema {
+        self.fields.push(FieldRule {
+            key,
+            required: false,
+            rule,
+        });
+        self
+    }
 
-    let centimeters = terminated(digit1, tag("cm"));
-    let centimeters = map(centimeters, |s: &str| s.parse::<u32>());
-    let centimeters = map(centimeters, |result| {
-        result
-            .map(|cm| {
-                if !(150..=193).contains(&cm) {
-                    Value::Invalid
-                } else {
-                    Value::Valid
-                }
-            })
-            .unwrap_or(Value::Invalid)
-    });
-    let inches = terminated(digit1, tag("in"));
-    let inches = map(inches, |s: &str| s.parse::<u32>());
-    let inches = map(inches, |result| {
-        result
-            .map(|height| {
-                if !(59..=76).contains(&height) {
-                    Value::Invalid
+    /// Every field but the country has to be there, whatever it holds
+    fn required_keys() -> Schema {
+        Schema::strict()
+            .fields
+            .into_iter()
+            .fold(Schema::new(), |schema, field| {
+                if field.required {
+                    schema.required(field.key, Rule::Any)
                 } else {
-                    Value::Valid
+                    schema.optional(field.key, Rule::Any)
                 }
             })
-            .unwrap_or(Value::Invalid)
-    });
-
-    let (_, kv) = map(
-        alt((centimeters, inches, success(Value::Invalid))),
-        |value| KeyValue {
-            key_type: KeyType::Height,
-            value,
-        },
-    )(value)?;
-
-    Ok((remaining, kv))
-}
-
-fn hair_color(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, raw) = unvalidated_value("hcl")(i)?;
-
-    let value: IResult<&str, Value> =
-        all_consuming(value(Value::Valid, preceded(tag("#"), hex_digit1)))(raw);
-    let value = value.map(|(_, v)| v);
-    let value = value.unwrap_or(Value::Invalid);
-
-    let key_value = KeyValue {
-        key_type: KeyType::HairColor,
-        value,
-    };
-
-    Ok((remaining, key_value))
-}
-
-fn eye_color(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, raw) = unvalidated_value("ecl")(i)?;
+    }
 
-    let amb = tag("amb");
-    let blu = tag("blu");
-    let brn = tag("brn");
-    let gry = tag("gry");
-    let grn = tag("grn");
-    let hzl = tag("hzl");
-    let oth = tag("oth");
+    fn strict() -> Schema {
+        Schema::new()
+            .required("byr", Rule::Integer(1920..=2002))
+            .required("iyr", Rule::Integer(2010..=2020))
+            .required("eyr", Rule::Integer(2020..=2030))
+            .required(
+                "hgt",
+                Rule::Measurement(vec![("cm", 150..=193), ("in", 59..=76)]),
+            )
+            .required("hcl", Rule::Pattern(Pattern::new("#[0-9a-f]{6}")))
+            .required(
+                "ecl",
+                Rule::OneOf(vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]),
+            )
+            .required("pid", Rule::Pattern(Pattern::new("[0-9]{9}")))
+            .optional("cid", Rule::Any)
+    }
 
-    let color = alt((amb, blu, brn, gry, grn, hzl, oth));
-    let color = value(Value::Valid, color);
+    /// Every rule the passport breaks, which is empty for a valid passport
+    fn validate(&self, passport: &Passport) -> Vec<Violation> {
+        let mut violations = Vec::new();
 
-    let result: IResult<&str, Value> = all_consuming(color)(raw);
-    let result = result.map(|(_, v)| v);
-    let result = result.unwrap_or(Value::Invalid);
+        for field in &self.fields {
+            match passport.get(field.key) {
+                Some(value) => violations.extend(field.rule.check(field.key, value)),
+                None if field.required => violations.push(Violation::Missing(field.key)),
+                None => {}
+            }
+        }
 
-    let key_value = KeyValue {
-        key_type: KeyType::EyeColor,
-        value: result,
-    };
+        for key in passport.keys() {
+            if !self.fields.iter().any(|field| field.key == key) {
+                violations.push(Violation::UnknownKey(key.clone()));
+            }
+        }
 
-    Ok((remaining, key_value))
+        violations
+    }
 }
 
-fn passport_id(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, raw) = unvalidated_value("pid")(i)?;
-
-    let digits = take_while_m_n(9, 9, |c: char| is_digit(c as u8));
-    let digits = all_consuming(digits);
-    let mut digits = value(Value::Valid, digits);
-    let result: IResult<&str, Value> = digits(raw);
-
-    let value = result.map(|(_, v)| v).unwrap_or(Value::Invalid);
-
-    let key_value = KeyValue {
-        key_type: KeyType::PassportId,
-        value,
-    };
+#[derive(Debug, PartialEq)]
+enum Violation {
+    Missing(&'static str),
+    UnknownKey(String),
+    Invalid {
+        key: &'static str,
+        value: String,
+        problem: Problem,
+    },
+}
 
-    Ok((remaining, key_value))
+#[derive(Debug, PartialEq)]
+enum Problem {
+    NotAnInteger,
+    OutOfRange(RangeInclusive<u32>),
+    UnknownUnit,
+    NoMatch(Pattern),
+    NotOneOf(Vec<&'static str>),
 }
 
-fn country_id(i: &str) -> IResult<&str, KeyValue> {
-    let (remaining, value) = value(Value::Valid, unvalidated_value("cid"))(i)?;
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Missing(key) => write!(f, "{} is missing", key),
+            Violation::UnknownKey(key) => write!(f, "{} is not a passport field", key),
+            Violation::Invalid {
+                key,
+                value,
+                problem,
+            } => write!(f, "{}:{} {}", key, value, problem),
+        }
+    }
+}
 
-    let key_value = KeyValue {
-        key_type: KeyType::CountryId,
-        value,
-    };
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::NotAnInteger => write!(f, "is not an integer"),
+            Problem::OutOfRange(range) => {
+                write!(f, "is outside {}..={}", range.start(), range.end())
+            }
+            Problem::UnknownUnit => write!(f, "has no known unit"),
+            Problem::NoMatch(pattern) => write!(f, "does not match {}", pattern),
+            Problem::NotOneOf(options) => write!(f, "is not one of {}", options.join(", ")),
+        }
+    }
+}
 
-    Ok((remaining, key_value))
+fn passports(i: &str) -> IResult<&str, Vec<Passport>> {
+    all_consuming(terminated(
+        separated_list1(tag("\n\n"), passport),
+        many0(tag("\n")),
+    ))(i)
 }
 
-fn unvalidated_value<'a>(key_name: &'a str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
-    map(separated_pair(tag(key_name), tag(":"), any), |(_, v)| v)
+fn passport(i: &str) -> IResult<&str, Passport> {
+    map(separated_list1(space_or_newline, field), |fields| {
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    })(i)
 }
 
-fn any(i: &str) -> IResult<&str, &str> {
-    take_till(is_whitespace)(i)
+fn field(i: &str) -> IResult<&str, (&str, &str)> {
+    separated_pair(alpha1, tag(":"), take_till1(is_whitespace))(i)
 }
 
 fn is_whitespace(c: char) -> bool {
@@ -376,196 +400,142 @@ fn space_or_newline(i: &str) -> IResult<&str, ()> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_birth_year() {
-        assert_eq!(
-            birth_year("byr:1940"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::BirthYear,
-                    value: Value::Valid
-                }
-            ))
-        );
+    fn check(key: &'static str, value: &str) -> Option<Violation> {
+        let schema = Schema::strict();
+        let field = schema.fields.iter().find(|field| field.key == key).unwrap();
+
+        field.rule.check(key, value)
     }
 
-    #[test]
-    fn test_issue_year() {
-        assert_eq!(
-            issue_year("iyr:2015"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::IssueYear,
-                    value: Value::Valid
-                }
-            ))
-        );
+    fn invalid(key: &'static str, value: &str, problem: Problem) -> Option<Violation> {
+        Some(Violation::Invalid {
+            key,
+            value: value.into(),
+            problem,
+        })
     }
 
     #[test]
-    fn test_expiration_year() {
+    fn test_years() {
+        assert_eq!(check("byr", "1940"), None);
+        assert_eq!(check("iyr", "2015"), None);
+        assert_eq!(check("eyr", "2029"), None);
+
         assert_eq!(
-            expiration_year("eyr:2029"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::ExpirationYear,
-                    value: Value::Valid
-                }
-            ))
+            check("byr", "2003"),
+            invalid("byr", "2003", Problem::OutOfRange(1920..=2002))
+        );
+        assert_eq!(
+            check("eyr", "soon"),
+            invalid("eyr", "soon", Problem::NotAnInteger)
         );
     }
 
     #[test]
     fn test_height() {
-        assert_eq!(
-            height("hgt:180cm"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::Height,
-                    value: Value::Valid
-                }
-            ))
-        );
+        assert_eq!(check("hgt", "180cm"), None);
+        assert_eq!(check("hgt", "70in"), None);
 
         assert_eq!(
-            height("hgt:70in"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::Height,
-                    value: Value::Valid
-                }
-            ))
+            check("hgt", "190in"),
+            invalid("hgt", "190in", Problem::OutOfRange(59..=76))
         );
-
         assert_eq!(
-            height("hgt:125"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::Height,
-                    value: Value::Invalid
-                }
-            ))
+            check("hgt", "125"),
+            invalid("hgt", "125", Problem::UnknownUnit)
         );
     }
 
     #[test]
     fn test_hair_color() {
+        assert_eq!(check("hcl", "#00aaff"), None);
+
+        let pattern = Pattern::new("#[0-9a-f]{6}");
+
         assert_eq!(
-            hair_color("hcl:#00aaff"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::HairColor,
-                    value: Value::Valid
-                }
-            ))
+            check("hcl", "z"),
+            invalid("hcl", "z", Problem::NoMatch(pattern.clone()))
         );
-
         assert_eq!(
-            hair_color("hcl:z"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::HairColor,
-                    value: Value::Invalid
-                }
-            ))
+            check("hcl", "#123abz"),
+            invalid("hcl", "#123abz", Problem::NoMatch(pattern))
         );
     }
 
     #[test]
     fn test_eye_color() {
-        assert_eq!(
-            eye_color("ecl:amb"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::EyeColor,
-                    value: Value::Valid
-                }
-            ))
-        );
+        assert_eq!(check("ecl", "amb"), None);
+        assert!(check("ecl", "wat").is_some());
     }
 
     #[test]
     fn test_passport_id() {
-        assert_eq!(
-            passport_id("pid:000000001"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::PassportId,
-                    value: Value::Valid
-                }
-            ))
-        );
+        assert_eq!(check("pid", "000000001"), None);
+        assert!(check("pid", "0123456789").is_some());
     }
 
     #[test]
-    fn test_country_id() {
-        assert_eq!(
-            country_id("cid:asdfdsadsf"),
-            Ok((
-                "",
-                KeyValue {
-                    key_type: KeyType::CountryId,
-                    value: Value::Valid
-                }
-            ))
-        );
+    fn test_pattern() {
+        let pattern = Pattern::new("a[b-d]{1,3}d");
+
+        assert!(pattern.matches("abd"));
+        assert!(pattern.matches("addd"));
+        assert!(pattern.matches("acccd"));
+        assert!(!pattern.matches("ad"));
+        assert!(!pattern.matches("abbbbd"));
+        assert!(!pattern.matches("abde"));
+        assert_eq!(pattern.to_string(), "a[b-d]{1,3}d");
     }
 
     #[test]
-    fn test_passport() {
-        let actual = passport(
+    fn test_validate() {
+        let (_, valid) = passport(
             "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm",
-        );
+        )
+        .unwrap();
 
-        let expected = Passport {
-            birth_year: Value::Valid,
-            issue_year: Value::Valid,
-            expiration_year: Value::Valid,
-            height: Value::Valid,
-            hair_color: Value::Valid,
-            eye_color: Value::Valid,
-            passport_id: Value::Valid,
-        };
+        assert_eq!(Schema::strict().validate(&valid), vec![]);
 
-        assert_eq!(actual, Ok(("", expected.into())));
+        let (_, missing_height) =
+            passport("iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929")
+                .unwrap();
+
+        assert_eq!(
+            Schema::required_keys().validate(&missing_height),
+            vec![Violation::Missing("hgt")]
+        );
 
-        let actual =
-            passport("iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929");
+        let (_, invalid) = passport("hcl:z byr:2026\npid:61805448\nhgt:125 iyr:2025").unwrap();
 
-        assert_eq!(actual, Ok(("", None)));
+        let violations: Vec<String> = Schema::strict()
+            .validate(&invalid)
+            .iter()
+            .map(|violation| violation.to_string())
+            .collect();
 
-        // hcl:z byr:2026\npid:61805448\nhgt:125 iyr:2025
-        let actual = passport("hcl:z byr:2026\npid:61805448\nhgt:125 iyr:2025");
-        assert_eq!(actual, Ok(("", None)));
+        assert_eq!(
+            violations,
+            vec![
+                "byr:2026 is outside 1920..=2002",
+                "iyr:2025 is outside 2010..=2020",
+                "eyr is missing",
+                "hgt:125 has no known unit",
+                "hcl:z does not match #[0-9a-f]{6}",
+                "ecl is missing",
+                "pid:61805448 does not match [0-9]{9}",
+            ]
+        );
     }
 
     #[test]
     fn test_passports() {
-        let actual = passports("ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm\n\niyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929");
-
-        let first = Passport {
-            birth_year: Value::Valid,
-            issue_year: Value::Valid,
-            expiration_year: Value::Valid,
-            height: Value::Valid,
-            hair_color: Value::Valid,
-            eye_color: Value::Valid,
-            passport_id: Value::Valid,
-        };
+        let actual = passports("ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm\n\niyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884\nhcl:#cfa07d byr:1929\n");
 
-        let expected = vec![first];
+        let (_, passports) = actual.unwrap();
 
-        assert_eq!(actual, Ok(("", expected)));
+        assert_eq!(passports.len(), 2);
+        assert_eq!(passports[0]["hgt"], "183cm");
+        assert_eq!(passports[1].len(), 7);
     }
 
     #[test]
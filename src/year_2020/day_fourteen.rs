@@ -1,15 +1,16 @@
 use crate::common::answer::*;
 
+use log::debug;
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    character::complete::digit1,
-    combinator::{map, map_res, value},
+    character::complete::{digit1, one_of},
+    combinator::{map, map_res},
     multi::{count, separated_list1},
     sequence::{delimited, preceded, tuple},
     IResult,
 };
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::time::{Duration, SystemTime};
 
 pub fn run() -> AdventOfCodeResult {
@@ -27,141 +28,218 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(instructions: &[Instruction], parse_duration: Duration) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut current_bitmask = Vec::new();
+    let mut current_bitmask = Bitmask::default();
     let mut memory = HashMap::new();
 
     for instruction in instructions {
         match instruction {
-            Instruction::SetMask(bitmask) => {
-                current_bitmask = bitmask.clone();
-            }
+            Instruction::SetMask(bitmask) => current_bitmask = *bitmask,
             Instruction::SetMemory(memory_value) => {
                 memory.insert(
                     memory_value.address,
-                    apply_mask_to_value(&current_bitmask, memory_value.value),
+                    current_bitmask.apply_to_value(memory_value.value),
                 );
             }
         }
     }
 
-    let solution: u64 = memory.values().into_iter().sum();
+    let solution: u64 = memory.values().sum();
 
     let elapsed = start.elapsed().unwrap();
 
     (solution, elapsed + parse_duration).into()
 }
 
-fn apply_mask_to_value(mask: &[MaskValue], value: u64) -> u64 {
-    let binary_string = integer_to_binary_string(value);
+fn part_two(instructions: &[Instruction], parse_duration: Duration) -> PartAnswer {
+    let start = SystemTime::now();
 
-    let zipped = mask.iter().zip(binary_string.chars());
+    let memory = run_floating_addresses(instructions);
 
-    let mut bits = Vec::new();
+    debug!("{} memory cells written", memory.written_cells());
 
-    for (mask_value, bit) in zipped {
-        match mask_value {
-            MaskValue::NoValue => bits.push(bit),
-            MaskValue::One => bits.push('1'),
-            MaskValue::Zero => bits.push('0'),
-        }
-    }
+    let solution = memory.sum();
 
-    let bit_string = bits.into_iter().collect::<String>();
+    let elapsed = start.elapsed().unwrap();
 
-    u64::from_str_radix(&bit_string, 2).unwrap()
+    (solution, elapsed + parse_duration).into()
 }
 
-fn part_two(instructions: &[Instruction], parse_duration: Duration) -> PartAnswer {
-    let start = SystemTime::now();
-
-    let mut current_mask = vec![];
-    let mut memory = HashMap::new();
+fn run_floating_addresses(instructions: &[Instruction]) -> SparseMemory {
+    let mut current_bitmask = Bitmask::default();
+    let mut memory = SparseMemory::default();
 
     for instruction in instructions {
         match instruction {
-            Instruction::SetMask(mask) => current_mask = mask.clone(),
+            Instruction::SetMask(bitmask) => current_bitmask = *bitmask,
             Instruction::SetMemory(memory_value) => {
-                let addresses = apply_mask_to_address(&current_mask, memory_value.address);
-
-                for address in addresses {
-                    memory.insert(address, memory_value.value);
-                }
+                memory.write(
+                    current_bitmask.apply_to_address(memory_value.address),
+                    memory_value.value,
+                );
             }
         }
     }
 
-    let solution: u64 = memory.values().into_iter().sum();
-
-    let elapsed = start.elapsed().unwrap();
-
-    (solution, elapsed + parse_duration).into()
+    memory
 }
 
-fn apply_mask_to_address(mask: &[MaskValue], address: u64) -> HashSet<u64> {
-    let bitstring_address = integer_to_binary_string(address);
+/**
+ * A mask as three sets of bits: those it sets to 1, those it clears to 0 and those it leaves
+ * floating, which is every bit marked X
+ */
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+struct Bitmask {
+    set: u64,
+    clear: u64,
+    floating: u64,
+}
 
-    let mut mask_without_floating_bits = Vec::new();
-    let mut floating_bit_locations = Vec::new();
+impl Bitmask {
+    fn from_chars(chars: &[char]) -> Bitmask {
+        let mut bitmask = Bitmask::default();
 
-    let zipped = mask.iter().zip(bitstring_address.chars());
+        for (index, c) in chars.iter().rev().enumerate() {
+            let bit = 1 << index;
 
-    for (index, (mask_value, address_char)) in zipped.enumerate() {
-        match mask_value {
-            MaskValue::NoValue => {
-                floating_bit_locations.push(mask.len() - 1 - index);
-                mask_without_floating_bits.push('0');
-            }
-            MaskValue::Zero => {
-                mask_without_floating_bits.push(address_char);
-            }
-            MaskValue::One => {
-                mask_without_floating_bits.push('1');
+            match c {
+                '1' => bitmask.set |= bit,
+                '0' => bitmask.clear |= bit,
+                _ => bitmask.floating |= bit,
             }
         }
+
+        bitmask
     }
 
-    let base: String = mask_without_floating_bits.into_iter().collect();
-    let base = u64::from_str_radix(&base, 2).unwrap();
+    /// The first version of the decoder overwrites bits of the value and ignores floating bits
+    fn apply_to_value(&self, value: u64) -> u64 {
+        (value | self.set) & !self.clear
+    }
 
-    let modifiers = powerset_modifiers(&floating_bit_locations);
+    /// The second version sets bits of the address, leaves zeroes alone and lets X bits float
+    fn apply_to_address(&self, address: u64) -> AddressPattern {
+        AddressPattern {
+            fixed: (address | self.set) & !self.floating,
+            floating: self.floating,
+        }
+    }
+}
 
-    modifiers.into_iter().map(|m| m + base).collect()
+/// Every address that matches `fixed` outside the bits in `floating`
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct AddressPattern {
+    fixed: u64,
+    floating: u64,
 }
 
-fn powerset_modifiers(floating_bits: &[usize]) -> HashSet<u64> {
-    let powerset_size = 2usize.pow(floating_bits.len() as u32);
+impl AddressPattern {
+    #[cfg(test)]
+    fn addresses(&self) -> impl Iterator<Item = u64> {
+        let fixed = self.fixed;
 
-    let mut numbers = HashSet::new();
+        subsets(self.floating).map(move |bits| fixed | bits)
+    }
 
-    for i in 0..powerset_size {
-        let mut current = 0;
+    fn len(&self) -> u64 {
+        1 << self.floating.count_ones()
+    }
 
-        for (j, floating_bit) in floating_bits.iter().enumerate() {
-            if i & (1 << j) != 0 {
-                let value = 2u64.pow(*floating_bit as u32);
-                current += value;
-            }
+    fn intersects(&self, other: &AddressPattern) -> bool {
+        (self.fixed ^ other.fixed) & !(self.floating | other.floating) == 0
+    }
+
+    /**
+     * The addresses in this pattern but not in `other`, as patterns that don't overlap each other
+     *
+     * Each bit that floats here but is fixed in `other` splits off the addresses that disagree with
+     * `other` on that bit, then is pinned to agree with it. What's left after the last one is
+     * inside `other`.
+     */
+    fn without(&self, other: &AddressPattern) -> Vec<AddressPattern> {
+        if !self.intersects(other) {
+            return vec![*self];
         }
 
-        numbers.insert(current);
+        let mut remaining = *self;
+        let mut pieces = Vec::new();
+
+        let mut splitting = self.floating & !other.floating;
+
+        while splitting != 0 {
+            let bit = splitting & splitting.wrapping_neg();
+            splitting &= splitting - 1;
+
+            let floating = remaining.floating & !bit;
+
+            pieces.push(AddressPattern {
+                fixed: remaining.fixed | (!other.fixed & bit),
+                floating,
+            });
+
+            remaining = AddressPattern {
+                fixed: remaining.fixed | (other.fixed & bit),
+                floating,
+            };
+        }
+
+        pieces
     }
+}
+
+/// Every subset of the bits in `mask`, starting from the empty set
+#[cfg(test)]
+fn subsets(mask: u64) -> impl Iterator<Item = u64> {
+    use std::iter::successors;
 
-    numbers
+    successors(Some(0), move |subset: &u64| {
+        let next = subset.wrapping_sub(mask) & mask;
+
+        if next == 0 {
+            None
+        } else {
+            Some(next)
+        }
+    })
 }
 
-fn integer_to_binary_string(integer: u64) -> String {
-    format!("{:036b}", integer)
+/**
+ * Memory written through floating addresses, kept as patterns that don't overlap so none of them
+ * has to be expanded
+ */
+#[derive(Debug, Default)]
+struct SparseMemory {
+    cells: Vec<(AddressPattern, u64)>,
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum MaskValue {
-    NoValue,
-    One,
-    Zero,
+impl SparseMemory {
+    fn write(&mut self, pattern: AddressPattern, value: u64) {
+        let mut cells = Vec::with_capacity(self.cells.len() + 1);
+
+        for (existing, existing_value) in &self.cells {
+            for piece in existing.without(&pattern) {
+                cells.push((piece, *existing_value));
+            }
+        }
+
+        cells.push((pattern, value));
+
+        self.cells = cells;
+    }
+
+    fn written_cells(&self) -> u64 {
+        self.cells.iter().map(|(pattern, _)| pattern.len()).sum()
+    }
+
+    fn sum(&self) -> u64 {
+        self.cells
+            .iter()
+            .map(|(pattern, value)| pattern.len() * value)
+            .sum()
+    }
 }
 
 enum Instruction {
-    SetMask(Vec<MaskValue>),
+    SetMask(Bitmask),
     SetMemory(MemoryValue),
 }
 
@@ -190,24 +268,11 @@ fn instruction(i: &str) -> IResult<&str, Instruction> {
     alt((bitmask, memory))(i)
 }
 
-fn bitmask(i: &str) -> IResult<&str, Vec<MaskValue>> {
-    preceded(tag("mask = "), count(mask_value, 36))(i)
-}
-
-fn mask_value(i: &str) -> IResult<&str, MaskValue> {
-    alt((empty_mask_value, one_mask_value, zero_mask_value))(i)
-}
-
-fn empty_mask_value(i: &str) -> IResult<&str, MaskValue> {
-    value(MaskValue::NoValue, tag("X"))(i)
-}
-
-fn one_mask_value(i: &str) -> IResult<&str, MaskValue> {
-    value(MaskValue::One, tag("1"))(i)
-}
-
-fn zero_mask_value(i: &str) -> IResult<&str, MaskValue> {
-    value(MaskValue::Zero, tag("0"))(i)
+fn bitmask(i: &str) -> IResult<&str, Bitmask> {
+    map(
+        preceded(tag("mask = "), count(one_of("X10"), 36)),
+        |chars| Bitmask::from_chars(&chars),
+    )(i)
 }
 
 fn memory(i: &str) -> IResult<&str, MemoryValue> {
@@ -228,8 +293,14 @@ fn integer(i: &str) -> IResult<&str, u64> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+
+    const EXAMPLE: &str = "mask = 000000000000000000000000000000X1001X
+mem[42] = 100
+mask = 00000000000000000000000000000000X0XX
+mem[26] = 1";
 
-    fn bitmask_string_to_mask_values(i: &str) -> Vec<MaskValue> {
+    fn parse_bitmask(i: &str) -> Bitmask {
         let (_, bitmask) = bitmask(i).unwrap();
         bitmask
     }
@@ -240,44 +311,11 @@ mod tests {
             bitmask("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X"),
             Ok((
                 "",
-                vec![
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::One,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::Zero,
-                    MaskValue::NoValue
-                ]
+                Bitmask {
+                    set: 0b1000000,
+                    clear: 0b10,
+                    floating: 0xF_FFFF_FFFF & !0b1000010
+                }
             ))
         );
     }
@@ -297,76 +335,25 @@ mod tests {
     }
 
     #[test]
-    fn test_integer_to_binary_string() {
-        assert_eq!(
-            integer_to_binary_string(11),
-            "000000000000000000000000000000001011"
-        );
-        assert_eq!(
-            integer_to_binary_string(73),
-            "000000000000000000000000000001001001"
-        );
-    }
+    fn test_apply_to_value() {
+        let bitmask = parse_bitmask("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X");
 
-    #[test]
-    fn test_apply_mask_to_value() {
-        assert_eq!(
-            apply_mask_to_value(
-                &[
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::One,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::NoValue,
-                    MaskValue::Zero,
-                    MaskValue::NoValue
-                ],
-                11
-            ),
-            73
-        );
+        assert_eq!(bitmask.apply_to_value(11), 73);
+        assert_eq!(bitmask.apply_to_value(101), 101);
+        assert_eq!(bitmask.apply_to_value(0), 64);
     }
 
     #[test]
-    fn test_apply_mask_to_address_recursive() {
-        let mask = bitmask_string_to_mask_values("mask = 000000000000000000000000000000X1001X");
+    fn test_apply_to_address() {
+        let bitmask = parse_bitmask("mask = 000000000000000000000000000000X1001X");
 
-        let addresses = apply_mask_to_address(&mask, 42);
+        let addresses: HashSet<u64> = bitmask.apply_to_address(42).addresses().collect();
 
         assert_eq!(addresses, vec![26, 27, 58, 59].into_iter().collect());
 
-        let mask = bitmask_string_to_mask_values("mask = 00000000000000000000000000000000X0XX");
+        let bitmask = parse_bitmask("mask = 00000000000000000000000000000000X0XX");
 
-        let addresses = apply_mask_to_address(&mask, 26);
+        let addresses: HashSet<u64> = bitmask.apply_to_address(26).addresses().collect();
 
         assert_eq!(
             addresses,
@@ -375,8 +362,41 @@ mod tests {
     }
 
     #[test]
-    fn test_powerset_modifiers() {
-        let expected = vec![0, 1, 2, 3, 8, 9, 10, 11].into_iter().collect();
-        assert_eq!(powerset_modifiers(&[0, 1, 3]), expected);
+    fn test_subsets() {
+        assert_eq!(
+            subsets(0b1011).collect::<Vec<u64>>(),
+            vec![0, 1, 2, 3, 8, 9, 10, 11]
+        );
+        assert_eq!(subsets(0).collect::<Vec<u64>>(), vec![0]);
+    }
+
+    #[test]
+    fn test_without() {
+        let pattern = AddressPattern {
+            fixed: 0b0000,
+            floating: 0b1011,
+        };
+        let other = AddressPattern {
+            fixed: 0b0010,
+            floating: 0b0001,
+        };
+
+        let remaining: HashSet<u64> = pattern
+            .without(&other)
+            .iter()
+            .flat_map(|piece| piece.addresses())
+            .collect();
+
+        assert_eq!(remaining, vec![0, 1, 8, 9, 10, 11].into_iter().collect());
+    }
+
+    #[test]
+    fn test_sparse_memory() {
+        let instructions = parse_instructions(EXAMPLE).unwrap();
+
+        let memory = run_floating_addresses(&instructions);
+
+        assert_eq!(memory.written_cells(), 10);
+        assert_eq!(memory.sum(), 208);
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, SystemTime};
 use crate::common::answer::*;
 use nom::{
@@ -10,7 +10,6 @@ use nom::{
     sequence::{delimited, preceded, separated_pair, terminated, tuple},
     IResult,
 };
-use regex::Regex;
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-19.txt");
@@ -19,116 +18,252 @@ pub fn run() -> AdventOfCodeResult {
 
     let parse_duration = parse_start.elapsed().unwrap();
 
-    let part_one = part_one(&rules_and_messages, parse_duration);
-    let part_two = part_two(&rules_and_messages, parse_duration);
+    let part_one = part_one(&rules_and_messages, parse_duration)?;
+    let part_two = part_two(&rules_and_messages, parse_duration)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(rules_and_messages: &RulesAndMessages, parse_duration: Duration) -> PartAnswer {
+fn part_one(
+    rules_and_messages: &RulesAndMessages,
+    parse_duration: Duration,
+) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let regexes_by_index = build_regular_expressions(&rules_and_messages.rules);
+    let grammar = Grammar::new(&rules_and_messages.rules)?;
 
-    let regex = format!("^{}$", &regexes_by_index[&0]);
-    let regex = Regex::new(&regex).unwrap();
-
-    let counter = count_matches(&rules_and_messages.messages, &regex);
+    let counter = count_matches(&rules_and_messages.messages, &grammar)?;
 
     let elapsed = start.elapsed().unwrap();
 
-    (counter, elapsed + parse_duration).into()
+    Ok((counter, elapsed + parse_duration).into())
 }
 
-fn part_two(rules_and_messages: &RulesAndMessages, parse_duration: Duration) -> PartAnswer {
+fn part_two(
+    rules_and_messages: &RulesAndMessages,
+    parse_duration: Duration,
+) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let regexes_by_index = build_regular_expressions(&rules_and_messages.rules);
+    let grammar = with_looping_rules(Grammar::new(&rules_and_messages.rules)?)?;
 
-    let rule_forty_two = &regexes_by_index[&42];
-    let rule_thirty_one = &regexes_by_index[&31];
+    let counter = count_matches(&rules_and_messages.messages, &grammar)?;
 
-    let new_rule_eleven = (1..=4)
-        .into_iter()
-        .map(|i| {
-            format!(
-                "(({}{{{}}})({}{{{}}}))",
-                rule_forty_two, i, rule_thirty_one, i
-            )
-        })
-        .collect::<Vec<String>>()
-        .join("|");
-    let new_rule_eleven = format!("({})", new_rule_eleven);
+    let elapsed = start.elapsed().unwrap();
 
-    let new_rule_eight = format!("({}+)", rule_forty_two);
+    Ok((counter, elapsed + parse_duration).into())
+}
 
-    let new_rule_zero = format!("^({})({})$", new_rule_eight, new_rule_eleven);
+/// Replaces rules 8 and 11 with the recursive versions from part two
+fn with_looping_rules(grammar: Grammar) -> Result<Grammar, AdventOfCodeError> {
+    let (_, rule_eight) =
+        referencing_rule("42 | 42 8").map_err(|_| AdventOfCodeError::NomParseError)?;
+    let (_, rule_eleven) =
+        referencing_rule("42 31 | 42 11 31").map_err(|_| AdventOfCodeError::NomParseError)?;
+
+    grammar
+        .with_rule(8, &rule_eight)?
+        .with_rule(11, &rule_eleven)
+}
 
-    let regex = Regex::new(&new_rule_zero).unwrap();
+/// The number of messages that completely match rule 0
+fn count_matches(messages: &[String], grammar: &Grammar) -> Result<u64, AdventOfCodeError> {
+    if !grammar.has_rule(0) {
+        return Err(AdventOfCodeError::InvalidInput(
+            "there's no rule 0 to match the messages against".to_string(),
+        ));
+    }
 
-    let counter = count_matches(&rules_and_messages.messages, &regex);
+    let mut counter = 0;
 
-    let elapsed = start.elapsed().unwrap();
+    for message in messages {
+        if grammar.matches(0, message) {
+            counter += 1;
+        }
+    }
 
-    (counter, elapsed + parse_duration).into()
+    Ok(counter)
 }
 
-fn build_regular_expressions(rules: &[Rule]) -> HashMap<usize, String> {
-    // first populate the terminal rules
-    // then populate the rules that only reference the initial terminal rules
-    // continue until all rules are populated
-    let mut terminating_rules = HashMap::new();
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Symbol {
+    Rule(usize),
+    Terminal(String),
+}
 
-    while terminating_rules.len() != rules.len() {
-        for rule in rules {
-            match &rule.rule_type {
-                RuleType::Terminal(s) => {
-                    terminating_rules.insert(rule.index, s.clone());
-                }
-                RuleType::Referencing(references) => {
-                    let all_reference_terminating_rule = references
-                        .iter()
-                        .flatten()
-                        .all(|index| terminating_rules.contains_key(index));
+/**
+ * The rules as a context-free grammar, with each rule a list of alternative sequences of symbols
+ *
+ * Messages are matched with an Earley recognizer, which copes with any recursion in the rules,
+ * including rules that refer to themselves at the start of an alternative. Every rule reference is
+ * checked when the grammar is built, so matching never looks up a rule that isn't there.
+ */
+#[derive(Debug, PartialEq, Clone)]
+struct Grammar {
+    alternatives_by_rule: HashMap<usize, Vec<Vec<Symbol>>>,
+}
+
+/// A partly matched alternative, `dot` symbols in, that started matching at `origin`
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct EarleyItem {
+    rule: usize,
+    alternative: usize,
+    dot: usize,
+    origin: usize,
+}
 
-                    if all_reference_terminating_rule {
-                        let mut regex_groups = Vec::new();
+impl EarleyItem {
+    fn advance(&self) -> EarleyItem {
+        EarleyItem {
+            dot: self.dot + 1,
+            ..*self
+        }
+    }
+}
+
+impl Grammar {
+    fn new(rules: &[Rule]) -> Result<Grammar, AdventOfCodeError> {
+        let mut grammar = Grammar {
+            alternatives_by_rule: HashMap::new(),
+        };
+
+        // rules can refer to ones further down, so they're only checked once they're all in
+        for rule in rules {
+            grammar.insert_rule(rule.index, &rule.rule_type);
+        }
 
-                        for list_of_references in references {
-                            let mut s = String::new();
-                            s += "(";
+        grammar.check_references()
+    }
 
-                            for reference in list_of_references {
-                                s += &terminating_rules[reference];
-                            }
+    /// Adds or replaces a rule, failing if the grammar then refers to a rule that isn't defined
+    fn with_rule(
+        mut self,
+        index: usize,
+        rule_type: &RuleType,
+    ) -> Result<Grammar, AdventOfCodeError> {
+        self.insert_rule(index, rule_type);
 
-                            s += ")";
+        self.check_references()
+    }
 
-                            regex_groups.push(s);
-                        }
+    fn insert_rule(&mut self, index: usize, rule_type: &RuleType) {
+        let alternatives = match rule_type {
+            RuleType::Terminal(s) => vec![vec![Symbol::Terminal(s.clone())]],
+            RuleType::Referencing(references) => references
+                .iter()
+                .map(|sequence| sequence.iter().cloned().map(Symbol::Rule).collect())
+                .collect(),
+        };
 
-                        let regex_group = format!("({})", regex_groups.join("|"));
+        self.alternatives_by_rule.insert(index, alternatives);
+    }
 
-                        terminating_rules.insert(rule.index, regex_group);
+    fn check_references(self) -> Result<Grammar, AdventOfCodeError> {
+        for (rule, alternatives) in self.alternatives_by_rule.iter() {
+            for symbol in alternatives.iter().flatten() {
+                if let Symbol::Rule(reference) = symbol {
+                    if !self.has_rule(*reference) {
+                        return Err(AdventOfCodeError::InvalidInput(format!(
+                            "rule {} refers to rule {}, which isn't defined",
+                            rule, reference
+                        )));
                     }
                 }
             }
         }
+
+        Ok(self)
     }
 
-    terminating_rules
-}
+    fn has_rule(&self, rule: usize) -> bool {
+        self.alternatives_by_rule.contains_key(&rule)
+    }
 
-fn count_matches(messages: &[String], regex: &Regex) -> u64 {
-    let mut counter = 0;
+    /// A rule that isn't defined has no alternatives, so it doesn't match anything
+    fn alternatives(&self, rule: usize) -> &[Vec<Symbol>] {
+        self.alternatives_by_rule
+            .get(&rule)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
 
-    for message in messages {
-        if regex.is_match(message) {
-            counter += 1;
-        }
+    fn next_symbol(&self, item: &EarleyItem) -> Option<&Symbol> {
+        self.alternatives(item.rule)[item.alternative].get(item.dot)
     }
 
-    counter
+    /**
+     * Whether all of `message` matches `rule`
+     *
+     * Every symbol consumes at least one character, so an item can only complete after its origin
+     * and completing never needs to look at the set it's being added to.
+     */
+    fn matches(&self, rule: usize, message: &str) -> bool {
+        let mut chart: Vec<Vec<EarleyItem>> = vec![vec![]; message.len() + 1];
+        let mut seen: Vec<HashSet<EarleyItem>> = vec![HashSet::new(); message.len() + 1];
+
+        let mut add = |chart: &mut Vec<Vec<EarleyItem>>, position: usize, item: EarleyItem| {
+            if seen[position].insert(item) {
+                chart[position].push(item);
+            }
+        };
+
+        for alternative in 0..self.alternatives(rule).len() {
+            let item = EarleyItem {
+                rule,
+                alternative,
+                dot: 0,
+                origin: 0,
+            };
+
+            add(&mut chart, 0, item);
+        }
+
+        for position in 0..=message.len() {
+            let mut index = 0;
+
+            while index < chart[position].len() {
+                let item = chart[position][index];
+                index += 1;
+
+                match self.next_symbol(&item) {
+                    Some(Symbol::Terminal(terminal)) => {
+                        if message[position..].starts_with(terminal.as_str()) {
+                            add(&mut chart, position + terminal.len(), item.advance());
+                        }
+                    }
+                    Some(Symbol::Rule(next_rule)) => {
+                        for alternative in 0..self.alternatives(*next_rule).len() {
+                            let predicted = EarleyItem {
+                                rule: *next_rule,
+                                alternative,
+                                dot: 0,
+                                origin: position,
+                            };
+
+                            add(&mut chart, position, predicted);
+                        }
+                    }
+                    None => {
+                        let waiting: Vec<EarleyItem> = chart[item.origin]
+                            .iter()
+                            .filter(|waiting| {
+                                self.next_symbol(waiting) == Some(&Symbol::Rule(item.rule))
+                            })
+                            .map(EarleyItem::advance)
+                            .collect();
+
+                        for advanced in waiting {
+                            add(&mut chart, position, advanced);
+                        }
+                    }
+                }
+            }
+        }
+
+        chart[message.len()]
+            .iter()
+            .any(|item| item.rule == rule && item.origin == 0 && self.next_symbol(item).is_none())
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -271,4 +406,137 @@ mod tests {
             ))
         );
     }
+
+    const EXAMPLE: &str = "0: 4 1 5
+1: 2 3 | 3 2
+2: 4 4 | 5 5
+3: 4 5 | 5 4
+4: \"a\"
+5: \"b\"
+
+ababbb
+bababa
+abbbab
+aaabbb
+aaaabbb";
+
+    const LOOPING_EXAMPLE: &str = "42: 9 14 | 10 1
+9: 14 27 | 1 26
+10: 23 14 | 28 1
+1: \"a\"
+11: 42 31
+5: 1 14 | 15 1
+19: 14 1 | 14 14
+12: 24 14 | 19 1
+16: 15 1 | 14 14
+31: 14 17 | 1 13
+6: 14 14 | 1 14
+2: 1 24 | 14 4
+0: 8 11
+13: 14 3 | 1 12
+15: 1 | 14
+17: 14 2 | 1 7
+23: 25 1 | 22 14
+28: 16 1
+4: 1 1
+20: 14 14 | 1 15
+3: 5 14 | 16 1
+27: 1 6 | 14 18
+14: \"b\"
+21: 14 1 | 1 14
+25: 1 1 | 1 14
+22: 14 14
+8: 42
+26: 14 22 | 1 20
+18: 15 15
+7: 14 5 | 1 21
+24: 14 1
+
+abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
+bbabbbbaabaabba
+babbbbaabbbbbabbbbbbaabaaabaaa
+aaabbbbbbaaaabaababaabababbabaaabbababababaaa
+bbbbbbbaaaabbbbaaabbabaaa
+bbbababbbbaaaaaaaabbababaaababaabab
+ababaaaaaabaaab
+ababaaaaabbbaba
+baabbaaaabbaaaababbaababb
+abbbbabbbbaaaababbbbbbaaaababb
+aaaaabbaabaaaaababaa
+aaaabbaaaabbaaa
+aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
+babaaabbbaaabaababbaabababaaab
+aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba";
+
+    #[test]
+    fn test_matches() {
+        let rules_and_messages = parse_rules_and_messages(EXAMPLE);
+        let grammar = Grammar::new(&rules_and_messages.rules).unwrap();
+
+        let matching: Vec<&str> = rules_and_messages
+            .messages
+            .iter()
+            .map(String::as_str)
+            .filter(|message| grammar.matches(0, message))
+            .collect();
+
+        assert_eq!(matching, vec!["ababbb", "abbbab"]);
+        assert!(grammar.matches(2, "aa"));
+        assert!(!grammar.matches(2, "ab"));
+        assert!(!grammar.matches(6, "a"));
+    }
+
+    #[test]
+    fn test_looping_rules() {
+        let rules_and_messages = parse_rules_and_messages(LOOPING_EXAMPLE);
+        let grammar = Grammar::new(&rules_and_messages.rules).unwrap();
+
+        assert_eq!(count_matches(&rules_and_messages.messages, &grammar), Ok(3));
+
+        let grammar = with_looping_rules(grammar).unwrap();
+
+        assert_eq!(
+            count_matches(&rules_and_messages.messages, &grammar),
+            Ok(12)
+        );
+        assert!(grammar.matches(0, "babbbbaabbbbbabbbbbbaabaaabaaa"));
+        assert!(!grammar.matches(0, "aaaabbaaaabbaaa"));
+    }
+
+    #[test]
+    fn test_left_recursion() {
+        let rules = rules("0: 0 1 | 1\n1: \"a\"").unwrap().1;
+        let grammar = Grammar::new(&rules).unwrap();
+
+        assert!(grammar.matches(0, "a"));
+        assert!(grammar.matches(0, "aaaa"));
+        assert!(!grammar.matches(0, ""));
+        assert!(!grammar.matches(0, "aab"));
+    }
+
+    #[test]
+    fn test_undefined_rules() {
+        let dangling = rules("0: 1 2\n1: \"a\"").unwrap().1;
+
+        assert!(matches!(
+            Grammar::new(&dangling),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+
+        let complete = rules("0: 1\n1: \"a\"").unwrap().1;
+        let grammar = Grammar::new(&complete).unwrap();
+
+        assert!(matches!(
+            grammar.with_rule(1, &RuleType::Referencing(vec![vec![3]])),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+
+        let rules_and_messages = parse_rules_and_messages("1: \"a\"\n\na");
+        let grammar = Grammar::new(&rules_and_messages.rules).unwrap();
+
+        assert!(matches!(
+            count_matches(&rules_and_messages.messages, &grammar),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+    }
 }
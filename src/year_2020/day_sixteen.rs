@@ -7,10 +7,11 @@ use nom::{
     IResult,
 };
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::ops::RangeInclusive;
 use std::time::{Duration, SystemTime};
 use crate::common::answer::*;
+use crate::common::assignment::{AssignmentError, Candidates};
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-16.txt");
@@ -19,7 +20,7 @@ pub fn run() -> AdventOfCodeResult {
     let parse_duration = parse_start.elapsed().unwrap();
 
     let part_one = part_one(&rules_and_tickets, parse_duration);
-    let part_two = part_two(&rules_and_tickets, parse_duration);
+    let part_two = part_two(&rules_and_tickets, parse_duration)?;
 
     Ok((part_one, part_two))
 }
@@ -50,10 +51,13 @@ fn part_one(rules_and_tickets: &RulesAndTickets, parse_duration: Duration) -> Pa
     (error_rate, elapsed + parse_duration).into()
 }
 
-fn part_two(rules_and_tickets: &RulesAndTickets, parse_duration: Duration) -> PartAnswer {
+fn part_two(
+    rules_and_tickets: &RulesAndTickets,
+    parse_duration: Duration,
+) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let assigned_fields_by_rule = assign_rules_to_fields(rules_and_tickets);
+    let assigned_fields_by_rule = assign_rules_to_fields(rules_and_tickets)?;
 
     let mut product = 1;
 
@@ -69,79 +73,36 @@ fn part_two(rules_and_tickets: &RulesAndTickets, parse_duration: Duration) -> Pa
 
     let elapsed = start.elapsed().unwrap();
 
-    (product, elapsed + parse_duration).into()
+    Ok((product, elapsed + parse_duration).into())
 }
 
-fn assign_rules_to_fields(rules_and_tickets: &RulesAndTickets) -> HashMap<usize, usize> {
-    let mut valid_tickets: Vec<Vec<u64>> = Vec::new();
-
-    for ticket in &rules_and_tickets.nearby_tickets {
-        if rules_and_tickets.rules.is_ticket_valid(ticket) {
-            valid_tickets.push(ticket.clone());
-        }
-    }
-
-    let mut valid_fields_by_rule: HashMap<usize, Vec<usize>> = HashMap::new();
+fn assign_rules_to_fields(
+    rules_and_tickets: &RulesAndTickets,
+) -> Result<HashMap<usize, usize>, AssignmentError<usize, usize>> {
+    let valid_tickets: Vec<&Ticket> = rules_and_tickets
+        .nearby_tickets
+        .iter()
+        .filter(|ticket| rules_and_tickets.rules.is_ticket_valid(ticket))
+        .collect();
 
     let number_of_rules = rules_and_tickets.rules.rules.len();
 
+    let mut candidates = Candidates::new();
+
     for rule in &rules_and_tickets.rules.rules {
-        valid_fields_by_rule.insert(rule.index, Vec::with_capacity(number_of_rules));
+        candidates.add_slot(rule.index);
 
         for field_index in 0..number_of_rules {
-            let mut is_valid_for_field = true;
-
-            for ticket in &valid_tickets {
-                let field = ticket[field_index];
-
-                if !rule.is_field_valid(&field) {
-                    is_valid_for_field = false;
-                }
-            }
-
-            if is_valid_for_field {
-                valid_fields_by_rule
-                    .get_mut(&rule.index)
-                    .unwrap()
-                    .push(field_index);
-            }
-        }
-    }
-
-    let mut valid_fields_by_rule: Vec<(usize, Vec<usize>)> =
-        valid_fields_by_rule.into_iter().collect();
-    valid_fields_by_rule.sort_by_key(|(_, v)| v.len());
-
-    let mut assigned_fields_by_rule: HashMap<usize, usize> = HashMap::new();
-    let mut assigned_fields: HashSet<usize> = HashSet::new();
-
-    for (rule_index, possible_field_indexes) in &valid_fields_by_rule {
-        if assigned_fields_by_rule.contains_key(rule_index) {
-            continue;
-        }
-
-        let mut valid_and_unassigned_field_indexes = Vec::new();
-
-        for field_index in possible_field_indexes {
-            if !assigned_fields.contains(field_index) {
-                valid_and_unassigned_field_indexes.push(field_index);
+            if valid_tickets
+                .iter()
+                .all(|ticket| rule.is_field_valid(&ticket[field_index]))
+            {
+                candidates.add(rule.index, field_index);
             }
         }
-
-        valid_and_unassigned_field_indexes.sort();
-
-        if valid_and_unassigned_field_indexes.len() == 1 {
-            let field_index = valid_and_unassigned_field_indexes
-                .into_iter()
-                .next()
-                .unwrap();
-
-            assigned_fields_by_rule.insert(*rule_index, *field_index);
-            assigned_fields.insert(*field_index);
-        }
     }
 
-    assigned_fields_by_rule
+    Ok(candidates.solve()?.into_iter().collect())
 }
 
 #[derive(Debug, PartialEq)]
@@ -399,6 +360,6 @@ mod tests {
 
         let expected = vec![(0, 1), (1, 0), (2, 2)].into_iter().collect();
 
-        assert_eq!(assigned, expected);
+        assert_eq!(assigned, Ok(expected));
     }
 }
@@ -1,12 +1,13 @@
+use std::convert::TryFrom;
 use std::time::SystemTime;
-use crate::common::answer::*;
+use crate::common::{answer::*, math::chinese_remainder};
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-13.txt");
     let (timestamp, bus_schedule) = parse_bus_schedule(input);
 
     let part_one = part_one(timestamp, &bus_schedule);
-    let part_two = part_two(&bus_schedule);
+    let part_two = part_two(&bus_schedule)?;
 
     Ok((part_one, part_two))
 }
@@ -31,71 +32,27 @@ fn part_one(timestamp: u64, bus_schedule: &[BusTiming]) -> PartAnswer {
     (min_bus_wait * min_bus_id, elapsed).into()
 }
 
-fn part_two(bus_schedule: &[BusTiming]) -> PartAnswer {
+fn part_two(bus_schedule: &[BusTiming]) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
-    let solution = solve_congruences(bus_schedule);
+    let solution = solve_congruences(bus_schedule)?;
     let elapsed = start.elapsed().unwrap();
 
-    (solution, elapsed).into()
+    Ok((solution, elapsed).into())
 }
 
-// uses Lagrange interpolation
-fn solve_congruences(schedule: &[BusTiming]) -> u64 {
-    let product_of_all: i64 = schedule.iter().map(|b| b.id).product();
-
-    let product_of_all_except_self: Vec<i64> =
-        schedule.iter().map(|b| product_of_all / b.id).collect();
-
-    let mut sum = 0;
-
-    for i in 0..schedule.len() {
-        let bus = &schedule[i];
-
-        let n_i = product_of_all_except_self[i];
-        let n = bus.id;
-
-        let (m_i, _) = bezout_coefficients(n_i, n);
-        sum += bus.index as i64 * m_i * n_i;
-    }
-
-    while sum < 0 {
-        sum += product_of_all;
-    }
-
-    while sum > 0 {
-        sum -= product_of_all
-    }
-
-    sum += product_of_all;
-
-    (product_of_all - sum) as u64
-}
+// bus `id` leaves `index` minutes after t, so t == -index (mod id)
+fn solve_congruences(schedule: &[BusTiming]) -> Result<u64, AdventOfCodeError> {
+    let congruences: Vec<(i128, i128)> = schedule
+        .iter()
+        .map(|bus| (-(bus.index as i128), bus.id as i128))
+        .collect();
 
-#[allow(clippy::many_single_char_names)]
-fn bezout_coefficients(a: i64, b: i64) -> (i64, i64) {
-    let mut old_r = a;
-    let mut r = b;
-    let mut old_s = 1;
-    let mut s = 0;
-    let mut old_t = 0;
-    let mut t = 1;
-
-    while r != 0 {
-        let quotient = old_r / r;
-        let temp = r;
-        r = old_r - (quotient * r);
-        old_r = temp;
-
-        let temp = s;
-        s = old_s - (quotient * s);
-        old_s = temp;
-
-        let temp = t;
-        t = old_t - (quotient * t);
-        old_t = temp;
-    }
+    let (solution, _) = chinese_remainder(&congruences).ok_or_else(|| {
+        AdventOfCodeError::NoSolution("no timestamp lines up every bus".to_string())
+    })?;
 
-    (old_s, old_t)
+    u64::try_from(solution)
+        .map_err(|_| AdventOfCodeError::Overflow(format!("timestamp {}", solution)))
 }
 
 #[derive(Debug, PartialEq)]
@@ -133,12 +90,6 @@ fn sort_bus_schedule(schedule: &mut [BusTiming]) {
 mod test {
     use super::*;
 
-    #[test]
-    fn test_bezout_coefficients() {
-        assert_eq!(bezout_coefficients(3, 4), (-1, 1));
-        assert_eq!(bezout_coefficients(5, 12), (5, -2));
-    }
-
     #[test]
     fn test_solve_congruences() {
         let buses = vec![
@@ -147,7 +98,7 @@ mod test {
             BusTiming { id: 19, index: 3 },
         ];
 
-        let solution = solve_congruences(&buses);
+        let solution = solve_congruences(&buses).unwrap();
         assert_eq!(solution, 3417);
 
         let buses = vec![
@@ -157,7 +108,7 @@ mod test {
             BusTiming { id: 61, index: 3 },
         ];
 
-        let solution = solve_congruences(&buses);
+        let solution = solve_congruences(&buses).unwrap();
         assert_eq!(solution, 754018);
 
         let buses = vec![
@@ -167,7 +118,7 @@ mod test {
             BusTiming { id: 61, index: 4 },
         ];
 
-        let solution = solve_congruences(&buses);
+        let solution = solve_congruences(&buses).unwrap();
         assert_eq!(solution, 779210);
 
         let buses = vec![
@@ -177,7 +128,7 @@ mod test {
             BusTiming { id: 61, index: 4 },
         ];
 
-        let solution = solve_congruences(&buses);
+        let solution = solve_congruences(&buses).unwrap();
         assert_eq!(solution, 1261476);
 
         let buses = vec![
@@ -187,7 +138,12 @@ mod test {
             BusTiming { id: 1889, index: 3 },
         ];
 
-        let solution = solve_congruences(&buses);
+        let solution = solve_congruences(&buses).unwrap();
         assert_eq!(solution, 1202161486);
+
+        // the first bus needs an even timestamp and the second an odd one
+        let buses = vec![BusTiming { id: 4, index: 0 }, BusTiming { id: 2, index: 1 }];
+
+        assert!(solve_congruences(&buses).is_err());
     }
 }
@@ -5,6 +5,7 @@ use std::{
 };
 use std::time::{Duration, SystemTime};
 use crate::common::answer::*;
+use crate::common::geometry::{Matrix2, Vector2};
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -24,7 +25,7 @@ pub fn run() -> AdventOfCodeResult {
     let parse_duration = parse_start.elapsed().unwrap();
 
     let part_one = part_one(&tiles, parse_duration);
-    let part_two = part_two(&tiles, parse_duration);
+    let part_two = part_two(&tiles, parse_duration)?;
 
     Ok((part_one, part_two))
 }
@@ -41,14 +42,14 @@ fn part_one(tiles: &Tiles, parse_duration: Duration) -> PartAnswer {
     (product as u64, elapsed + parse_duration).into()
 }
 
-fn part_two(tiles: &Tiles, parse_duration: Duration) -> PartAnswer {
+fn part_two(tiles: &Tiles, parse_duration: Duration) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
     let corners = find_corners(tiles);
 
     let top_left = find_top_left(corners, tiles);
 
-    let tile_layout = get_tile_layout(top_left, tiles);
+    let tile_layout = get_tile_layout(top_left, tiles)?;
 
     let image_string = build_image_string(&tile_layout, tiles);
 
@@ -84,15 +85,11 @@ fn part_two(tiles: &Tiles, parse_duration: Duration) -> PartAnswer {
 
     let mut seen_coordinates = HashSet::new();
 
-    for i in 0..8 {
-        if i % 4 == 0 {
-            image_tile.flip();
-        }
-
-        image_tile.rotate();
+    for orientation in Matrix2::dihedral_group() {
+        let oriented_tile = image_tile.transformed(&orientation);
 
-        for delta_x in 0..image_tile.length - monster_height {
-            for delta_y in 0..image_tile.length - monster_width {
+        for delta_x in 0..oriented_tile.length - monster_height {
+            for delta_y in 0..oriented_tile.length - monster_width {
                 let adjusted_monster: HashSet<(usize, usize)> = monster
                     .keys()
                     .map(|(x, y)| (*x + delta_x, *y + delta_y))
@@ -102,7 +99,7 @@ fn part_two(tiles: &Tiles, parse_duration: Duration) -> PartAnswer {
 
                 for coordinates in adjusted_monster {
                     seen_coordinates.insert(coordinates);
-                    let pixel = image_tile
+                    let pixel = oriented_tile
                         .pixels
                         .get(&coordinates)
                         .map(|s| s.as_str())
@@ -121,6 +118,7 @@ fn part_two(tiles: &Tiles, parse_duration: Duration) -> PartAnswer {
         }
 
         if number_of_monsters_seen > 0 {
+            image_tile = oriented_tile;
             break;
         }
     }
@@ -147,7 +145,7 @@ fn part_two(tiles: &Tiles, parse_duration: Duration) -> PartAnswer {
 
     let elapsed = start.elapsed().unwrap();
 
-    (rocks, elapsed + parse_duration).into()
+    Ok((rocks, elapsed + parse_duration).into())
 }
 
 #[allow(dead_code)]
@@ -248,7 +246,10 @@ fn build_image_string(tile_layout: &HashMap<(usize, usize), Tile>, tiles: &Tiles
     rows.join("\n")
 }
 
-fn get_tile_layout(top_left: &Tile, tiles: &Tiles) -> HashMap<(usize, usize), Tile> {
+fn get_tile_layout(
+    top_left: &Tile,
+    tiles: &Tiles,
+) -> Result<HashMap<(usize, usize), Tile>, AdventOfCodeError> {
     let mut placed_tiles = HashSet::new();
     let mut tile_set = HashMap::new();
 
@@ -273,61 +274,33 @@ fn get_tile_layout(top_left: &Tile, tiles: &Tiles) -> HashMap<(usize, usize), Ti
             };
 
             let next_tile_id =
-                get_other_tile_with_border(&previous_tile.id, &border_to_find, tiles);
-            if next_tile_id == None {
-                panic!(
-                    "Could not get next tile for tile {} (row {}, column {}, border {})",
-                    previous_tile.id, row, column, border_to_find
-                );
-            }
-
-            let next_tile_id = next_tile_id.unwrap();
-
-            let mut next_tile = tiles.get(&next_tile_id).clone();
-
-            for _ in 0..4 {
-                let borders = next_tile.get_borders();
-                let border_to_check = if column == 0 {
-                    borders.top
-                } else {
-                    borders.left
-                };
-
-                if border_to_check == border_to_find {
-                    break;
-                }
-
-                next_tile.rotate();
-            }
-
-            let borders = next_tile.get_borders();
-
-            let border_to_check = if column == 0 {
-                borders.top
-            } else {
-                borders.left
-            };
-
-            if border_to_check != border_to_find {
-                next_tile.flip();
-            }
-
-            for _ in 0..4 {
-                let borders = next_tile.get_borders();
-                let border_to_check = if column == 0 {
-                    borders.top
-                } else {
-                    borders.left
-                };
-
-                if border_to_check == border_to_find {
-                    break;
-                }
-
-                next_tile.rotate();
-            }
-
-            next_tile
+                get_other_tile_with_border(&previous_tile.id, &border_to_find, tiles).ok_or_else(
+                    || {
+                        AdventOfCodeError::NoSolution(format!(
+                            "no tile follows tile {} (row {}, column {}, border {})",
+                            previous_tile.id, row, column, border_to_find
+                        ))
+                    },
+                )?;
+
+            let next_tile = tiles.get(&next_tile_id);
+
+            Matrix2::dihedral_group()
+                .iter()
+                .map(|orientation| next_tile.transformed(orientation))
+                .find(|candidate| {
+                    let borders = candidate.get_borders();
+                    let border_to_check = if column == 0 {
+                        borders.top
+                    } else {
+                        borders.left
+                    };
+
+                    border_to_check == border_to_find
+                })
+                .ok_or_else(|| {
+                    AdventOfCodeError::NoSolution(format!("could not orient tile {}", next_tile_id))
+                })?
         };
 
         placed_tiles.insert(next_tile.id);
@@ -341,7 +314,7 @@ fn get_tile_layout(top_left: &Tile, tiles: &Tiles) -> HashMap<(usize, usize), Ti
         }
     }
 
-    tile_set
+    Ok(tile_set)
 }
 
 fn get_other_tile_with_border(this_tile_id: &usize, border: &str, tiles: &Tiles) -> Option<usize> {
@@ -554,61 +527,44 @@ impl Tile {
         }
     }
 
-    fn rotate(&mut self) {
-        self.pixels = rotate(&self.pixels, self.length, self.length);
-    }
-
-    fn flip(&mut self) {
-        self.pixels = flip(&self.pixels, self.length, self.length);
-    }
-}
-
-fn rotate<V: Clone>(
-    map: &HashMap<(usize, usize), V>,
-    width: usize,
-    height: usize,
-) -> HashMap<(usize, usize), V> {
-    let mut output = HashMap::new();
-
-    for row in 0..width {
-        for column in 0..height {
-            let pixel_type = map.get(&(row, column));
-
-            if pixel_type.is_none() {
-                continue;
-            }
-
-            let pixel_type = pixel_type.unwrap().clone();
-
-            let new_coordinates = (height - column - 1, row);
-
-            output.insert(new_coordinates, pixel_type);
+    fn transformed(&self, matrix: &Matrix2) -> Tile {
+        Tile {
+            id: self.id,
+            pixels: transform(&self.pixels, self.length, self.length, matrix),
+            length: self.length,
         }
     }
-
-    output
 }
 
-fn flip<V: Clone>(
+/**
+ * Moves every cell of a `width` by `height` grid, keyed by (row, column), through one of the
+ * symmetries of the square, keeping the grid's top left corner at (0, 0)
+ *
+ * Cells are measured from the centre of the grid, doubled so the centre of an even sized grid
+ * lands on a whole number, which puts the transformed grid back in the same place.
+ */
+fn transform<V: Clone>(
     map: &HashMap<(usize, usize), V>,
     width: usize,
     height: usize,
+    matrix: &Matrix2,
 ) -> HashMap<(usize, usize), V> {
-    let mut output = HashMap::new();
+    let centre = Vector2::new(width as i64 - 1, height as i64 - 1);
 
-    for row in 0..width {
-        for column in 0..height {
-            let pixel_type = map.get(&(row, column));
-            if pixel_type.is_none() {
-                continue;
-            }
+    // the quarter turns and the reflections through the diagonals swap rows and columns over
+    let transformed_centre = *matrix * centre;
+    let transformed_centre = Vector2::new(transformed_centre.x.abs(), transformed_centre.y.abs());
 
-            let pixel_type = pixel_type.unwrap().clone();
+    let mut output = HashMap::new();
 
-            let new_coordinates = (row, height - column - 1);
+    for ((row, column), value) in map {
+        let position = Vector2::new(*row as i64 * 2, *column as i64 * 2) - centre;
+        let position = *matrix * position + transformed_centre;
 
-            output.insert(new_coordinates, pixel_type);
-        }
+        output.insert(
+            ((position.x / 2) as usize, (position.y / 2) as usize),
+            value.clone(),
+        );
     }
 
     output
@@ -808,9 +764,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let mut tile = Tile::new(0, pixels);
-
-        tile.rotate();
+        let tile = Tile::new(0, pixels).transformed(&Matrix2::QUARTER_TURN);
 
         let expected_pixels = vec![
             ((0, 0), ".".into()),
@@ -835,9 +789,7 @@ mod tests {
         .into_iter()
         .collect();
 
-        let mut tile = Tile::new(0, pixels);
-
-        tile.flip();
+        let tile = Tile::new(0, pixels).transformed(&Matrix2::REFLECTION);
 
         let expected_pixels = vec![
             ((0, 0), ".".into()),
@@ -888,7 +840,7 @@ mod tests {
         ]
         .into_iter()
         .collect();
-        let map = rotate(&map, 3, 2);
+        let map = transform(&map, 3, 2, &Matrix2::QUARTER_TURN);
 
         let expected = vec![
             ((0, 0), 4),
@@ -916,7 +868,7 @@ mod tests {
         ]
         .into_iter()
         .collect();
-        let map = flip(&map, 3, 2);
+        let map = transform(&map, 3, 2, &Matrix2::REFLECTION);
 
         let expected = vec![
             ((0, 0), 4),
@@ -1,50 +1,49 @@
 use std::time::SystemTime;
-use crate::common::answer::*;
+use crate::common::{
+    answer::*,
+    math::{discrete_log, mod_pow},
+};
+
+const MODULUS: u64 = 20201227;
+const SUBJECT_NUMBER: u64 = 7;
 
 pub fn run() -> AdventOfCodeResult {
-    let part_one = part_one();
+    let part_one = part_one()?;
     let part_two = PartAnswer::default();
 
     Ok((part_one, part_two))
 }
 
-fn part_one() -> PartAnswer {
+fn part_one() -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
     let card_public_key = 12092626;
     let door_public_key = 4707356;
 
-    let door_loop_size = find_loop_size(door_public_key);
+    let door_loop_size = find_loop_size(door_public_key)?;
 
-    let mut card_encryption_key = 1;
-    for _ in 0..door_loop_size {
-        card_encryption_key = transform(card_encryption_key, card_public_key);
-    }
+    let card_encryption_key = transform(card_public_key, door_loop_size)?;
 
     let elapsed = start.elapsed().unwrap();
 
-    (card_encryption_key, elapsed).into()
+    Ok((card_encryption_key, elapsed).into())
 }
 
-fn find_loop_size(public_key: u64) -> usize {
-    let mut loop_size = 1;
-    let mut current_subject_number = 1;
-
-    loop {
-        let new_card_subject_number = transform(current_subject_number, 7);
-        if new_card_subject_number == public_key {
-            break;
-        }
-
-        current_subject_number = new_card_subject_number;
-        loop_size += 1;
-    }
-
-    loop_size
+// the loop size is the discrete logarithm of the public key, base 7
+fn find_loop_size(public_key: u64) -> Result<u64, AdventOfCodeError> {
+    discrete_log(SUBJECT_NUMBER, public_key, MODULUS)
+        .map(|loop_size| loop_size as u64)
+        .ok_or_else(|| {
+            AdventOfCodeError::NoSolution(format!("no loop size gives public key {}", public_key))
+        })
 }
 
-fn transform(old: u64, subject_number: u64) -> u64 {
-    (old * subject_number) % 20201227
+fn transform(subject_number: u64, loop_size: u64) -> Result<u64, AdventOfCodeError> {
+    mod_pow(subject_number, loop_size, MODULUS)
+        .map(|key| key as u64)
+        .ok_or_else(|| {
+            AdventOfCodeError::NoSolution(format!("cannot transform modulo {}", MODULUS))
+        })
 }
 
 #[cfg(test)]
@@ -54,18 +53,21 @@ mod tests {
     #[test]
     fn test_find_loop_size() {
         let card_public_key = 5764801;
-        let card_loop_size = find_loop_size(card_public_key);
+        let card_loop_size = find_loop_size(card_public_key).unwrap();
         assert_eq!(card_loop_size, 8);
 
-        let mut subject_number = 1;
-
-        for _ in 0..card_loop_size {
-            subject_number = transform(subject_number, 7);
-        }
-
-        assert_eq!(subject_number, card_public_key);
+        assert_eq!(
+            transform(SUBJECT_NUMBER, card_loop_size),
+            Ok(card_public_key)
+        );
 
         let door_public_key = 17807724;
-        assert_eq!(find_loop_size(door_public_key), 11);
+        assert_eq!(find_loop_size(door_public_key), Ok(11));
+    }
+
+    #[test]
+    fn test_transform() {
+        assert_eq!(transform(17807724, 8), Ok(14897079));
+        assert_eq!(transform(5764801, 11), Ok(14897079));
     }
 }
@@ -1,12 +1,8 @@
 use crate::common::answer::*;
-use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    combinator::value,
-    multi::{many1, separated_list1},
-    IResult,
-};
-use std::{collections::HashSet, ops::Add};
+use crate::common::hex::{pointy_directions, walk, Cube, PointyDirection};
+use log::debug;
+use nom::{bytes::complete::tag, multi::separated_list1, IResult};
+use std::collections::HashSet;
 use std::time::{Duration, SystemTime};
 
 pub fn run() -> AdventOfCodeResult {
@@ -41,36 +37,27 @@ fn part_two(tile_pointers: &[TilePointer], parse_duration: Duration) -> PartAnsw
         art.another_day();
     }
 
+    debug!(
+        "after 100 days, black tiles reach {} tiles from the reference tile",
+        art.extent()
+    );
+
     let elapsed = start.elapsed().unwrap();
 
     (art.len() as u64, elapsed + parse_duration).into()
 }
 
-fn get_initial_state(tile_pointers: &[TilePointer]) -> HashSet<CubeCoordinates> {
+fn get_initial_state(tile_pointers: &[TilePointer]) -> HashSet<Cube> {
     let mut tile_states = HashSet::new();
 
     for pointer in tile_pointers {
-        let mut current = CubeCoordinates::new(0, 0, 0);
-
-        for direction in pointer {
-            let offset = match direction {
-                Direction::Northwest => (0, 1, -1),
-                Direction::West => (-1, 1, 0),
-                Direction::Southwest => (-1, 0, 1),
-                Direction::Southeast => (0, -1, 1),
-                Direction::East => (1, -1, 0),
-                Direction::Northeast => (1, 0, -1),
-            };
-
-            let offset = offset.into();
-
-            current = &current + &offset;
-        }
+        let tile = walk(
+            Cube::ORIGIN,
+            pointer.iter().map(|direction| direction.offset()),
+        );
 
-        if tile_states.contains(&current) {
-            tile_states.remove(&current);
-        } else {
-            tile_states.insert(current);
+        if !tile_states.remove(&tile) {
+            tile_states.insert(tile);
         }
     }
 
@@ -79,66 +66,44 @@ fn get_initial_state(tile_pointers: &[TilePointer]) -> HashSet<CubeCoordinates>
 
 #[derive(Debug, PartialEq)]
 struct TileFloorArtExhibit {
-    black_tiles: HashSet<CubeCoordinates>,
+    black_tiles: HashSet<Cube>,
 }
 
 impl TileFloorArtExhibit {
     fn new(tile_pointers: &[TilePointer]) -> TileFloorArtExhibit {
-        let mut black_tiles = HashSet::new();
-
-        for pointer in tile_pointers {
-            let mut current = CubeCoordinates::new(0, 0, 0);
-
-            for direction in pointer {
-                let offset = match direction {
-                    Direction::Northwest => (0, 1, -1),
-                    Direction::West => (-1, 1, 0),
-                    Direction::Southwest => (-1, 0, 1),
-                    Direction::Southeast => (0, -1, 1),
-                    Direction::East => (1, -1, 0),
-                    Direction::Northeast => (1, 0, -1),
-                };
-
-                let offset = offset.into();
-
-                current = &current + &offset;
-            }
-
-            if black_tiles.contains(&current) {
-                black_tiles.remove(&current);
-            } else {
-                black_tiles.insert(current);
-            }
+        Self {
+            black_tiles: get_initial_state(tile_pointers),
         }
-
-        Self { black_tiles }
     }
 
     fn len(&self) -> usize {
         self.black_tiles.len()
     }
 
+    /// How far the furthest black tile is from the reference tile
+    fn extent(&self) -> i64 {
+        self.black_tiles
+            .iter()
+            .map(|tile| tile.distance(&Cube::ORIGIN))
+            .max()
+            .unwrap_or(0)
+    }
+
     fn another_day(&mut self) {
-        let tiles_to_consider: HashSet<CubeCoordinates> = self
+        let tiles_to_consider: HashSet<Cube> = self
             .black_tiles
             .iter()
-            .flat_map(|tile| {
-                let mut me_and_adjacent = vec![*tile];
-                me_and_adjacent.extend(tile.adjacent());
-
-                me_and_adjacent
-            })
+            .flat_map(|tile| tile.spiral(1))
             .collect();
 
         let mut new_black_tiles = self.black_tiles.clone();
 
         for tile_to_consider in &tiles_to_consider {
-            let adjacent = tile_to_consider.adjacent();
-
-            let black_adjacent_tiles: HashSet<&CubeCoordinates> =
-                adjacent.intersection(&self.black_tiles).collect();
-
-            let black_adjacent_tiles = black_adjacent_tiles.len();
+            let black_adjacent_tiles = tile_to_consider
+                .neighbours()
+                .iter()
+                .filter(|tile| self.black_tiles.contains(tile))
+                .count();
 
             if self.black_tiles.contains(tile_to_consider) {
                 // current tile is black
@@ -157,86 +122,14 @@ impl TileFloorArtExhibit {
     }
 }
 
-type TilePointer = Vec<Direction>;
-
-#[derive(Debug, PartialEq, Hash, Eq, Copy, Clone)]
-struct CubeCoordinates {
-    x: i64,
-    y: i64,
-    z: i64,
-}
-
-impl CubeCoordinates {
-    fn new(x: i64, y: i64, z: i64) -> Self {
-        Self { x, y, z }
-    }
-
-    fn adjacent(&self) -> HashSet<Self> {
-        vec![
-            (0, 1, -1).into(),
-            (-1, 1, 0).into(),
-            (-1, 0, 1).into(),
-            (0, -1, 1).into(),
-            (1, -1, 0).into(),
-            (1, 0, -1).into(),
-        ]
-        .iter()
-        .map(|other| self + other)
-        .collect()
-    }
-}
-
-impl From<(i64, i64, i64)> for CubeCoordinates {
-    fn from(tuple: (i64, i64, i64)) -> CubeCoordinates {
-        let (x, y, z) = tuple;
-
-        CubeCoordinates { x, y, z }
-    }
-}
-
-impl Add<&CubeCoordinates> for &CubeCoordinates {
-    type Output = CubeCoordinates;
-
-    fn add(self, other: &CubeCoordinates) -> Self::Output {
-        let x = self.x + other.x;
-        let y = self.y + other.y;
-        let z = self.z + other.z;
-
-        Self::Output { x, y, z }
-    }
-}
-
-#[derive(Debug, PartialEq, Copy, Clone)]
-enum Direction {
-    East,
-    Southeast,
-    Southwest,
-    West,
-    Northwest,
-    Northeast,
-}
+type TilePointer = Vec<PointyDirection>;
 
 fn parse_tile_pointers(input: &str) -> Vec<TilePointer> {
     tile_pointers(input).map(|(_, pointers)| pointers).unwrap()
 }
 
 fn tile_pointers(i: &str) -> IResult<&str, Vec<TilePointer>> {
-    separated_list1(tag("\n"), tile_pointer)(i)
-}
-
-fn tile_pointer(i: &str) -> IResult<&str, TilePointer> {
-    many1(direction)(i)
-}
-
-fn direction(i: &str) -> IResult<&str, Direction> {
-    let east = value(Direction::East, tag("e"));
-    let southeast = value(Direction::Southeast, tag("se"));
-    let southwest = value(Direction::Southwest, tag("sw"));
-    let west = value(Direction::West, tag("w"));
-    let northwest = value(Direction::Northwest, tag("nw"));
-    let northeast = value(Direction::Northeast, tag("ne"));
-
-    alt((southeast, southwest, northeast, northwest, east, west))(i)
+    separated_list1(tag("\n"), pointy_directions)(i)
 }
 
 #[cfg(test)]
@@ -246,15 +139,15 @@ mod tests {
     #[test]
     fn test_tile_pointer() {
         assert_eq!(
-            tile_pointer("nwwswee"),
+            pointy_directions("nwwswee"),
             Ok((
                 "",
                 vec![
-                    Direction::Northwest,
-                    Direction::West,
-                    Direction::Southwest,
-                    Direction::East,
-                    Direction::East
+                    PointyDirection::Northwest,
+                    PointyDirection::West,
+                    PointyDirection::Southwest,
+                    PointyDirection::East,
+                    PointyDirection::East
                 ]
             ))
         );
@@ -276,5 +169,6 @@ mod tests {
 
         art.another_day();
         assert_eq!(art.len(), 14);
+        assert_eq!(art.extent(), 6);
     }
 }
@@ -1,4 +1,5 @@
 use crate::common::answer::*;
+use crate::common::assignment::{AssignmentError, Candidates};
 
 use nom::{
     bytes::complete::tag,
@@ -17,16 +18,16 @@ pub fn run() -> AdventOfCodeResult {
     let foods = parse_foods(input);
     let parse_duration = parse_start.elapsed().unwrap();
 
-    let part_one = part_one(&foods, parse_duration);
-    let part_two = part_two(&foods, parse_duration);
+    let part_one = part_one(&foods, parse_duration)?;
+    let part_two = part_two(&foods, parse_duration)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(foods: &[Food], parse_duration: Duration) -> PartAnswer {
+fn part_one(foods: &[Food], parse_duration: Duration) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let ingredient_to_identified_allergen = identify_allergen_containing_ingredients(foods);
+    let ingredient_to_identified_allergen = identify_allergen_containing_ingredients(foods)?;
 
     let mut counter = 0;
 
@@ -40,13 +41,13 @@ fn part_one(foods: &[Food], parse_duration: Duration) -> PartAnswer {
 
     let elapsed = start.elapsed().unwrap();
 
-    (counter as u64, elapsed + parse_duration).into()
+    Ok((counter as u64, elapsed + parse_duration).into())
 }
 
-fn part_two(foods: &[Food], parse_duration: Duration) -> PartAnswer {
+fn part_two(foods: &[Food], parse_duration: Duration) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let ingredient_to_identified_allergen = identify_allergen_containing_ingredients(foods);
+    let ingredient_to_identified_allergen = identify_allergen_containing_ingredients(foods)?;
 
     let mut sorted_ingredients: Vec<(String, String)> = ingredient_to_identified_allergen
         .into_iter()
@@ -63,61 +64,45 @@ fn part_two(foods: &[Food], parse_duration: Duration) -> PartAnswer {
 
     let elapsed = start.elapsed().unwrap();
 
-    PartAnswer::new(sorted_ingredients, elapsed + parse_duration)
+    Ok(PartAnswer::new(
+        sorted_ingredients,
+        elapsed + parse_duration,
+    ))
 }
 
-fn identify_allergen_containing_ingredients(foods: &[Food]) -> HashMap<String, String> {
-    let mut foods_by_allergen = HashMap::new();
+fn identify_allergen_containing_ingredients(
+    foods: &[Food],
+) -> Result<HashMap<Ingredient, Allergen>, AssignmentError<Allergen, Ingredient>> {
+    let mut ingredients_by_allergen: HashMap<&Allergen, HashSet<&Ingredient>> = HashMap::new();
 
     for food in foods {
-        for allergen in &food.allergens {
-            if !foods_by_allergen.contains_key(allergen) {
-                foods_by_allergen.insert(allergen.clone(), vec![]);
-            }
+        let ingredients: HashSet<&Ingredient> = food.ingredients.iter().collect();
 
-            foods_by_allergen.get_mut(allergen).unwrap().push(food);
+        for allergen in &food.allergens {
+            ingredients_by_allergen
+                .entry(allergen)
+                .and_modify(|common| common.retain(|ingredient| ingredients.contains(ingredient)))
+                .or_insert_with(|| ingredients.clone());
         }
     }
 
-    let mut allergens_to_identified_ingredient = HashMap::new();
+    let mut candidates = Candidates::new();
 
-    while allergens_to_identified_ingredient.len() < foods_by_allergen.len() {
-        for (allergen, foods) in &foods_by_allergen {
-            let all_ingredients: HashSet<String> = foods
-                .clone()
-                .into_iter()
-                .flat_map(|food| food.ingredients.clone())
-                .collect();
+    for (allergen, ingredients) in ingredients_by_allergen {
+        candidates.add_slot(allergen.clone());
 
-            let identified_ingredients: HashSet<String> = allergens_to_identified_ingredient
-                .values()
-                .into_iter()
-                .map(|s: &String| s.into())
-                .collect();
-
-            let mut common_ingredients = &all_ingredients - &identified_ingredients;
-
-            for food in foods {
-                let these_ingredients: HashSet<String> =
-                    food.ingredients.clone().into_iter().collect();
-                common_ingredients = &common_ingredients & &these_ingredients;
-            }
-
-            if common_ingredients.len() == 1 {
-                let ingredient = common_ingredients.into_iter().next().unwrap();
-
-                allergens_to_identified_ingredient.insert(allergen.clone(), ingredient);
-            }
+        for ingredient in ingredients {
+            candidates.add(allergen.clone(), ingredient.clone());
         }
     }
 
-    let ingredient_to_identified_allergen: HashMap<String, String> =
-        allergens_to_identified_ingredient
-            .into_iter()
-            .map(|(allergen, ingredient)| (ingredient, allergen))
-            .collect();
+    let ingredient_to_identified_allergen = candidates
+        .solve()?
+        .into_iter()
+        .map(|(allergen, ingredient)| (ingredient, allergen))
+        .collect();
 
-    ingredient_to_identified_allergen
+    Ok(ingredient_to_identified_allergen)
 }
 
 type Allergen = String;
@@ -1,7 +1,5 @@
-use std::collections::HashSet;
-use std::collections::VecDeque;
-use std::time::{Duration, SystemTime};
 use crate::common::answer::*;
+use log::debug;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -11,6 +9,10 @@ use nom::{
     sequence::{separated_pair, tuple},
     IResult,
 };
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime};
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-22.txt");
@@ -27,27 +29,7 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(player_one: &Deck, player_two: &Deck, parse_duration: Duration) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut player_one_deck = player_one.clone();
-    let mut player_two_deck = player_two.clone();
-
-    while !player_one_deck.is_empty() && !player_two_deck.is_empty() {
-        let player_one_card = player_one_deck.pop_front().unwrap();
-        let player_two_card = player_two_deck.pop_front().unwrap();
-
-        if player_one_card > player_two_card {
-            player_one_deck.push_back(player_one_card);
-            player_one_deck.push_back(player_two_card);
-        } else {
-            player_two_deck.push_back(player_two_card);
-            player_two_deck.push_back(player_one_card);
-        }
-    }
-
-    let winning_deck = if player_one_deck.is_empty() {
-        player_two_deck
-    } else {
-        player_one_deck
-    };
+    let winning_deck = Game::new(Rules::Combat).play(player_one, player_two);
 
     let score: u64 = winning_deck.score();
 
@@ -57,78 +39,253 @@ fn part_one(player_one: &Deck, player_two: &Deck, parse_duration: Duration) -> P
 }
 
 fn part_two(player_one: &Deck, player_two: &Deck, parse_duration: Duration) -> PartAnswer {
-    //println!("part two");
     let start = SystemTime::now();
-    let winning_deck = play_game(1, player_one, player_two);
+
+    let mut game = Game::new(Rules::RecursiveCombat);
+    let winning_deck = game.play(player_one, player_two);
+
+    debug!("{}", game.stats());
+
     let elapsed = start.elapsed().unwrap();
 
     PartAnswer::new(winning_deck.score(), elapsed + parse_duration)
 }
 
-fn play_game(game_id: usize, player_one: &Deck, player_two: &Deck) -> Deck {
-    let mut previous_rounds = HashSet::new();
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum Rules {
+    Combat,
+    RecursiveCombat,
+}
+
+/**
+ * Plays games of Combat, keeping track of every sub-game played along the way
+ *
+ * Sub-games only decide who wins a round, so their winners are remembered by the pair of decks
+ * they started from and never played twice. The exception is while writing a transcript, which
+ * has to number the games exactly as the puzzle does.
+ */
+struct Game {
+    rules: Rules,
+    winners_by_decks: HashMap<(DeckKey, DeckKey), Player>,
+    stats: GameStats,
+    transcript: Option<String>,
+}
+
+impl Game {
+    fn new(rules: Rules) -> Game {
+        Game {
+            rules,
+            winners_by_decks: HashMap::new(),
+            stats: GameStats::default(),
+            transcript: None,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_transcript(mut self) -> Game {
+        self.transcript = Some(String::new());
+        self
+    }
+
+    fn stats(&self) -> &GameStats {
+        &self.stats
+    }
+
+    #[cfg(test)]
+    fn transcript(&self) -> Option<&str> {
+        self.transcript.as_deref()
+    }
 
-    let mut player_one_deck = player_one.clone();
-    let mut player_two_deck = player_two.clone();
+    /// Plays a whole game, returning the winner's deck
+    fn play(&mut self, player_one: &Deck, player_two: &Deck) -> Deck {
+        let (player_one, player_two) = self.play_game(player_one.clone(), player_two.clone(), 0);
 
-    while !&player_one_deck.is_empty() && !&player_two_deck.is_empty() {
-        let state_id = player_one_deck.deck_string() + &player_two_deck.deck_string();
+        let winning_deck = if player_one.is_empty() {
+            player_two.clone()
+        } else {
+            player_one.clone()
+        };
 
-        let is_new_state = previous_rounds.insert(state_id);
+        self.write(|| {
+            format!(
+                "\n== Post-game results ==\n{}\n{}\n",
+                player_one, player_two
+            )
+        });
 
-        if !is_new_state {
-            return player_one_deck;
+        winning_deck
+    }
+
+    /**
+     * Plays until one player has all the cards, or in Recursive Combat until a round repeats, when
+     * player one takes the game, and hands back both decks as they were left
+     */
+    fn play_game(
+        &mut self,
+        mut player_one: Deck,
+        mut player_two: Deck,
+        depth: usize,
+    ) -> (Deck, Deck) {
+        self.stats.rounds_by_game.push(0);
+        self.stats.max_depth = self.stats.max_depth.max(depth);
+        let game_id = self.stats.rounds_by_game.len();
+
+        let recursive = self.rules == Rules::RecursiveCombat;
+
+        if recursive {
+            self.write(|| format!("=== Game {} ===\n\n", game_id));
         }
 
-        let player_one_card = player_one_deck.pop_front().unwrap() as usize;
-        let player_two_card = player_two_deck.pop_front().unwrap() as usize;
-
-        if player_one_card <= player_one_deck.len() && player_two_card <= player_two_deck.len() {
-            let player_one_subdeck: Vec<u64> = player_one_deck.clone().cards.into_iter().collect();
-            let player_one_subdeck = player_one_subdeck[0..player_one_card]
-                .iter()
-                .copied()
-                .collect();
-            let player_one_subdeck = Deck {
-                player: Player::PlayerOne,
-                cards: player_one_subdeck,
-            };
+        let mut previous_rounds = HashSet::new();
+        let mut round = 0;
 
-            let player_two_subdeck: Vec<u64> = player_two_deck.clone().cards.into_iter().collect();
-            let player_two_subdeck = player_two_subdeck[0..player_two_card]
-                .iter()
-                .copied()
-                .collect();
-            let player_two_subdeck = Deck {
-                player: Player::PlayerTwo,
-                cards: player_two_subdeck,
+        while !player_one.is_empty() && !player_two.is_empty() {
+            if recursive && !previous_rounds.insert((player_one.key(), player_two.key())) {
+                player_two.clear();
+                break;
+            }
+
+            round += 1;
+            self.stats.rounds_by_game[game_id - 1] += 1;
+
+            self.write(|| {
+                let heading = if recursive {
+                    format!("-- Round {} (Game {}) --", round, game_id)
+                } else {
+                    format!("-- Round {} --", round)
+                };
+
+                format!("{}\n{}\n{}\n", heading, player_one, player_two)
+            });
+
+            let player_one_card = player_one.pop_front().unwrap();
+            let player_two_card = player_two.pop_front().unwrap();
+
+            self.write(|| {
+                format!(
+                    "Player 1 plays: {}\nPlayer 2 plays: {}\n",
+                    player_one_card, player_two_card
+                )
+            });
+
+            let round_winner = if recursive
+                && player_one_card as usize <= player_one.len()
+                && player_two_card as usize <= player_two.len()
+            {
+                self.write(|| "Playing a sub-game to determine the winner...\n\n".to_string());
+
+                let winner = self.sub_game_winner(
+                    player_one.take(player_one_card as usize),
+                    player_two.take(player_two_card as usize),
+                    depth + 1,
+                );
+
+                self.write(|| format!("...anyway, back to game {}.\n", game_id));
+
+                winner
+            } else if player_one_card > player_two_card {
+                Player::PlayerOne
+            } else {
+                Player::PlayerTwo
             };
 
-            let winning_deck = play_game(game_id + 1, &player_one_subdeck, &player_two_subdeck);
+            self.write(|| {
+                if recursive {
+                    format!(
+                        "{} wins round {} of game {}!\n\n",
+                        round_winner, round, game_id
+                    )
+                } else {
+                    format!("{} wins the round!\n\n", round_winner)
+                }
+            });
 
-            match winning_deck.player {
+            match round_winner {
                 Player::PlayerOne => {
-                    player_one_deck.push_back(player_one_card as u64);
-                    player_one_deck.push_back(player_two_card as u64);
+                    player_one.push_back(player_one_card);
+                    player_one.push_back(player_two_card);
                 }
                 Player::PlayerTwo => {
-                    player_two_deck.push_back(player_two_card as u64);
-                    player_two_deck.push_back(player_one_card as u64);
+                    player_two.push_back(player_two_card);
+                    player_two.push_back(player_one_card);
                 }
             }
-        } else if player_one_card > player_two_card {
-            player_one_deck.push_back(player_one_card as u64);
-            player_one_deck.push_back(player_two_card as u64);
+        }
+
+        if recursive {
+            let winner = if player_one.is_empty() {
+                Player::PlayerTwo
+            } else {
+                Player::PlayerOne
+            };
+
+            self.write(|| {
+                format!(
+                    "The winner of game {} is {}!\n\n",
+                    game_id,
+                    winner.to_string().to_lowercase()
+                )
+            });
+        }
+
+        (player_one, player_two)
+    }
+
+    fn sub_game_winner(&mut self, player_one: Deck, player_two: Deck, depth: usize) -> Player {
+        let key = (player_one.key(), player_two.key());
+
+        if self.transcript.is_none() {
+            if let Some(winner) = self.winners_by_decks.get(&key) {
+                self.stats.remembered_sub_games += 1;
+                return *winner;
+            }
+        }
+
+        let (player_one, _) = self.play_game(player_one, player_two, depth);
+
+        let winner = if player_one.is_empty() {
+            Player::PlayerTwo
         } else {
-            player_two_deck.push_back(player_two_card as u64);
-            player_two_deck.push_back(player_one_card as u64);
+            Player::PlayerOne
+        };
+
+        self.winners_by_decks.insert(key, winner);
+
+        winner
+    }
+
+    fn write<F: FnOnce() -> String>(&mut self, text: F) {
+        if let Some(transcript) = self.transcript.as_mut() {
+            transcript.push_str(&text());
         }
     }
+}
+
+#[derive(Debug, PartialEq, Default)]
+struct GameStats {
+    /// How many rounds each game took, in the order the games started
+    rounds_by_game: Vec<usize>,
+    max_depth: usize,
+    /// Sub-games whose winner was already known
+    remembered_sub_games: usize,
+}
+
+impl GameStats {
+    fn sub_games(&self) -> usize {
+        self.rounds_by_game.len().saturating_sub(1)
+    }
+}
 
-    if player_one_deck.is_empty() {
-        player_two_deck
-    } else {
-        player_one_deck
+impl Display for GameStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sub-games ({} more remembered), {} rounds, nested {} deep",
+            self.sub_games(),
+            self.remembered_sub_games,
+            self.rounds_by_game.iter().sum::<usize>(),
+            self.max_depth
+        )
     }
 }
 
@@ -138,22 +295,78 @@ enum Player {
     PlayerTwo,
 }
 
+impl Display for Player {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Player::PlayerOne => write!(f, "Player 1"),
+            Player::PlayerTwo => write!(f, "Player 2"),
+        }
+    }
+}
+
+const HASH_BASE: u64 = 1_000_003;
+
+/**
+ * The cards in a deck, in order, hashed by the deck's running hash
+ *
+ * Only the hash goes into a hash table, so the cards don't have to be hashed again each round, but
+ * keys are only equal when their cards are, so two decks whose hashes collide are still told apart.
+ */
+#[derive(Debug, PartialEq, Clone, Eq)]
+struct DeckKey {
+    hash: u64,
+    cards: Vec<u64>,
+}
+
+impl Hash for DeckKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+/**
+ * A player's cards, top first, along with a polynomial hash of them
+ *
+ * The top card is the highest power of `HASH_BASE`, so putting a card on the bottom multiplies
+ * the hash up and adds the card, and taking the top one off subtracts its term.
+ */
 #[derive(Debug, PartialEq, Clone, Hash, Eq)]
 struct Deck {
     player: Player,
     cards: VecDeque<u64>,
+    hash: u64,
 }
 
 impl Deck {
+    fn new<I: IntoIterator<Item = u64>>(player: Player, cards: I) -> Deck {
+        let mut deck = Deck {
+            player,
+            cards: VecDeque::new(),
+            hash: 0,
+        };
+
+        for card in cards {
+            deck.push_back(card);
+        }
+
+        deck
+    }
+
     fn len(&self) -> usize {
         self.cards.len()
     }
 
     fn pop_front(&mut self) -> Option<u64> {
-        self.cards.pop_front()
+        let card = self.cards.pop_front()?;
+
+        let weight = HASH_BASE.wrapping_pow(self.cards.len() as u32);
+        self.hash = self.hash.wrapping_sub(card.wrapping_mul(weight));
+
+        Some(card)
     }
 
     fn push_back(&mut self, card: u64) {
+        self.hash = self.hash.wrapping_mul(HASH_BASE).wrapping_add(card);
         self.cards.push_back(card)
     }
 
@@ -161,22 +374,39 @@ impl Deck {
         self.cards.is_empty()
     }
 
+    fn clear(&mut self) {
+        self.cards.clear();
+        self.hash = 0;
+    }
+
+    /// A new deck of the top `count` cards
+    fn take(&self, count: usize) -> Deck {
+        Deck::new(self.player, self.cards.iter().take(count).copied())
+    }
+
+    fn key(&self) -> DeckKey {
+        DeckKey {
+            hash: self.hash,
+            cards: self.cards.iter().copied().collect(),
+        }
+    }
+
     fn score(&self) -> u64 {
-        let length = self.len();
+        let length = self.len() as u64;
 
-        let score = &self
-            .cards
-            .clone()
-            .into_iter()
+        self.cards
+            .iter()
             .enumerate()
-            .map(|(index, value)| value * (length as u64 - index as u64))
-            .sum();
-
-        *score
+            .map(|(index, value)| value * (length - index as u64))
+            .sum()
     }
+}
+
+impl Display for Deck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let cards: Vec<String> = self.cards.iter().map(|card| card.to_string()).collect();
 
-    fn deck_string(&self) -> String {
-        format!("{:?}", self.cards)
+        write!(f, "{}'s deck: {}", self.player, cards.join(", "))
     }
 }
 
@@ -196,13 +426,11 @@ fn decks(i: &str) -> IResult<&str, (Deck, Deck)> {
 }
 
 fn deck(i: &str) -> IResult<&str, Deck> {
-    let cards = map(separated_list1(tag("\n"), number), |v| {
-        v.into_iter().collect()
-    });
-    map(tuple((player, cards)), |(player, cards)| Deck {
-        player,
-        cards,
-    })(i)
+    let cards = separated_list1(tag("\n"), number);
+    map(
+        tuple((player, cards)),
+        |(player, cards): (Player, Vec<u64>)| Deck::new(player, cards),
+    )(i)
 }
 
 fn number(i: &str) -> IResult<&str, u64> {
@@ -213,13 +441,141 @@ fn number(i: &str) -> IResult<&str, u64> {
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "Player 1:\n9\n2\n6\n3\n1\n\nPlayer 2:\n5\n8\n4\n7\n10";
+
     #[test]
     fn test_deck() {
-        let cards = vec![9, 4, 3].into_iter().collect();
-        let player_deck = Deck {
-            cards,
-            player: Player::PlayerOne,
-        };
+        let player_deck = Deck::new(Player::PlayerOne, vec![9, 4, 3]);
         assert_eq!(deck("Player 1:\n9\n4\n3"), Ok(("", player_deck)));
     }
+
+    #[test]
+    fn test_incremental_hash() {
+        let mut deck = Deck::new(Player::PlayerOne, vec![9, 4, 3]);
+
+        deck.pop_front();
+        deck.push_back(9);
+        deck.push_back(1);
+
+        assert_eq!(deck, Deck::new(Player::PlayerOne, vec![4, 3, 9, 1]));
+        assert_ne!(
+            deck.key(),
+            Deck::new(Player::PlayerOne, vec![3, 4, 9, 1]).key()
+        );
+
+        deck.clear();
+
+        assert_eq!(deck, Deck::new(Player::PlayerOne, vec![]));
+    }
+
+    #[test]
+    fn test_colliding_keys() {
+        let first = DeckKey {
+            hash: 7,
+            cards: vec![1, 2],
+        };
+        let second = DeckKey {
+            hash: 7,
+            cards: vec![2, 1],
+        };
+
+        let keys: HashSet<DeckKey> = vec![first, second].into_iter().collect();
+
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn test_combat() {
+        let (player_one, player_two) = parse_decks(EXAMPLE);
+
+        let mut game = Game::new(Rules::Combat).with_transcript();
+        let winning_deck = game.play(&player_one, &player_two);
+
+        assert_eq!(winning_deck.score(), 306);
+        assert_eq!(game.stats().rounds_by_game, vec![29]);
+
+        let transcript = game.transcript().unwrap();
+
+        assert!(transcript.starts_with(
+            "-- Round 1 --
+Player 1's deck: 9, 2, 6, 3, 1
+Player 2's deck: 5, 8, 4, 7, 10
+Player 1 plays: 9
+Player 2 plays: 5
+Player 1 wins the round!
+
+-- Round 2 --
+Player 1's deck: 2, 6, 3, 1, 9, 5
+Player 2's deck: 8, 4, 7, 10
+Player 1 plays: 2
+Player 2 plays: 8
+Player 2 wins the round!
+"
+        ));
+        assert!(transcript.ends_with(
+            "== Post-game results ==
+Player 1's deck: 
+Player 2's deck: 3, 2, 10, 6, 8, 5, 9, 4, 7, 1
+"
+        ));
+    }
+
+    #[test]
+    fn test_recursive_combat() {
+        let (player_one, player_two) = parse_decks(EXAMPLE);
+
+        let mut game = Game::new(Rules::RecursiveCombat).with_transcript();
+        let winning_deck = game.play(&player_one, &player_two);
+
+        assert_eq!(winning_deck.score(), 291);
+        assert_eq!(game.stats().sub_games(), 4);
+        assert_eq!(game.stats().max_depth, 2);
+
+        let transcript = game.transcript().unwrap();
+
+        assert!(transcript.contains(
+            "-- Round 9 (Game 1) --
+Player 1's deck: 4, 9, 8, 5, 2
+Player 2's deck: 3, 10, 1, 7, 6
+Player 1 plays: 4
+Player 2 plays: 3
+Playing a sub-game to determine the winner...
+
+=== Game 2 ===
+
+-- Round 1 (Game 2) --
+Player 1's deck: 9, 8, 5, 2
+Player 2's deck: 10, 1, 7
+Player 1 plays: 9
+Player 2 plays: 10
+Player 2 wins round 1 of game 2!
+"
+        ));
+        assert!(transcript.contains(
+            "The winner of game 2 is player 2!
+
+...anyway, back to game 1.
+Player 2 wins round 9 of game 1!
+"
+        ));
+        assert!(transcript.ends_with(
+            "The winner of game 1 is player 2!
+
+
+== Post-game results ==
+Player 1's deck: 
+Player 2's deck: 7, 5, 6, 2, 4, 1, 10, 8, 9, 3
+"
+        ));
+    }
+
+    #[test]
+    fn test_infinite_game() {
+        let (player_one, player_two) = parse_decks("Player 1:\n43\n19\n\nPlayer 2:\n2\n29\n14");
+
+        let mut game = Game::new(Rules::RecursiveCombat);
+        let winning_deck = game.play(&player_one, &player_two);
+
+        assert_eq!(winning_deck.player, Player::PlayerOne);
+    }
 }
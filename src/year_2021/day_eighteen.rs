@@ -1,4 +1,7 @@
 use std::fmt::Display;
+use std::iter::Sum;
+use std::ops::Add;
+use std::str::FromStr;
 use std::time::SystemTime;
 use crate::common::{parse::unsigned_number, answer::*};
 use log::debug;
@@ -6,18 +9,18 @@ use nom::{
     branch::alt,
     bytes::complete::tag,
     character::complete::multispace0,
-    combinator::{all_consuming, into, map, value},
-    multi::{many1, separated_list1},
-    sequence::terminated,
+    combinator::{all_consuming, map},
+    multi::separated_list1,
+    sequence::{delimited, separated_pair, terminated},
     IResult,
 };
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-18.txt");
-    let symbols = parse_symbols(input);
+    let numbers = parse_numbers(input);
 
-    let part_one = part_one(&symbols);
-    let part_two = part_two(&symbols);
+    let part_one = part_one(&numbers);
+    let part_two = part_two(&numbers);
 
     Ok((part_one, part_two))
 }
@@ -25,8 +28,8 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(numbers: &[Number]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let sum = iterated_add(numbers);
-    let magnitude = magnitude(&sum);
+    let sum: Number = numbers.iter().cloned().sum();
+    let magnitude = sum.magnitude();
 
     PartAnswer::new(magnitude, start.elapsed().unwrap())
 }
@@ -42,282 +45,207 @@ fn part_two(numbers: &[Number]) -> PartAnswer {
                 continue;
             }
 
-            let first_plus_second = add(first, second);
-            let second_plus_first = add(second, first);
+            // addition isn't commutative, but both orders come up as the loops swap around
+            let first_plus_second = first.clone() + second.clone();
 
-            max_magnitude = max_magnitude.max(magnitude(&first_plus_second));
-            max_magnitude = max_magnitude.max(magnitude(&second_plus_first));
+            max_magnitude = max_magnitude.max(first_plus_second.magnitude());
         }
     }
 
     PartAnswer::new(max_magnitude, start.elapsed().unwrap())
 }
 
-fn iterated_add(numbers: &[Number]) -> Number {
-    let first = &numbers[0];
-    let second = &numbers[1];
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Number {
+    Regular(u32),
+    Pair(Box<Number>, Box<Number>),
+}
 
-    let mut out = add(first, second);
+impl Number {
+    fn pair(left: Number, right: Number) -> Number {
+        Number::Pair(Box::new(left), Box::new(right))
+    }
 
-    for number in numbers.iter().skip(2) {
-        out = add(&out, number);
+    fn magnitude(&self) -> u64 {
+        match self {
+            Number::Regular(value) => *value as u64,
+            Number::Pair(left, right) => 3 * left.magnitude() + 2 * right.magnitude(),
+        }
     }
 
-    out
-}
+    /**
+     * Applies the first reduction step that applies, returning which one it was. Explosions
+     * always take priority over splits
+     */
+    fn reduce_once(&mut self) -> Option<ReductionStep> {
+        if self.explode() {
+            Some(ReductionStep::Explode)
+        } else if self.split() {
+            Some(ReductionStep::Split)
+        } else {
+            None
+        }
+    }
 
-fn add(first: &Number, second: &Number) -> Number {
-    // add 3 to combined lengths for open + close brackets and comma
-    let new_length = first.len() + second.len() + 3;
+    /**
+     * Adds two numbers, calling `on_step` with the number after the addition and again after every
+     * reduction step until it's fully reduced
+     */
+    fn add_with<F: FnMut(ReductionStep, &Number)>(self, other: Number, mut on_step: F) -> Number {
+        let mut number = Number::pair(self, other);
 
-    let mut number = Vec::with_capacity(new_length);
+        on_step(ReductionStep::Addition, &number);
 
-    number.push(Symbol::OpenBracket);
+        while let Some(step) = number.reduce_once() {
+            on_step(step, &number);
+        }
 
-    number.extend(first.symbols.iter().copied());
-    number.push(Symbol::Comma);
-    number.extend(second.symbols.iter().copied());
+        number
+    }
 
-    number.push(Symbol::CloseBracket);
+    /// Every step of adding two numbers, so the last number in the trace is the sum
+    #[cfg(test)]
+    fn add_with_trace(self, other: Number) -> Vec<(ReductionStep, Number)> {
+        let mut trace = vec![];
 
-    let number: Number = number.into();
+        self.add_with(other, |step, number| trace.push((step, number.clone())));
 
-    reduce(&number)
-}
+        trace
+    }
 
-fn reduce(number: &Number) -> Number {
-    let mut before = number.clone();
-    debug!("reducing {:?}", before);
-    loop {
-        let after = explode(&before);
-        if before != after {
-            debug!("exploded {:?}", after);
-            before = after;
-            continue;
-        }
+    /**
+     * Explodes the leftmost pair nested inside four pairs, if there is one. Its left value is added
+     * to the first regular number to its left, its right value to the first regular number to its
+     * right, and the pair itself is replaced with 0
+     */
+    fn explode(&mut self) -> bool {
+        self.explode_at_depth(0).is_some()
+    }
 
-        let after = split(&after);
-        if before != after {
-            debug!("split    {:?}", after);
-            before = after;
-            continue;
-        }
+    // returns the values that still need to be added to the left and right of the exploded pair
+    fn explode_at_depth(&mut self, depth: usize) -> Option<(Option<u32>, Option<u32>)> {
+        let (left, right) = match self {
+            Number::Regular(_) => return None,
+            Number::Pair(left, right) => (left, right),
+        };
 
-        if before == after {
-            return before;
-        }
-    }
-}
+        if depth >= 4 {
+            if let (Number::Regular(left_value), Number::Regular(right_value)) =
+                (left.as_ref(), right.as_ref())
+            {
+                let carry = (Some(*left_value), Some(*right_value));
 
-fn explode(number: &Number) -> Number {
-    let mut result = Vec::new();
-
-    let mut current_depth = 0;
-    let mut carryover = 0;
-    let mut should_check_left = true;
-    let mut last_placed = Symbol::Comma;
-    let mut applied_count = 0;
-    let mut skip_next_closing_bracket = false;
-
-    for symbol in number.symbols.iter() {
-        if let Symbol::Comma = symbol {
-            if last_placed != Symbol::Comma {
-                result.push(*symbol);
-                last_placed = Symbol::Comma;
-            }
-        } else if let Symbol::OpenBracket = symbol {
-            current_depth += 1;
-            if current_depth <= 4 || applied_count == 2 {
-                result.push(*symbol);
-                last_placed = Symbol::OpenBracket;
-            }
-        } else if let Symbol::CloseBracket = symbol {
-            if current_depth <= 4 || applied_count == 2 && !skip_next_closing_bracket {
-                result.push(*symbol);
-                last_placed = Symbol::CloseBracket;
+                *self = Number::Regular(0);
+
+                return Some(carry);
             }
-            current_depth -= 1;
-            skip_next_closing_bracket = false;
-        } else if let Symbol::Number(current_number) = symbol {
-            if current_depth > 4 && applied_count < 2 {
-                skip_next_closing_bracket = true;
-                applied_count += 1;
-                if should_check_left {
-                    debug!("searching number to the left");
-
-                    let mut stack = Vec::new();
-
-                    // look back to find a number
-                    while let Some(popped) = result.pop() {
-                        debug!("checking {}", popped);
-                        if let Symbol::Number(left_number) = popped {
-                            let new_left_number = left_number + current_number;
-                            debug!(
-                                "found left number {}, new combined number is {}",
-                                left_number, new_left_number
-                            );
-                            stack.push(Symbol::Number(new_left_number));
-                            break;
-                        } else {
-                            let current_depth_modifier = match popped {
-                                Symbol::OpenBracket => -1,
-                                Symbol::CloseBracket => 1,
-                                _ => 0,
-                            };
-
-                            current_depth += current_depth_modifier;
-                            stack.push(popped);
-                        }
-                    }
-
-                    // replay the symbols
-                    while let Some(popped) = stack.pop() {
-                        result.push(popped);
-
-                        let current_depth_modifier = match popped {
-                            Symbol::OpenBracket => 1,
-                            Symbol::CloseBracket => -1,
-                            _ => 0,
-                        };
-
-                        current_depth += current_depth_modifier;
-                    }
-
-                    // push a 0 for current exploded pair
-                    result.push(Symbol::Number(0));
-                    if last_placed != Symbol::Comma {
-                        result.push(Symbol::Comma);
-                        last_placed = Symbol::Comma;
-                    }
-
-                    should_check_left = false;
-                } else {
-                    // carry current number to next number
-                    carryover = *current_number;
-                    should_check_left = true;
-                }
-            } else {
-                let current_number = current_number + carryover;
-                carryover = 0;
-
-                result.push(Symbol::Number(current_number));
-                last_placed = Symbol::Number(0);
+        }
+
+        if let Some((left_carry, right_carry)) = left.explode_at_depth(depth + 1) {
+            if let Some(value) = right_carry {
+                right.add_to_leftmost(value);
             }
+
+            return Some((left_carry, None));
         }
-    }
 
-    result.into()
-}
+        if let Some((left_carry, right_carry)) = right.explode_at_depth(depth + 1) {
+            if let Some(value) = left_carry {
+                left.add_to_rightmost(value);
+            }
 
-fn split(number: &Number) -> Number {
-    let mut result = Vec::with_capacity(number.len() + 10);
+            return Some((None, right_carry));
+        }
 
-    let mut applied = false;
+        None
+    }
 
-    for symbol in &number.symbols {
-        if let Symbol::Number(number) = symbol {
-            let number = *number;
+    fn add_to_leftmost(&mut self, value: u32) {
+        match self {
+            Number::Regular(regular) => *regular += value,
+            Number::Pair(left, _) => left.add_to_leftmost(value),
+        }
+    }
 
-            if number >= 10 && !applied {
-                applied = true;
-                result.push(Symbol::OpenBracket);
+    fn add_to_rightmost(&mut self, value: u32) {
+        match self {
+            Number::Regular(regular) => *regular += value,
+            Number::Pair(_, right) => right.add_to_rightmost(value),
+        }
+    }
 
-                let first = (number as f32 / 2.0).floor() as u8;
-                let second = (number as f32 / 2.0).ceil() as u8;
+    /**
+     * Splits the leftmost regular number that's 10 or greater, if there is one, into a pair of its
+     * halves, rounding the left half down and the right half up
+     */
+    fn split(&mut self) -> bool {
+        match self {
+            Number::Regular(value) if *value >= 10 => {
+                let half = *value / 2;
 
-                result.push(Symbol::Number(first));
-                result.push(Symbol::Comma);
-                result.push(Symbol::Number(second));
+                *self = Number::pair(Number::Regular(half), Number::Regular(*value - half));
 
-                result.push(Symbol::CloseBracket);
-            } else {
-                result.push(*symbol);
+                true
             }
-        } else {
-            result.push(*symbol);
+            Number::Regular(_) => false,
+            Number::Pair(left, right) => left.split() || right.split(),
         }
     }
-
-    result.into()
 }
 
-fn magnitude(number: &Number) -> u64 {
-    let mut magnitude = 0;
-
-    let mut symbol_multiplier = 1;
+impl Add for Number {
+    type Output = Number;
 
-    for symbol in &number.symbols {
-        match symbol {
-            Symbol::OpenBracket => symbol_multiplier *= 3,
-            Symbol::CloseBracket => symbol_multiplier /= 2,
-            Symbol::Comma => symbol_multiplier = (symbol_multiplier * 2) / 3,
-            Symbol::Number(number) => magnitude += *number as u64 * symbol_multiplier as u64,
-        };
+    fn add(self, other: Number) -> Number {
+        self.add_with(other, |step, number| debug!("after {step}: {number}"))
     }
-
-    magnitude
 }
 
-#[derive(PartialEq, Clone)]
-struct Number {
-    symbols: Vec<Symbol>,
+impl Sum for Number {
+    /// Panics when there's nothing to add, since there's no empty snailfish number
+    fn sum<I: Iterator<Item = Number>>(iter: I) -> Number {
+        iter.reduce(Add::add)
+            .expect("can't add up an empty list of snailfish numbers")
+    }
 }
 
-impl Number {
-    fn new(symbols: Vec<Symbol>) -> Number {
-        Number { symbols }
-    }
+impl FromStr for Number {
+    type Err = AdventOfCodeError;
 
-    fn len(&self) -> usize {
-        self.symbols.len()
+    fn from_str(s: &str) -> Result<Number, AdventOfCodeError> {
+        all_consuming(number)(s)
+            .map(|(_, number)| number)
+            .map_err(|_| AdventOfCodeError::NomParseError)
     }
 }
 
-impl core::fmt::Debug for Number {
+impl Display for Number {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let out = self
-            .symbols
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>()
-            .join("");
-
-        write!(f, "{}", out)
-    }
-}
-
-impl From<Vec<Symbol>> for Number {
-    fn from(symbols: Vec<Symbol>) -> Number {
-        Number::new(symbols)
+        match self {
+            Number::Regular(value) => write!(f, "{value}"),
+            Number::Pair(left, right) => write!(f, "[{left},{right}]"),
+        }
     }
 }
 
-#[derive(PartialEq, Clone, Copy)]
-enum Symbol {
-    OpenBracket,
-    CloseBracket,
-    Comma,
-    Number(u8),
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ReductionStep {
+    Addition,
+    Explode,
+    Split,
 }
 
-impl core::fmt::Debug for Symbol {
+impl Display for ReductionStep {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::OpenBracket => write!(f, "["),
-            Self::CloseBracket => write!(f, "]"),
-            Self::Comma => write!(f, ","),
-            Self::Number(number) => write!(f, "{}", number),
+            ReductionStep::Addition => write!(f, "addition"),
+            ReductionStep::Explode => write!(f, "explode"),
+            ReductionStep::Split => write!(f, "split"),
         }
     }
 }
 
-impl Display for Symbol {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
-    }
-}
-
-fn parse_symbols(i: &str) -> Vec<Number> {
+fn parse_numbers(i: &str) -> Vec<Number> {
     all_consuming(numbers)(i).unwrap().1
 }
 
@@ -326,207 +254,162 @@ fn numbers(i: &str) -> IResult<&str, Vec<Number>> {
 }
 
 fn number(i: &str) -> IResult<&str, Number> {
-    into(many1(symbol))(i)
-}
-
-fn symbol(i: &str) -> IResult<&str, Symbol> {
-    alt((open_bracket, regular_number, comma, close_bracket))(i)
-}
-
-fn open_bracket(i: &str) -> IResult<&str, Symbol> {
-    value(Symbol::OpenBracket, tag("["))(i)
+    alt((regular_number, pair))(i)
 }
 
-fn regular_number(i: &str) -> IResult<&str, Symbol> {
-    map(unsigned_number, Symbol::Number)(i)
+fn regular_number(i: &str) -> IResult<&str, Number> {
+    map(unsigned_number, Number::Regular)(i)
 }
 
-fn comma(i: &str) -> IResult<&str, Symbol> {
-    value(Symbol::Comma, tag(","))(i)
-}
-
-fn close_bracket(i: &str) -> IResult<&str, Symbol> {
-    value(Symbol::CloseBracket, tag("]"))(i)
+fn pair(i: &str) -> IResult<&str, Number> {
+    map(
+        delimited(tag("["), separated_pair(number, tag(","), number), tag("]")),
+        |(left, right)| Number::pair(left, right),
+    )(i)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn parse(s: &str) -> Number {
+        s.parse().unwrap()
+    }
+
     #[test]
     fn test_number() {
         assert_eq!(
             number("[1,2]"),
-            Ok((
-                "",
-                vec![
-                    Symbol::OpenBracket,
-                    Symbol::Number(1),
-                    Symbol::Comma,
-                    Symbol::Number(2),
-                    Symbol::CloseBracket
-                ]
-                .into()
-            ))
+            Ok(("", Number::pair(Number::Regular(1), Number::Regular(2))))
+        );
+        assert_eq!(
+            "[1,2".parse::<Number>(),
+            Err(AdventOfCodeError::NomParseError)
         );
     }
 
     #[test]
-    fn test_add_number_simple() {
-        let first = vec![
-            Symbol::OpenBracket,
-            Symbol::Number(1),
-            Symbol::Number(2),
-            Symbol::CloseBracket,
-        ]
-        .into();
-        let second = vec![
-            Symbol::OpenBracket,
-            Symbol::Number(3),
-            Symbol::Number(4),
-            Symbol::CloseBracket,
-        ]
-        .into();
-
-        let added = add(&first, &second);
+    fn test_display() {
+        let input = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]";
+
+        assert_eq!(parse(input).to_string(), input);
+    }
 
+    #[test]
+    fn test_add_number_simple() {
         assert_eq!(
-            added,
-            vec![
-                Symbol::OpenBracket,
-                Symbol::OpenBracket,
-                Symbol::Number(1),
-                Symbol::Number(2),
-                Symbol::CloseBracket,
-                Symbol::Comma,
-                Symbol::OpenBracket,
-                Symbol::Number(3),
-                Symbol::Number(4),
-                Symbol::CloseBracket,
-                Symbol::CloseBracket
-            ]
-            .into()
-        )
+            parse("[1,2]") + parse("[[3,4],5]"),
+            parse("[[1,2],[[3,4],5]]")
+        );
     }
 
     #[test]
     fn test_add_number_example() {
-        let first = number("[[[[4,3],4],4],[7,[[8,4],9]]]").unwrap().1;
-        let second = number("[1,1]").unwrap().1;
+        let added = parse("[[[[4,3],4],4],[7,[[8,4],9]]]") + parse("[1,1]");
 
-        let added = add(&first, &second);
-        let expected = number("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]").unwrap().1;
-        assert_eq!(added, expected);
+        assert_eq!(added, parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]"));
     }
 
     #[test]
-    fn test_add_number_example_2() {
-        let first = number("[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]")
-            .unwrap()
-            .1;
-        let second = number("[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]").unwrap().1;
-
-        let added = add(&first, &second);
-        let expected = number("[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]")
-            .unwrap()
-            .1;
-        assert_eq!(added, expected);
+    fn test_add_with_trace() {
+        let trace: Vec<String> = parse("[[[[4,3],4],4],[7,[[8,4],9]]]")
+            .add_with_trace(parse("[1,1]"))
+            .into_iter()
+            .map(|(step, number)| format!("after {step}: {number}"))
+            .collect();
+
+        assert_eq!(
+            trace,
+            vec![
+                "after addition: [[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]",
+                "after explode: [[[[0,7],4],[7,[[8,4],9]]],[1,1]]",
+                "after explode: [[[[0,7],4],[15,[0,13]]],[1,1]]",
+                "after split: [[[[0,7],4],[[7,8],[0,13]]],[1,1]]",
+                "after split: [[[[0,7],4],[[7,8],[0,[6,7]]]],[1,1]]",
+                "after explode: [[[[0,7],4],[[7,8],[6,0]]],[8,1]]",
+            ]
+        );
     }
 
     #[test]
     fn test_magnitude() {
-        let parsed = number("[[1,2],[[3,4],5]]").unwrap().1;
-        assert_eq!(magnitude(&parsed), 143);
-
-        let parsed = number("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]").unwrap().1;
-        assert_eq!(magnitude(&parsed), 1384);
-
-        let parsed = number("[[[[1,1],[2,2]],[3,3]],[4,4]]").unwrap().1;
-        assert_eq!(magnitude(&parsed), 445);
-
-        let parsed = number("[[[[3,0],[5,3]],[4,4]],[5,5]]").unwrap().1;
-        assert_eq!(magnitude(&parsed), 791);
-
-        let parsed = number("[[[[5,0],[7,4]],[5,5]],[6,6]]").unwrap().1;
-        assert_eq!(magnitude(&parsed), 1137);
-
-        let parsed = number("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]")
-            .unwrap()
-            .1;
-        assert_eq!(magnitude(&parsed), 3488);
+        assert_eq!(parse("[[1,2],[[3,4],5]]").magnitude(), 143);
+        assert_eq!(parse("[[[[0,7],4],[[7,8],[6,0]]],[8,1]]").magnitude(), 1384);
+        assert_eq!(parse("[[[[1,1],[2,2]],[3,3]],[4,4]]").magnitude(), 445);
+        assert_eq!(parse("[[[[3,0],[5,3]],[4,4]],[5,5]]").magnitude(), 791);
+        assert_eq!(parse("[[[[5,0],[7,4]],[5,5]],[6,6]]").magnitude(), 1137);
+        assert_eq!(
+            parse("[[[[8,7],[7,7]],[[8,6],[7,7]]],[[[0,7],[6,6]],[8,7]]]").magnitude(),
+            3488
+        );
     }
 
     #[test]
     fn test_example() {
-        let numbers = parse_symbols("[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]\n[[[5,[2,8]],4],[5,[[9,9],0]]]\n[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]\n[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]\n[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]\n[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]\n[[[[5,4],[7,7]],8],[[8,3],8]]\n[[9,3],[[9,9],[6,[4,9]]]]\n[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]\n[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]");
-        debug!("{} numbers", numbers.len());
+        let numbers = parse_numbers("[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]\n[[[5,[2,8]],4],[5,[[9,9],0]]]\n[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]\n[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]\n[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]\n[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]\n[[[[5,4],[7,7]],8],[[8,3],8]]\n[[9,3],[[9,9],[6,[4,9]]]]\n[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]\n[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]");
 
-        let sum = iterated_add(&numbers);
-        let expected_sum = number("[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]")
-            .unwrap()
-            .1;
+        let sum: Number = numbers.iter().cloned().sum();
 
-        assert_eq!(sum, expected_sum);
-
-        let magnitude = magnitude(&sum);
-
-        assert_eq!(magnitude, 4140);
+        assert_eq!(
+            sum,
+            parse("[[[[6,6],[7,6]],[[7,7],[7,0]]],[[[7,7],[7,7]],[[7,8],[9,9]]]]")
+        );
+        assert_eq!(sum.magnitude(), 4140);
     }
 
     #[test]
-    fn test_add_iterated() {
-        let numbers = parse_symbols("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]");
-        let sum = iterated_add(&numbers);
-        let expected_sum = number("[[[[3,0],[5,3]],[4,4]],[5,5]]").unwrap().1;
-        assert_eq!(sum, expected_sum);
+    fn test_sum() {
+        let numbers = parse_numbers("[1,1]\n[2,2]\n[3,3]\n[4,4]\n[5,5]");
+
+        assert_eq!(
+            numbers.into_iter().sum::<Number>(),
+            parse("[[[[3,0],[5,3]],[4,4]],[5,5]]")
+        );
     }
 
     #[test]
     fn test_larger_example() {
-        let first = number("[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]")
-            .unwrap()
-            .1;
-        let second = number("[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]").unwrap().1;
-
-        let sum = add(&first, &second);
+        let sum = parse("[[[0,[4,5]],[0,0]],[[[4,5],[2,6]],[9,5]]]")
+            + parse("[7,[[[3,7],[4,3]],[[6,3],[8,8]]]]");
 
-        let expected_sum = number("[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]")
-            .unwrap()
-            .1;
-
-        assert_eq!(sum, expected_sum);
+        assert_eq!(
+            sum,
+            parse("[[[[4,0],[5,4]],[[7,7],[6,0]]],[[8,[7,7]],[[7,9],[5,0]]]]")
+        );
     }
 
     #[test]
     fn test_split() {
-        let parsed = number("[[[[0,7],4],[15,[0,13]]],[1,1]]").unwrap().1;
+        let mut number = parse("[[[[0,7],4],[15,[0,13]]],[1,1]]");
+
+        assert!(number.split());
+        assert_eq!(number, parse("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]"));
 
-        let after = split(&parsed);
-        let expected = number("[[[[0,7],4],[[7,8],[0,13]]],[1,1]]").unwrap().1;
+        let mut number = parse("[1,2]");
 
-        assert_eq!(after, expected);
+        assert!(!number.split());
     }
 
     #[test]
     fn test_explode() {
-        let parsed = number("[[[[[9,8],1],2],3],4]").unwrap().1;
-        let after = explode(&parsed);
-        let expected = number("[[[[0,9],2],3],4]").unwrap().1;
-        assert_eq!(after, expected);
-
-        let parsed = number("[7,[6,[5,[4,[3,2]]]]]").unwrap().1;
-        let after = explode(&parsed);
-        let expected = number("[7,[6,[5,[7,0]]]]").unwrap().1;
-        assert_eq!(after, expected);
-
-        let parsed = number("[[6,[5,[4,[3,2]]]],1]").unwrap().1;
-        let after = explode(&parsed);
-        let expected = number("[[6,[5,[7,0]]],3]").unwrap().1;
-        assert_eq!(after, expected);
-
-        let parsed = number("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]").unwrap().1;
-        let after = explode(&parsed);
-        let expected = number("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]").unwrap().1;
-        assert_eq!(after, expected);
+        let explode = |s: &str| {
+            let mut number = parse(s);
+            assert!(number.explode());
+            number
+        };
+
+        assert_eq!(explode("[[[[[9,8],1],2],3],4]"), parse("[[[[0,9],2],3],4]"));
+        assert_eq!(explode("[7,[6,[5,[4,[3,2]]]]]"), parse("[7,[6,[5,[7,0]]]]"));
+        assert_eq!(explode("[[6,[5,[4,[3,2]]]],1]"), parse("[[6,[5,[7,0]]],3]"));
+        assert_eq!(
+            explode("[[3,[2,[1,[7,3]]]],[6,[5,[4,[3,2]]]]]"),
+            parse("[[3,[2,[8,0]]],[9,[5,[4,[3,2]]]]]")
+        );
+        assert_eq!(
+            explode("[[[[[4,3],4],4],[7,[[8,4],9]]],[1,1]]"),
+            parse("[[[[0,7],4],[7,[[8,4],9]]],[1,1]]")
+        );
+
+        assert!(!parse("[[[[0,9],2],3],4]").explode());
     }
 }
@@ -1,12 +1,9 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::Debug,
-    ops::Sub,
-};
+use std::collections::{HashMap, HashSet};
 use std::time::{Duration, SystemTime};
 use crate::common::{
-    parse::{number, unsigned_number},
     answer::*,
+    geometry::{Matrix3, Vector3},
+    parse::{number, unsigned_number},
 };
 use log::debug;
 use nom::{
@@ -35,10 +32,7 @@ pub fn run() -> AdventOfCodeResult {
     Ok((part_one, part_two))
 }
 
-fn part_one(
-    absolute_beacon_locations: &HashSet<Coordinate>,
-    preprocessing: &Duration,
-) -> PartAnswer {
+fn part_one(absolute_beacon_locations: &HashSet<Vector3>, preprocessing: &Duration) -> PartAnswer {
     let start = SystemTime::now();
 
     let total_beacons = absolute_beacon_locations.len();
@@ -46,14 +40,14 @@ fn part_one(
     PartAnswer::new(total_beacons, start.elapsed().unwrap() + *preprocessing)
 }
 
-fn part_two(scanner_locations: &HashMap<u8, Coordinate>, preprocessing: &Duration) -> PartAnswer {
+fn part_two(scanner_locations: &HashMap<u8, Vector3>, preprocessing: &Duration) -> PartAnswer {
     let start = SystemTime::now();
 
     let mut max_manhattan_distance = 0;
 
     for (_, outer) in scanner_locations.iter() {
         for (_, inner) in scanner_locations.iter() {
-            max_manhattan_distance = max_manhattan_distance.max(outer.l1_norm(inner));
+            max_manhattan_distance = max_manhattan_distance.max(outer.manhattan_distance(inner));
         }
     }
 
@@ -65,7 +59,7 @@ fn part_two(scanner_locations: &HashMap<u8, Coordinate>, preprocessing: &Duratio
 
 fn find_absolute_coordinates_for_scanners_and_beacons(
     scanners: &[ScannerView],
-) -> (HashMap<u8, Coordinate>, HashSet<Coordinate>) {
+) -> (HashMap<u8, Vector3>, HashSet<Vector3>) {
     let scanner_0 = scanners
         .iter()
         .find(|scanner| scanner.id == 0)
@@ -75,7 +69,7 @@ fn find_absolute_coordinates_for_scanners_and_beacons(
     let mut known_scanner_locations = HashMap::new();
     let mut known_coordinates = HashSet::new();
 
-    known_scanner_locations.insert(0, Coordinate::new(0, 0, 0));
+    known_scanner_locations.insert(0, Vector3::ZERO);
     known_coordinates.extend(&scanner_0.beacons);
 
     while known_scanner_locations.len() < scanners.len() {
@@ -98,22 +92,22 @@ fn find_absolute_coordinates_for_scanners_and_beacons(
 }
 
 fn find_scanner_position_and_true_beacon_locations(
-    known_coordinates: &HashSet<Coordinate>,
+    known_coordinates: &HashSet<Vector3>,
     other: &ScannerView,
-) -> Option<(Coordinate, Vec<Coordinate>)> {
+) -> Option<(Vector3, Vec<Vector3>)> {
     // check all rotations
-    for rotation in Rotation::all() {
+    for rotation in Matrix3::proper_rotations() {
         let current_scanner = other.rotate(&rotation);
 
         for scanner_0_coordinate in known_coordinates {
             for scanner_1_coordinate in &current_scanner.beacons {
-                let offset = scanner_1_coordinate - scanner_0_coordinate;
+                let offset = *scanner_1_coordinate - *scanner_0_coordinate;
 
                 let mut matches = 0;
                 let mut offset_scanner_1_coordinates = Vec::new();
 
                 for scanner_1_coordinate in &current_scanner.beacons {
-                    let adjusted = scanner_1_coordinate - &offset;
+                    let adjusted = *scanner_1_coordinate - offset;
                     if known_coordinates.contains(&adjusted) {
                         matches += 1;
                     }
@@ -121,7 +115,7 @@ fn find_scanner_position_and_true_beacon_locations(
                 }
 
                 if matches >= 12 {
-                    let scanner_1_location = &Coordinate::new(0, 0, 0) - &offset;
+                    let scanner_1_location = -offset;
                     return Some((scanner_1_location, offset_scanner_1_coordinates));
                 }
             }
@@ -134,11 +128,11 @@ fn find_scanner_position_and_true_beacon_locations(
 #[derive(Debug, PartialEq, Clone)]
 struct ScannerView {
     id: u8,
-    beacons: Vec<Coordinate>,
+    beacons: Vec<Vector3>,
 }
 
 impl ScannerView {
-    fn new(id: u8, beacons: Vec<Coordinate>) -> ScannerView {
+    fn new(id: u8, beacons: Vec<Vector3>) -> ScannerView {
         ScannerView { id, beacons }
     }
 
@@ -161,11 +155,11 @@ impl ScannerView {
         fingerprints
     }
 
-    fn rotate(&self, rotation: &Rotation) -> ScannerView {
+    fn rotate(&self, rotation: &Matrix3) -> ScannerView {
         let beacons = self
             .beacons
             .iter()
-            .map(|coordinate| coordinate.rotate(rotation))
+            .map(|coordinate| *rotation * *coordinate)
             .collect();
 
         ScannerView {
@@ -177,13 +171,13 @@ impl ScannerView {
 
 #[derive(Debug, PartialEq)]
 struct SegmentAndFingerprint {
-    c1: Coordinate,
-    c2: Coordinate,
+    c1: Vector3,
+    c2: Vector3,
     fingerprint: Fingerprint,
 }
 
 impl SegmentAndFingerprint {
-    fn new(c1: Coordinate, c2: Coordinate, fingerprint: Fingerprint) -> SegmentAndFingerprint {
+    fn new(c1: Vector3, c2: Vector3, fingerprint: Fingerprint) -> SegmentAndFingerprint {
         SegmentAndFingerprint {
             c1,
             c2,
@@ -194,183 +188,18 @@ impl SegmentAndFingerprint {
 
 #[derive(Debug, PartialEq)]
 struct Fingerprint {
-    l1_norm: i32,
-    l1_max: i32,
+    l1_norm: i64,
+    l1_max: i64,
 }
 
 impl Fingerprint {
-    fn new(c1: &Coordinate, c2: &Coordinate) -> Fingerprint {
-        let l1_x = (c1.x - c2.x).abs();
-        let l1_y = (c1.y - c2.y).abs();
-        let l1_z = (c1.z - c2.z).abs();
-
-        let l1_norm = l1_x + l1_y + l1_z;
-        let l1_max = l1_x.max(l1_y).max(l1_z);
-
-        Fingerprint { l1_norm, l1_max }
-    }
-}
-
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-struct Coordinate {
-    x: i32,
-    y: i32,
-    z: i32,
-}
-
-impl Coordinate {
-    fn new(x: i32, y: i32, z: i32) -> Coordinate {
-        Coordinate { x, y, z }
-    }
-
-    fn l1_norm(&self, other: &Self) -> u32 {
-        (self.x - other.x).abs() as u32
-            + (self.y - other.y).abs() as u32
-            + (self.z - other.z).abs() as u32
-    }
+    fn new(c1: &Vector3, c2: &Vector3) -> Fingerprint {
+        let difference = *c1 - *c2;
 
-    fn rotate(&self, rotation: &Rotation) -> Coordinate {
-        let mut x = self.x;
-        let mut y = self.y;
-        let mut z = self.z;
-
-        match rotation.negation {
-            Negation::XYZ => {}
-            Negation::XYNegZ => z *= -1,
-            Negation::XNegYZ => y *= -1,
-            Negation::XNegYNegZ => {
-                y *= -1;
-                z *= -1;
-            }
-            Negation::NegXYZ => x *= -1,
-            Negation::NegXYNegZ => {
-                x *= -1;
-                z *= -1;
-            }
-            Negation::NegXNegYZ => {
-                x *= -1;
-                y *= -1;
-            }
-            Negation::NegXNegYNegZ => {
-                x *= -1;
-                y *= -1;
-                z *= -1;
-            }
-        };
-
-        match rotation.coordinate_order {
-            CoordinateOrder::XYZ => (x, y, z),
-            CoordinateOrder::XZY => (x, z, y),
-            CoordinateOrder::YXZ => (y, x, z),
-            CoordinateOrder::YZX => (y, z, x),
-            CoordinateOrder::ZXY => (z, x, y),
-            CoordinateOrder::ZYX => (z, y, x),
+        Fingerprint {
+            l1_norm: difference.manhattan(),
+            l1_max: difference.chebyshev(),
         }
-        .into()
-    }
-}
-
-impl Sub<&Coordinate> for &Coordinate {
-    type Output = Coordinate;
-
-    fn sub(self, rhs: &Coordinate) -> Self::Output {
-        let x = self.x - rhs.x;
-        let y = self.y - rhs.y;
-        let z = self.z - rhs.z;
-
-        Coordinate::new(x, y, z)
-    }
-}
-
-impl From<(i32, i32, i32)> for Coordinate {
-    fn from(tuple: (i32, i32, i32)) -> Coordinate {
-        let (x, y, z) = tuple;
-        Coordinate::new(x, y, z)
-    }
-}
-
-impl Debug for Coordinate {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({},{},{})", self.x, self.y, self.z)
-    }
-}
-
-#[derive(Debug, PartialEq, Clone, Copy)]
-struct Rotation {
-    negation: Negation,
-    coordinate_order: CoordinateOrder,
-}
-
-impl Rotation {
-    fn new(negation: Negation, coordinate_order: CoordinateOrder) -> Rotation {
-        Rotation {
-            negation,
-            coordinate_order,
-        }
-    }
-
-    fn all() -> Vec<Rotation> {
-        let mut rotations = Vec::new();
-
-        for negation in Negation::values() {
-            for coordinate_order in CoordinateOrder::values() {
-                rotations.push(Rotation::new(negation, coordinate_order));
-            }
-        }
-
-        rotations
-    }
-}
-
-#[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum Negation {
-    XYZ,
-    XYNegZ,
-    XNegYZ,
-    XNegYNegZ,
-    NegXYZ,
-    NegXYNegZ,
-    NegXNegYZ,
-    NegXNegYNegZ,
-}
-
-impl Negation {
-    fn values() -> Vec<Negation> {
-        vec![
-            Negation::XYZ,
-            Negation::XYNegZ,
-            Negation::XNegYZ,
-            Negation::XNegYNegZ,
-            Negation::NegXYZ,
-            Negation::NegXYNegZ,
-            Negation::NegXNegYZ,
-            Negation::NegXNegYNegZ,
-        ]
-    }
-}
-
-#[allow(clippy::upper_case_acronyms)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum CoordinateOrder {
-    XYZ,
-    XZY,
-    YXZ,
-    YZX,
-    ZXY,
-    ZYX,
-}
-
-impl CoordinateOrder {
-    fn values() -> Vec<CoordinateOrder> {
-        vec![
-            CoordinateOrder::XYZ,
-            CoordinateOrder::XZY,
-            CoordinateOrder::YXZ,
-            CoordinateOrder::YZX,
-            CoordinateOrder::ZXY,
-            CoordinateOrder::ZYX,
-        ]
     }
 }
 
@@ -395,11 +224,11 @@ fn scanner_id(i: &str) -> IResult<&str, u8> {
     delimited(tag("--- scanner "), unsigned_number, tag(" ---"))(i)
 }
 
-fn coordinates(i: &str) -> IResult<&str, Vec<Coordinate>> {
+fn coordinates(i: &str) -> IResult<&str, Vec<Vector3>> {
     separated_list1(tag("\n"), coordinate)(i)
 }
 
-fn coordinate(i: &str) -> IResult<&str, Coordinate> {
+fn coordinate(i: &str) -> IResult<&str, Vector3> {
     into(map(
         tuple((number, comma, number, comma, number)),
         |(x, _, y, _, z)| (x, y, z),
@@ -421,12 +250,11 @@ mod tests {
 
     #[test]
     fn test_rotation() {
-        let coordinate = Coordinate::new(1, 2, 3);
-
-        let rotation = Rotation::new(Negation::XYZ, CoordinateOrder::XZY);
+        let view = ScannerView::new(0, vec![Vector3::new(1, 2, 3)]);
 
-        let rotated = coordinate.rotate(&rotation);
+        let rotation = Matrix3::new([[0, 0, 1], [0, -1, 0], [1, 0, 0]]);
 
-        assert_eq!(rotated, Coordinate::new(1, 3, 2));
+        assert!(Matrix3::proper_rotations().contains(&rotation));
+        assert_eq!(view.rotate(&rotation).beacons, vec![Vector3::new(3, -2, 1)]);
     }
 }
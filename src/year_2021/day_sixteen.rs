@@ -1,12 +1,15 @@
+use std::fmt::Display;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
-use crate::common::{parse::ParseResult, answer::*};
+use crate::common::{parse::{unsigned_number, ParseResult}, answer::*};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take},
-    character::complete::multispace0,
-    combinator::{all_consuming, map, map_res},
+    character::complete::{alpha1, multispace0, multispace1},
+    combinator::{all_consuming, map, map_opt, map_res, opt, verify},
     multi::{length_count, length_value, many0, many1},
-    sequence::{preceded, terminated, tuple},
+    sequence::{delimited, preceded, terminated, tuple},
+    IResult,
 };
 
 pub fn run() -> AdventOfCodeResult {
@@ -164,6 +167,219 @@ impl Packet {
             }
         }
     }
+
+    /**
+     * Builds an operator packet from its S-expression name, checking that the comparison operators
+     * have exactly two sub-packets
+     */
+    fn operator(name: &str, version: u8, sub_packets: Vec<Packet>) -> Option<Packet> {
+        let packet = match name {
+            "sum" => Packet::Sum {
+                version,
+                sub_packets,
+            },
+            "product" => Packet::Product {
+                version,
+                sub_packets,
+            },
+            "min" => Packet::Minimum {
+                version,
+                sub_packets,
+            },
+            "max" => Packet::Maximum {
+                version,
+                sub_packets,
+            },
+            "gt" if sub_packets.len() == 2 => Packet::GreaterThan {
+                version,
+                sub_packets,
+            },
+            "lt" if sub_packets.len() == 2 => Packet::LessThan {
+                version,
+                sub_packets,
+            },
+            "eq" if sub_packets.len() == 2 => Packet::EqualTo {
+                version,
+                sub_packets,
+            },
+            _ => return None,
+        };
+
+        Some(packet)
+    }
+
+    fn get_name(&self) -> &'static str {
+        match &self {
+            Packet::Literal { .. } => "lit",
+            Packet::Sum { .. } => "sum",
+            Packet::Product { .. } => "product",
+            Packet::Minimum { .. } => "min",
+            Packet::Maximum { .. } => "max",
+            Packet::GreaterThan { .. } => "gt",
+            Packet::LessThan { .. } => "lt",
+            Packet::EqualTo { .. } => "eq",
+        }
+    }
+
+    fn write_expression(&self, f: &mut std::fmt::Formatter<'_>, depth: usize) -> std::fmt::Result {
+        write!(f, "({}", self.get_name())?;
+
+        if self.get_version() != 0 {
+            write!(f, "@{}", self.get_version())?;
+        }
+
+        if let Packet::Literal { literal, .. } = self {
+            return write!(f, " {})", literal);
+        }
+
+        for sub_packet in self.get_sub_packets() {
+            if f.alternate() {
+                write!(f, "\n{}", "  ".repeat(depth + 1))?;
+            } else {
+                write!(f, " ")?;
+            }
+
+            sub_packet.write_expression(f, depth + 1)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+/**
+ * Encoding is test tooling: it turns hand-written packets into transmissions, so the decoder can be
+ * tested on packets the puzzle doesn't give. Nothing in a run needs it, so it's only built for tests
+ */
+#[cfg(test)]
+impl Packet {
+    fn get_type_id(&self) -> u8 {
+        match &self {
+            Packet::Sum { .. } => 0,
+            Packet::Product { .. } => 1,
+            Packet::Minimum { .. } => 2,
+            Packet::Maximum { .. } => 3,
+            Packet::Literal { .. } => 4,
+            Packet::GreaterThan { .. } => 5,
+            Packet::LessThan { .. } => 6,
+            Packet::EqualTo { .. } => 7,
+        }
+    }
+
+    /**
+     * Encodes the packet as a BITS transmission, with every operator packet using the same length
+     * type. The transmission is padded with zeros to a whole number of bytes, like the puzzle's
+     * examples. Returns `None` if a version doesn't fit in 3 bits or the sub-packets don't fit in
+     * the length field
+     */
+    fn encode(&self, length_type: LengthType) -> Option<String> {
+        let mut bits = String::new();
+        self.write_bits(length_type, &mut bits)?;
+
+        while bits.len() % 8 != 0 {
+            bits.push('0');
+        }
+
+        let hex = bits
+            .as_bytes()
+            .chunks(4)
+            .map(|chunk| {
+                let nibble = chunk
+                    .iter()
+                    .fold(0, |acc, bit| acc << 1 | (bit - b'0') as u32);
+                std::char::from_digit(nibble, 16)
+                    .unwrap()
+                    .to_ascii_uppercase()
+            })
+            .collect();
+
+        Some(hex)
+    }
+
+    fn write_bits(&self, length_type: LengthType, bits: &mut String) -> Option<()> {
+        use std::fmt::Write;
+
+        if self.get_version() >= 8 {
+            return None;
+        }
+
+        write!(bits, "{:03b}{:03b}", self.get_version(), self.get_type_id()).unwrap();
+
+        if let Packet::Literal { literal, .. } = self {
+            // groups of four bits, most significant first, each prefixed by whether another follows
+            let significant_bits = (usize::BITS - literal.leading_zeros()) as usize;
+            let groups = ((significant_bits + 3) / 4).max(1);
+
+            for group in (0..groups).rev() {
+                let continued = if group == 0 { 0 } else { 1 };
+                write!(bits, "{}{:04b}", continued, (literal >> (group * 4)) & 0xF).unwrap();
+            }
+
+            return Some(());
+        }
+
+        let sub_packets = self.get_sub_packets();
+
+        match length_type {
+            LengthType::TotalLength => {
+                let mut sub_packet_bits = String::new();
+
+                for sub_packet in sub_packets.iter() {
+                    sub_packet.write_bits(length_type, &mut sub_packet_bits)?;
+                }
+
+                if sub_packet_bits.len() >= 1 << 15 {
+                    return None;
+                }
+
+                write!(bits, "0{:015b}{}", sub_packet_bits.len(), sub_packet_bits).unwrap();
+            }
+            LengthType::SubPacketCount => {
+                if sub_packets.len() >= 1 << 11 {
+                    return None;
+                }
+
+                write!(bits, "1{:011b}", sub_packets.len()).unwrap();
+
+                for sub_packet in sub_packets.iter() {
+                    sub_packet.write_bits(length_type, bits)?;
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+/**
+ * Renders the packet as an S-expression such as `(sum (lit 1) (max@3 (lit 2) (lit 3)))`, where a
+ * version other than 0 follows the name after an `@`. The alternate form `{:#}` puts each
+ * sub-packet on its own indented line
+ */
+impl Display for Packet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_expression(f, 0)
+    }
+}
+
+/// Reads back the S-expression form written by `Display`, in either layout
+impl FromStr for Packet {
+    type Err = AdventOfCodeError;
+
+    fn from_str(s: &str) -> Result<Packet, AdventOfCodeError> {
+        all_consuming(delimited(multispace0, expression, multispace0))(s)
+            .map(|(_, packet)| packet)
+            .map_err(|_| AdventOfCodeError::NomParseError)
+    }
+}
+
+/// How an operator packet records the extent of its sub-packets
+#[cfg(test)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum LengthType {
+    /// Length type 0, the total number of bits in the sub-packets
+    TotalLength,
+    /// Length type 1, the number of immediate sub-packets
+    SubPacketCount,
 }
 
 fn parse_packets(i: &str) -> Vec<Packet> {
@@ -320,6 +536,39 @@ fn hex_digit(i: &str) -> ParseResult<String> {
     )(i)
 }
 
+fn expression(i: &str) -> IResult<&str, Packet> {
+    delimited(
+        tag("("),
+        alt((literal_expression, operator_expression)),
+        tag(")"),
+    )(i)
+}
+
+fn literal_expression(i: &str) -> IResult<&str, Packet> {
+    map(
+        tuple((tag("lit"), expression_version, multispace1, unsigned_number)),
+        |(_, version, _, literal)| Packet::Literal { version, literal },
+    )(i)
+}
+
+fn operator_expression(i: &str) -> IResult<&str, Packet> {
+    map_opt(
+        tuple((
+            alpha1,
+            expression_version,
+            many1(preceded(multispace1, expression)),
+        )),
+        |(name, version, sub_packets)| Packet::operator(name, version, sub_packets),
+    )(i)
+}
+
+fn expression_version(i: &str) -> IResult<&str, u8> {
+    map(
+        opt(preceded(tag("@"), verify(unsigned_number, |v: &u8| *v < 8))),
+        Option::unwrap_or_default,
+    )(i)
+}
+
 #[cfg(test)]
 mod tests {
     use nom::{multi::many0, sequence::terminated};
@@ -483,4 +732,88 @@ mod tests {
         let packets = parse_packets("A0016C880162017C3686B18A3D4780");
         assert_eq!(sum_packet_versions(&packets), 31);
     }
+
+    #[test]
+    fn test_encode() {
+        let literal = Packet::Literal {
+            version: 6,
+            literal: 2021,
+        };
+        assert_eq!(
+            literal.encode(LengthType::TotalLength),
+            Some("D2FE28".into())
+        );
+
+        let less_than: Packet = "(lt@1 (lit@6 10) (lit@2 20))".parse().unwrap();
+        assert_eq!(
+            less_than.encode(LengthType::TotalLength),
+            Some("38006F45291200".into())
+        );
+
+        let maximum: Packet = "(max@7 (lit@2 1) (lit@4 2) (lit@1 3))".parse().unwrap();
+        assert_eq!(
+            maximum.encode(LengthType::SubPacketCount),
+            Some("EE00D40C823060".into())
+        );
+
+        let too_new = Packet::Literal {
+            version: 8,
+            literal: 0,
+        };
+        assert_eq!(too_new.encode(LengthType::TotalLength), None);
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for hex in [
+            "8A004A801A8002F478",
+            "620080001611562C8802118E34",
+            "C0015000016115A2E0802F182340",
+            "A0016C880162017C3686B18A3D4780",
+            "9C0141080250320F1802104A08",
+        ] {
+            let packets = parse_packets(hex);
+
+            for length_type in [LengthType::TotalLength, LengthType::SubPacketCount] {
+                let encoded = packets[0].encode(length_type).unwrap();
+
+                assert_eq!(parse_packets(&encoded), packets);
+            }
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let packets = parse_packets("9C0141080250320F1802104A08");
+
+        assert_eq!(
+            packets[0].to_string(),
+            "(eq@4 (sum@2 (lit@2 1) (lit@4 3)) (product@6 (lit 2) (lit@2 2)))"
+        );
+        assert_eq!(
+            format!("{:#}", packets[0]),
+            "(eq@4
+  (sum@2
+    (lit@2 1)
+    (lit@4 3))
+  (product@6
+    (lit 2)
+    (lit@2 2)))"
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        let packet: Packet = "(sum (lit 1) (max (lit 2) (lit 3)))".parse().unwrap();
+
+        assert_eq!(packet.value(), 4);
+        assert_eq!(packet.version_sum(), 0);
+        assert_eq!(packet.to_string().parse::<Packet>(), Ok(packet.clone()));
+        assert_eq!(format!("{:#}", packet).parse::<Packet>(), Ok(packet));
+
+        assert!("(gt (lit 1))".parse::<Packet>().is_err());
+        assert!("(lit@8 1)".parse::<Packet>().is_err());
+        assert!("(pow (lit 1) (lit 2))".parse::<Packet>().is_err());
+        assert!("(sum)".parse::<Packet>().is_err());
+    }
 }
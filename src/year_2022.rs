@@ -1,4 +1,4 @@
-use crate::common::{answer::*, result_logger};
+use crate::common::{answer::*, output::OutputSettings, result_logger};
 
 mod day_eight;
 mod day_eighteen;
@@ -22,15 +22,15 @@ mod day_twenty;
 mod day_twenty_one;
 mod day_two;
 
-pub fn run_all() -> Result<(), AdventOfCodeError> {
+pub fn run_all(output: &OutputSettings) -> Result<(), AdventOfCodeError> {
     for i in 1..=21 {
-        run_day(i)?;
+        run_day(i, output)?;
     }
 
     Ok(())
 }
 
-pub fn run_day(day: u8) -> Result<(), AdventOfCodeError> {
+pub fn run_day(day: u8, output: &OutputSettings) -> Result<(), AdventOfCodeError> {
     let result = match day {
         1 => day_one::run()?,
         2 => day_two::run()?,
@@ -40,15 +40,15 @@ pub fn run_day(day: u8) -> Result<(), AdventOfCodeError> {
         6 => day_six::run()?,
         7 => day_seven::run()?,
         8 => day_eight::run()?,
-        9 => day_nine::run()?,
+        9 => day_nine::run(output)?,
         10 => day_ten::run()?,
         11 => day_eleven::run()?,
         12 => day_twelve::run()?,
         13 => day_thirteen::run()?,
-        14 => day_fourteen::run()?,
+        14 => day_fourteen::run(output)?,
         15 => day_fifteen::run()?,
-        16 => day_sixteen::run()?,
-        17 => day_seventeen::run()?,
+        16 => day_sixteen::run(output)?,
+        17 => day_seventeen::run(output)?,
         18 => day_eighteen::run()?,
         19 => day_nineteen::run()?,
         20 => day_twenty::run()?,
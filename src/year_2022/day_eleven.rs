@@ -1,9 +1,12 @@
 use std::{
     collections::{HashMap, VecDeque},
+    convert::TryFrom,
+    fmt::Debug,
+    hash::Hash,
     iter,
 };
 use std::time::SystemTime;
-use crate::common::answer::*;
+use crate::common::{answer::*, math::checked_lcm};
 use log::debug;
 use nom::{
     branch::alt,
@@ -20,119 +23,185 @@ pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-11.txt");
     let monkeys = parse(input);
 
-    let part_one = part_one(monkeys.clone());
-    let part_two = part_two(monkeys);
+    let part_one = part_one(monkeys.clone())?;
+    let part_two = part_two(monkeys)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(monkeys: Vec<Monkey>) -> PartAnswer {
+fn part_one(monkeys: Vec<Monkey>) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut game = KeepAwayGame::new(monkeys, true);
+    let mut game = KeepAwayGame::new(monkeys, Relieved);
 
     for _ in 0..20 {
-        game.play_round();
+        game.play_round()?;
     }
 
-    let mut values: Vec<usize> = game.inspected_items_by_monkey.values().cloned().collect();
+    let answer = monkey_business(game.inspected_items_by_monkey.values().cloned());
+
+    let elapsed = start.elapsed().unwrap();
+
+    Ok(PartAnswer::new(answer, elapsed))
+}
+
+fn part_two(monkeys: Vec<Monkey>) -> Result<PartAnswer, AdventOfCodeError> {
+    let start = SystemTime::now();
+
+    let modulus = test_modulus(&monkeys)?;
+    let game = KeepAwayGame::new(monkeys, |item| Residue::new(item, modulus));
+
+    let answer = monkey_business(game.inspections_by_following_items(10_000)?);
+
+    let elapsed = start.elapsed().unwrap();
+
+    Ok(PartAnswer::new(answer, elapsed))
+}
+
+/// The product of the two largest numbers of inspections
+fn monkey_business<I: IntoIterator<Item = usize>>(inspections: I) -> usize {
+    let mut values: Vec<usize> = inspections.into_iter().collect();
 
     values.sort_unstable();
 
-    let answer = values
+    values
         .into_iter()
         .rev()
         .take(2)
         .reduce(|first, second| first * second)
-        .unwrap();
+        .unwrap()
+}
 
-    let elapsed = start.elapsed().unwrap();
+/**
+ * For part 2, the numbers get large very quickly
+ * The key observation (from Reddit) is that every monkey's "divisible-by" test is preserved modulo
+ * any common multiple of the test integers. The product is one such multiple, but the least common
+ * multiple is the smallest, so we can always "reduce" the worry level modulo the lcm and end up
+ * giving the item to the same monkey as without taking the modulus
+ */
+fn test_modulus(monkeys: &[Monkey]) -> Result<usize, AdventOfCodeError> {
+    monkeys.iter().try_fold(1_usize, |modulus, monkey| {
+        checked_lcm(modulus as u64, monkey.test.divisible_by as u64)
+            .and_then(|modulus| usize::try_from(modulus).ok())
+            .ok_or_else(|| {
+                AdventOfCodeError::Overflow("the lcm of the test divisors is too big".to_string())
+            })
+    })
+}
+
+/// An item's worry level, and how it's kept manageable after each inspection
+trait Worry: Copy + Eq + Hash + Debug {
+    /// The new worry level once a monkey has inspected the item, or `None` if it overflowed
+    fn inspect(self, operation: &Operation) -> Option<Self>;
 
-    PartAnswer::new(answer, elapsed)
+    fn is_divisible_by(self, divisor: usize) -> bool;
 }
 
-fn part_two(monkeys: Vec<Monkey>) -> PartAnswer {
-    let start = SystemTime::now();
+/// A worry level that's divided by three after every inspection, out of relief that the item
+/// wasn't damaged
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct Relieved(usize);
 
-    let mut game = KeepAwayGame::new(monkeys, false);
+impl Worry for Relieved {
+    fn inspect(self, operation: &Operation) -> Option<Relieved> {
+        operation.apply(self.0).map(|value| Relieved(value / 3))
+    }
 
-    for _ in 0..10_000 {
-        game.play_round();
+    fn is_divisible_by(self, divisor: usize) -> bool {
+        self.0 % divisor == 0
     }
+}
 
-    let mut values: Vec<usize> = game.inspected_items_by_monkey.values().cloned().collect();
+/**
+ * A worry level kept modulo a multiple of every test divisor
+ *
+ * Only the residue is needed to find which monkey an item is thrown to, and since the value stays
+ * below the modulus, squaring it can't overflow as long as the square of the modulus fits
+ */
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct Residue {
+    value: usize,
+    modulus: usize,
+}
 
-    values.sort_unstable();
+impl Residue {
+    fn new(value: usize, modulus: usize) -> Residue {
+        Residue {
+            value: value % modulus,
+            modulus,
+        }
+    }
+}
 
-    let answer = values
-        .into_iter()
-        .rev()
-        .take(2)
-        .reduce(|first, second| first * second)
-        .unwrap();
+impl Worry for Residue {
+    fn inspect(self, operation: &Operation) -> Option<Residue> {
+        operation
+            .apply(self.value)
+            .map(|value| Residue::new(value, self.modulus))
+    }
 
-    let elapsed = start.elapsed().unwrap();
+    fn is_divisible_by(self, divisor: usize) -> bool {
+        debug_assert!(
+            self.modulus % divisor == 0,
+            "{} doesn't divide the modulus",
+            divisor
+        );
+
+        self.value % divisor == 0
+    }
+}
 
-    PartAnswer::new(answer, elapsed)
+/// Where an item is at the start of a round
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct ItemState<W> {
+    monkey_id: usize,
+    worry: W,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-struct KeepAwayGame {
+struct KeepAwayGame<W> {
     monkey_ids: Vec<usize>,
     monkeys: Vec<Monkey>,
+    items_by_monkey: Vec<VecDeque<W>>,
     round: usize,
     inspected_items_by_monkey: HashMap<usize, usize>,
-    reduce_worry_level: bool,
-    modulo: usize,
 }
 
-/**
- * For part 2, the numbers get large very quickly
- * The key observation (from Reddit) is that the monkeys "divisible-by" test integers are all prime
- * Multiplying them together gives a safe modulus, so we can always "reduce" the worry level
- * modulo that modulus and end up giving the item to the same monkey as without taking the modulus
- */
-impl KeepAwayGame {
-    fn new(monkeys: Vec<Monkey>, reduce_worry_level: bool) -> KeepAwayGame {
-        let monkey_ids = monkeys.iter().map(|monkey| monkey.id.clone()).collect();
+impl<W: Worry> KeepAwayGame<W> {
+    fn new<F: Fn(usize) -> W>(monkeys: Vec<Monkey>, worry: F) -> KeepAwayGame<W> {
+        let monkey_ids = monkeys.iter().map(|monkey| monkey.id).collect();
         let mut inspected_items_by_monkey = HashMap::new();
 
-        let mut modulo = 1;
-
         for monkey in monkeys.iter() {
             inspected_items_by_monkey.insert(monkey.id, 0);
-
-            modulo *= monkey.test.divisible_by;
         }
 
+        let items_by_monkey = monkeys
+            .iter()
+            .map(|monkey| monkey.items.iter().map(|item| worry(*item)).collect())
+            .collect();
+
         KeepAwayGame {
             monkey_ids,
             monkeys,
+            items_by_monkey,
             round: 0,
             inspected_items_by_monkey,
-            reduce_worry_level,
-            modulo,
         }
     }
 
-    fn play_round(&mut self) {
+    fn play_round(&mut self) -> Result<(), AdventOfCodeError> {
         for id in &self.monkey_ids {
-            let mut new_items_for_monkeys: Vec<Vec<usize>> =
+            let mut new_items_for_monkeys: Vec<Vec<W>> =
                 iter::repeat(vec![]).take(self.monkeys.len()).collect();
 
-            if let Some(monkey) = self.monkeys.get_mut(*id) {
+            if let Some(monkey) = self.monkeys.get(*id) {
                 let mut items_inspected = 0;
 
-                while let Some(item) = monkey.items.pop_front() {
+                while let Some(item) = self.items_by_monkey[*id].pop_front() {
                     items_inspected += 1;
 
-                    let new_item_value = monkey.get_new_value(item);
-
-                    let new_item_value = if self.reduce_worry_level {
-                        new_item_value / 3
-                    } else {
-                        new_item_value % self.modulo
-                    };
+                    let new_item_value = monkey.inspect(item)?;
 
                     let next_monkey_id = monkey.get_next_monkey(new_item_value);
 
@@ -144,8 +213,8 @@ impl KeepAwayGame {
                 }
             }
 
-            for monkey in self.monkeys.iter_mut() {
-                monkey.items.extend(&new_items_for_monkeys[monkey.id]);
+            for (items, new_items) in self.items_by_monkey.iter_mut().zip(new_items_for_monkeys) {
+                items.extend(new_items);
             }
         }
 
@@ -160,6 +229,120 @@ impl KeepAwayGame {
 
             debug!("\n");
         }
+
+        Ok(())
+    }
+
+    /**
+     * Moves a single item through one round, adding each inspection along the way to `inspections`
+     *
+     * Items never affect each other, so one item's path only depends on where it starts. Monkeys
+     * take turns in order, so an item thrown to a later monkey is inspected again in the same
+     * round, and an item thrown to an earlier monkey waits for the next round.
+     */
+    fn play_item_round(
+        &self,
+        state: ItemState<W>,
+        inspections: &mut [usize],
+    ) -> Result<ItemState<W>, AdventOfCodeError> {
+        let ItemState {
+            mut monkey_id,
+            mut worry,
+        } = state;
+
+        loop {
+            let monkey = &self.monkeys[monkey_id];
+
+            inspections[monkey_id] += 1;
+            worry = monkey.inspect(worry)?;
+
+            let next_monkey_id = monkey.get_next_monkey(worry);
+
+            if next_monkey_id <= monkey_id {
+                return Ok(ItemState {
+                    monkey_id: next_monkey_id,
+                    worry,
+                });
+            }
+
+            monkey_id = next_monkey_id;
+        }
+    }
+
+    /**
+     * The number of times each monkey inspects an item over `rounds` rounds
+     *
+     * There are only so many states an item can be in at the start of a round, so its trajectory
+     * eventually loops. Once a state repeats, the inspections from the rest of the rounds are
+     * whole laps of the loop plus part of one more, without needing to play them
+     */
+    fn item_inspections(
+        &self,
+        state: ItemState<W>,
+        rounds: usize,
+    ) -> Result<Vec<usize>, AdventOfCodeError> {
+        let mut seen: HashMap<ItemState<W>, usize> = HashMap::new();
+
+        // the total inspections by each monkey after each number of rounds
+        let mut history = vec![vec![0; self.monkeys.len()]];
+
+        let mut state = state;
+
+        for round in 0..rounds {
+            if let Some(&cycle_start) = seen.get(&state) {
+                let cycle_length = round - cycle_start;
+                let remaining_rounds = rounds - round;
+                let (cycles, rest) = (
+                    remaining_rounds / cycle_length,
+                    remaining_rounds % cycle_length,
+                );
+
+                debug!("{state:?} repeats every {cycle_length} rounds from round {cycle_start}");
+
+                return Ok((0..self.monkeys.len())
+                    .map(|id| {
+                        let per_cycle = history[round][id] - history[cycle_start][id];
+                        let partial_cycle =
+                            history[cycle_start + rest][id] - history[cycle_start][id];
+
+                        history[round][id] + cycles * per_cycle + partial_cycle
+                    })
+                    .collect());
+            }
+
+            seen.insert(state, round);
+
+            let mut inspections = history[round].clone();
+            state = self.play_item_round(state, &mut inspections)?;
+            history.push(inspections);
+        }
+
+        Ok(history.pop().unwrap())
+    }
+
+    /// The same as playing `rounds` rounds, but following each item on its own
+    fn inspections_by_following_items(
+        &self,
+        rounds: usize,
+    ) -> Result<Vec<usize>, AdventOfCodeError> {
+        let mut inspections = vec![0; self.monkeys.len()];
+
+        for (monkey_id, items) in self.items_by_monkey.iter().enumerate() {
+            for worry in items.iter() {
+                let state = ItemState {
+                    monkey_id,
+                    worry: *worry,
+                };
+
+                let item_inspections = self.item_inspections(state, rounds)?;
+
+                for (total, count) in inspections.iter_mut().zip(item_inspections) {
+                    *total += count;
+                }
+            }
+        }
+
+        Ok(inspections)
     }
 }
 
@@ -183,12 +366,13 @@ impl Operation {
         }
     }
 
-    fn apply(&self, value: usize) -> usize {
+    /// Returns `None` if the new value overflows
+    fn apply(&self, value: usize) -> Option<usize> {
         match (self.operation_type, self.term) {
-            (OperationType::Add, Term::Old) => value + value,
-            (OperationType::Add, Term::Constant(constant)) => value + constant,
-            (OperationType::Multiply, Term::Old) => value * value,
-            (OperationType::Multiply, Term::Constant(constant)) => value * constant,
+            (OperationType::Add, Term::Old) => value.checked_add(value),
+            (OperationType::Add, Term::Constant(constant)) => value.checked_add(constant),
+            (OperationType::Multiply, Term::Old) => value.checked_mul(value),
+            (OperationType::Multiply, Term::Constant(constant)) => value.checked_mul(constant),
         }
     }
 }
@@ -215,8 +399,8 @@ impl Test {
         }
     }
 
-    fn apply(&self, value: usize) -> usize {
-        if value % self.divisible_by == 0 {
+    fn apply<W: Worry>(&self, worry: W) -> usize {
+        if worry.is_divisible_by(self.divisible_by) {
             self.true_monkey_id
         } else {
             self.false_monkey_id
@@ -227,15 +411,13 @@ impl Test {
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct Monkey {
     id: usize,
-    items: VecDeque<usize>,
+    items: Vec<usize>,
     operation: Operation,
     test: Test,
 }
 
 impl Monkey {
     fn new(id: usize, items: Vec<usize>, operation: Operation, test: Test) -> Monkey {
-        let items = items.into_iter().collect();
-
         Monkey {
             id,
             items,
@@ -244,12 +426,17 @@ impl Monkey {
         }
     }
 
-    fn get_new_value(&self, value: usize) -> usize {
-        self.operation.apply(value)
+    fn inspect<W: Worry>(&self, worry: W) -> Result<W, AdventOfCodeError> {
+        worry.inspect(&self.operation).ok_or_else(|| {
+            AdventOfCodeError::Overflow(format!(
+                "monkey {} inspecting an item with worry level {:?}",
+                self.id, worry
+            ))
+        })
     }
 
-    fn get_next_monkey(&self, value: usize) -> usize {
-        self.test.apply(value)
+    fn get_next_monkey<W: Worry>(&self, worry: W) -> usize {
+        self.test.apply(worry)
     }
 }
 
@@ -361,4 +548,138 @@ mod tests {
     fn test_false_test() {
         assert_eq!(false_test("    If false: throw to monkey 7"), Ok(("", 7)));
     }
+
+    const EXAMPLE: &str = "Monkey 0:
+  Starting items: 79, 98
+  Operation: new = old * 19
+  Test: divisible by 23
+    If true: throw to monkey 2
+    If false: throw to monkey 3
+
+Monkey 1:
+  Starting items: 54, 65, 75, 74
+  Operation: new = old + 6
+  Test: divisible by 19
+    If true: throw to monkey 2
+    If false: throw to monkey 0
+
+Monkey 2:
+  Starting items: 79, 60, 97
+  Operation: new = old * old
+  Test: divisible by 13
+    If true: throw to monkey 1
+    If false: throw to monkey 3
+
+Monkey 3:
+  Starting items: 74
+  Operation: new = old + 3
+  Test: divisible by 17
+    If true: throw to monkey 0
+    If false: throw to monkey 1
+";
+
+    #[test]
+    fn test_operation_overflow() {
+        let square = Operation::new(OperationType::Multiply, Term::Old);
+
+        assert_eq!(square.apply(3), Some(9));
+        assert_eq!(square.apply(usize::MAX / 2), None);
+    }
+
+    #[test]
+    fn test_residue() {
+        let modulus = test_modulus(&parse(EXAMPLE)).unwrap();
+        assert_eq!(modulus, 23 * 19 * 13 * 17);
+
+        let square = Operation::new(OperationType::Multiply, Term::Old);
+        let residue = Residue::new(modulus + 5, modulus).inspect(&square).unwrap();
+
+        assert_eq!(residue, Residue::new(25, modulus));
+        assert!(Residue::new(13 * 17, modulus).is_divisible_by(13));
+    }
+
+    #[test]
+    fn test_play_round() {
+        let mut game = KeepAwayGame::new(parse(EXAMPLE), Relieved);
+
+        game.play_round().unwrap();
+
+        let worry_levels: Vec<Vec<usize>> = game
+            .items_by_monkey
+            .iter()
+            .map(|items| items.iter().map(|item| item.0).collect())
+            .collect();
+
+        assert_eq!(
+            worry_levels,
+            vec![
+                vec![20, 23, 27, 26],
+                vec![2080, 25, 167, 207, 401, 1046],
+                vec![],
+                vec![]
+            ]
+        );
+
+        for _ in 1..20 {
+            game.play_round().unwrap();
+        }
+
+        assert_eq!(
+            monkey_business(game.inspected_items_by_monkey.values().cloned()),
+            10605
+        );
+    }
+
+    #[test]
+    fn test_following_items() {
+        let monkeys = parse(EXAMPLE);
+        let modulus = test_modulus(&monkeys).unwrap();
+
+        let mut game = KeepAwayGame::new(monkeys.clone(), |item| Residue::new(item, modulus));
+
+        for _ in 0..1000 {
+            game.play_round().unwrap();
+        }
+
+        let played: Vec<usize> = game
+            .monkey_ids
+            .iter()
+            .map(|id| game.inspected_items_by_monkey[id])
+            .collect();
+
+        let game = KeepAwayGame::new(monkeys, |item| Residue::new(item, modulus));
+
+        assert_eq!(played, vec![5204, 4792, 199, 5192]);
+        assert_eq!(game.inspections_by_following_items(1000), Ok(played));
+        assert_eq!(
+            game.inspections_by_following_items(10_000),
+            Ok(vec![52166, 47830, 1938, 52013])
+        );
+
+        // following items works for relieved worry levels too
+        let game = KeepAwayGame::new(parse(EXAMPLE), Relieved);
+
+        assert_eq!(
+            game.inspections_by_following_items(20),
+            Ok(vec![101, 95, 7, 105])
+        );
+    }
+
+    #[test]
+    fn test_inspection_overflow() {
+        let square = Operation::new(OperationType::Multiply, Term::Old);
+        let monkey = Monkey::new(0, vec![usize::MAX / 2], square, Test::new(2, 0, 0));
+
+        assert!(matches!(
+            monkey.inspect(Relieved(usize::MAX / 2)),
+            Err(AdventOfCodeError::Overflow(_))
+        ));
+
+        let mut game = KeepAwayGame::new(vec![monkey], Relieved);
+
+        assert!(matches!(
+            game.play_round(),
+            Err(AdventOfCodeError::Overflow(_))
+        ));
+    }
 }
@@ -1,6 +1,7 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::HashSet;
 use std::time::SystemTime;
 use crate::common::answer::*;
+use crate::common::interval::{Interval, IntervalSet};
 use log::debug;
 use nom::{
     bytes::complete::tag,
@@ -11,11 +12,13 @@ use nom::{
 };
 use crate::common::parse::{finish, number};
 
+const SEARCH_LIMIT: isize = 4_000_000;
+
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-15.txt");
 
     let part_one = part_one(input);
-    let part_two = part_two(input);
+    let part_two = part_two(input)?;
 
     Ok((part_one, part_two))
 }
@@ -25,9 +28,7 @@ fn part_one(input: &str) -> PartAnswer {
 
     let sensors = parse(input);
 
-    let total_exclusion_area = total_exclusion_area(&sensors, 2000000);
-
-    let answer = total_exclusion_area.len();
+    let answer = count_excluded_positions(&sensors, 2000000);
 
     let elapsed = start.elapsed().unwrap();
 
@@ -35,103 +36,60 @@ fn part_one(input: &str) -> PartAnswer {
 }
 
 /**
- * The key insight here is that the one place where a beacon could be lies just out of range of 4 sensors
- * If the beacon were further away from the sensors, the area would be larger than just one square
- * To reduce the runtime, we only need to check the boundaries of each sensor and make sure it doesn't lie within another sensor
+ * Each sensor covers a single contiguous run of x values on any given row, so we can merge those
+ * runs row by row and look for the first row that leaves a gap inside the search area
  */
-fn part_two(input: &str) -> PartAnswer {
+fn part_two(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
     let sensors = parse(input);
 
-    for sensor in &sensors {
-        let boundary = get_closest_possible_beacons(sensor);
-
-        for potential_beacon_location in boundary {
-            let mut is_out_of_range_of_all_sensors = true;
-
-            for sensor in &sensors {
-                if sensor.is_in_range(&potential_beacon_location) {
-                    is_out_of_range_of_all_sensors = false;
-                    break;
-                }
-            }
-
-            if is_out_of_range_of_all_sensors {
-                let (x, y) = potential_beacon_location;
+    let (x, y) = find_distress_beacon(&sensors, SEARCH_LIMIT).ok_or_else(|| {
+        AdventOfCodeError::NoSolution("every position in the search area is covered".to_string())
+    })?;
 
-                let answer = (x * 4_000_000) + y;
+    let answer = (x * 4_000_000) + y;
 
-                let elapsed = start.elapsed().unwrap();
-
-                return PartAnswer::new(answer, elapsed);
-            }
-        }
-    }
+    let elapsed = start.elapsed().unwrap();
 
-    PartAnswer::default()
+    Ok(PartAnswer::new(answer, elapsed))
 }
 
-fn total_exclusion_area(sensors: &[Sensor], y_coordinate: isize) -> HashSet<(isize, isize)> {
-    let sensor_locations: HashSet<(isize, isize)> =
-        sensors.iter().map(|sensor| sensor.location).collect();
-
-    let beacon_locations: HashSet<(isize, isize)> =
-        sensors.iter().map(|sensor| sensor.closest_beacon).collect();
-
-    let mut total_exclusion_area = HashSet::new();
-
-    for sensor in sensors {
-        let exclusion_area = sensor.exclusion_area(y_coordinate);
-
-        total_exclusion_area.extend(exclusion_area);
-    }
-
-    let sensors_and_beacons: HashSet<(isize, isize)> =
-        sensor_locations.union(&beacon_locations).cloned().collect();
-
-    total_exclusion_area
-        .difference(&sensors_and_beacons)
-        .cloned()
+fn row_coverage(sensors: &[Sensor], y_coordinate: isize) -> IntervalSet {
+    sensors
+        .iter()
+        .filter_map(|sensor| sensor.row_coverage(y_coordinate))
         .collect()
 }
 
 /**
- * Returns the closest points to the sensor that _could_ contain beacons
+ * Counts the positions on the row that can't contain a beacon, not counting positions that already
+ * hold a sensor or a beacon
  */
-fn get_closest_possible_beacons(sensor: &Sensor) -> HashSet<(isize, isize)> {
-    let distance = (sensor.radius + 1) as isize;
-
-    let start = (sensor.location.0, sensor.location.1 + distance);
+fn count_excluded_positions(sensors: &[Sensor], y_coordinate: isize) -> u128 {
+    let coverage = row_coverage(sensors, y_coordinate);
 
-    let mut queue = VecDeque::new();
-    queue.push_back(start);
-
-    let mut seen = HashSet::new();
+    let occupied: HashSet<(isize, isize)> = sensors
+        .iter()
+        .flat_map(|sensor| [sensor.location, sensor.closest_beacon])
+        .filter(|(x, y)| *y == y_coordinate && coverage.contains(*x as i64))
+        .collect();
 
-    while let Some(current) = queue.pop_front() {
-        if seen.contains(&current) {
-            continue;
-        }
+    coverage.coverage() - occupied.len() as u128
+}
 
-        seen.insert(current);
+fn find_distress_beacon(sensors: &[Sensor], limit: isize) -> Option<(isize, isize)> {
+    let bounds = Interval::new(0, limit as i64);
 
-        let potential_next: Vec<(isize, isize)> = vec![
-            (current.0 + 1, current.1 + 1),
-            (current.0 - 1, current.1 + 1),
-            (current.0 + 1, current.1 - 1),
-            (current.0 - 1, current.1 - 1),
-        ]
-        .into_iter()
-        .filter(|coordinate| manhattan_distance(&sensor.location, coordinate) == distance as usize)
-        .collect();
+    (0..=limit).find_map(|y| {
+        let gaps = row_coverage(sensors, y).gaps(bounds);
 
-        for potential in potential_next {
-            queue.push_back(potential);
-        }
-    }
+        gaps.intervals().first().map(|gap| {
+            debug!("found gap {:?} on row y={y}", gap);
 
-    seen
+            (gap.start() as isize, y)
+        })
+    })
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -152,39 +110,25 @@ impl Sensor {
         }
     }
 
-    fn is_in_range(&self, other: &(isize, isize)) -> bool {
-        manhattan_distance(&self.location, other) <= self.radius
-    }
-
-    fn exclusion_area(&self, y_coordinate: isize) -> HashSet<(isize, isize)> {
-        let beacon_manhattan_distance = manhattan_distance(&self.location, &self.closest_beacon);
-
-        debug!(
-            "{:?} has distance {} from its closest beacon",
-            self.location, beacon_manhattan_distance
-        );
-
+    /**
+     * Returns the run of x values on the row that are within range of this sensor, if the sensor
+     * reaches the row at all
+     */
+    fn row_coverage(&self, y_coordinate: isize) -> Option<Interval> {
         let distance_to_target_row = self.location.1.abs_diff(y_coordinate);
 
         // check that going to target row in straight line is possible
-        if distance_to_target_row > beacon_manhattan_distance {
-            debug!(
-                "{:?} is too far away from row y={y_coordinate}",
-                self.location
-            );
-            return HashSet::new();
+        if distance_to_target_row > self.radius {
+            return None;
         }
 
-        let remaining_distance = beacon_manhattan_distance - distance_to_target_row;
-
-        let mut exclusion_area = HashSet::new();
+        let remaining_distance = (self.radius - distance_to_target_row) as i64;
+        let x = self.location.0 as i64;
 
-        for x in 0..=remaining_distance {
-            exclusion_area.insert((self.location.0 + x as isize, y_coordinate));
-            exclusion_area.insert((self.location.0 - x as isize, y_coordinate));
-        }
-
-        exclusion_area
+        Some(Interval::new(
+            x - remaining_distance,
+            x + remaining_distance,
+        ))
     }
 }
 
@@ -223,3 +167,43 @@ fn x_coordinate(i: &str) -> IResult<&str, isize> {
 fn y_coordinate(i: &str) -> IResult<&str, isize> {
     preceded(tag("y="), number)(i)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Sensor at x=2, y=18: closest beacon is at x=-2, y=15
+Sensor at x=9, y=16: closest beacon is at x=10, y=16
+Sensor at x=13, y=2: closest beacon is at x=15, y=3
+Sensor at x=12, y=14: closest beacon is at x=10, y=16
+Sensor at x=10, y=20: closest beacon is at x=10, y=16
+Sensor at x=14, y=17: closest beacon is at x=10, y=16
+Sensor at x=8, y=7: closest beacon is at x=2, y=10
+Sensor at x=2, y=0: closest beacon is at x=2, y=10
+Sensor at x=0, y=11: closest beacon is at x=2, y=10
+Sensor at x=20, y=14: closest beacon is at x=25, y=17
+Sensor at x=17, y=20: closest beacon is at x=21, y=22
+Sensor at x=16, y=7: closest beacon is at x=15, y=3
+Sensor at x=14, y=3: closest beacon is at x=15, y=3
+Sensor at x=20, y=1: closest beacon is at x=15, y=3";
+
+    #[test]
+    fn test_sensor_row_coverage() {
+        let sensor = Sensor::new((8, 7), (2, 10));
+
+        assert_eq!(sensor.row_coverage(7), Some(Interval::new(-1, 17)));
+        assert_eq!(sensor.row_coverage(10), Some(Interval::new(2, 14)));
+        assert_eq!(sensor.row_coverage(16), Some(Interval::new(8, 8)));
+        assert_eq!(sensor.row_coverage(17), None);
+    }
+
+    #[test]
+    fn test_count_excluded_positions() {
+        assert_eq!(count_excluded_positions(&parse(EXAMPLE), 10), 26);
+    }
+
+    #[test]
+    fn test_find_distress_beacon() {
+        assert_eq!(find_distress_beacon(&parse(EXAMPLE), 20), Some((14, 11)));
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
 use std::time::SystemTime;
 use crate::common::answer::*;
+use log::debug;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -17,25 +19,28 @@ pub fn run() -> AdventOfCodeResult {
 
     let (stacks, ids, instructions) = parse(input);
 
-    let part_one = part_one(stacks.clone(), ids.clone(), instructions.clone());
-    let part_two = part_two(stacks, ids, instructions);
+    // the CrateMover 9000 moves crates one at a time
+    let mut crane = Crane::new(stacks, ids, InBatchesOf::new(1)?);
+
+    let part_one = part_one(&mut crane, &instructions);
+
+    // rewinding the CrateMover 9000 gets back to the drawing in the input
+    crane.undo_all();
+
+    let part_two = part_two(crane.with_model(AllAtOnce), &instructions);
 
     Ok((part_one, part_two))
 }
 
-fn part_one(
-    stacks: Vec<Vec<CrateId>>,
-    ids: Vec<usize>,
-    instructions: Vec<Instruction>,
-) -> PartAnswer {
+fn part_one(crane: &mut Crane<InBatchesOf>, instructions: &[Instruction]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut crane = Crane::new(stacks, ids);
-
     for instruction in instructions {
-        crane.apply_in_series(&instruction);
+        crane.apply(instruction);
     }
 
+    debug!("after the CrateMover 9000:\n{}", crane.render());
+
     let answer = crane.get_top_of_stacks();
 
     let elapsed = start.elapsed().unwrap();
@@ -43,19 +48,15 @@ fn part_one(
     PartAnswer::new(answer, elapsed)
 }
 
-fn part_two(
-    stacks: Vec<Vec<CrateId>>,
-    ids: Vec<usize>,
-    instructions: Vec<Instruction>,
-) -> PartAnswer {
+fn part_two(mut crane: Crane<AllAtOnce>, instructions: &[Instruction]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut crane = Crane::new(stacks, ids);
-
     for instruction in instructions {
-        crane.apply_in_parallel(&instruction);
+        crane.apply(instruction);
     }
 
+    debug!("after the CrateMover 9001:\n{}", crane.render());
+
     let answer = crane.get_top_of_stacks();
 
     let elapsed = start.elapsed().unwrap();
@@ -63,13 +64,72 @@ fn part_two(
     PartAnswer::new(answer, elapsed)
 }
 
-struct Crane {
+/**
+ * How a crane moves crates between stacks
+ *
+ * Every crane picks up some crates from the top of the source stack and puts them down on the
+ * destination stack in the same order, repeating until it's moved everything. Models only differ in
+ * how many crates each lift takes.
+ */
+trait CraneModel {
+    /// The number of crates picked up in each lift when moving `quantity` crates
+    fn lifts(&self, quantity: usize) -> Vec<usize>;
+}
+
+/// The CrateMover 9001, which moves all the crates at once
+struct AllAtOnce;
+
+impl CraneModel for AllAtOnce {
+    fn lifts(&self, quantity: usize) -> Vec<usize> {
+        vec![quantity]
+    }
+}
+
+/// A crane that can lift up to a fixed number of crates at once
+struct InBatchesOf(NonZeroUsize);
+
+impl InBatchesOf {
+    fn new(batch_size: usize) -> Result<InBatchesOf, AdventOfCodeError> {
+        NonZeroUsize::new(batch_size)
+            .map(InBatchesOf)
+            .ok_or_else(|| {
+                AdventOfCodeError::InvalidInput(
+                    "a crane has to lift at least one crate".to_string(),
+                )
+            })
+    }
+}
+
+impl CraneModel for InBatchesOf {
+    fn lifts(&self, quantity: usize) -> Vec<usize> {
+        let batch_size = self.0.get();
+
+        let mut lifts = vec![batch_size; quantity / batch_size];
+
+        if quantity % batch_size != 0 {
+            lifts.push(quantity % batch_size);
+        }
+
+        lifts
+    }
+}
+
+/**
+ * Stacks of crates and a crane to move them around
+ *
+ * Every instruction that's applied is kept, so they can be undone in reverse order to get back to
+ * the starting layout. Undoing an instruction plays its lifts backwards, from the destination back
+ * to the source.
+ */
+struct Crane<M> {
     crates: HashMap<usize, Vec<char>>,
     ids: Vec<usize>,
+    model: M,
+    undo_log: Vec<Instruction>,
 }
 
-impl Crane {
-    fn new(stacks: Vec<Vec<CrateId>>, ids: Vec<usize>) -> Crane {
+impl<M: CraneModel> Crane<M> {
+    fn new(stacks: Vec<Vec<CrateId>>, ids: Vec<usize>, model: M) -> Crane<M> {
         let mut crates = HashMap::new();
 
         for id in &ids {
@@ -87,7 +147,12 @@ impl Crane {
                 index += 1;
             }
         }
-        Crane { crates, ids }
+        Crane {
+            crates,
+            ids,
+            model,
+            undo_log: vec![],
+        }
     }
 
     fn get_top_of_stacks(&self) -> String {
@@ -98,43 +163,86 @@ impl Crane {
             .collect()
     }
 
-    fn apply_in_series(&mut self, instruction: &Instruction) {
-        for _ in 0..instruction.quantity {
-            let item = self
-                .crates
-                .get_mut(&instruction.source)
-                .unwrap()
-                .pop()
-                .unwrap();
-
-            self.crates
-                .get_mut(&instruction.destination)
-                .unwrap()
-                .push(item);
+    fn apply(&mut self, instruction: &Instruction) {
+        for lift in self.model.lifts(instruction.quantity) {
+            self.lift(instruction.source, instruction.destination, lift);
         }
+
+        self.undo_log.push(*instruction);
     }
 
-    fn apply_in_parallel(&mut self, instruction: &Instruction) {
-        let mut batch = vec![];
+    /// Reverses the most recently applied instruction, returning it
+    fn undo(&mut self) -> Option<Instruction> {
+        let instruction = self.undo_log.pop()?;
 
-        for _ in 0..instruction.quantity {
-            let item = self
-                .crates
-                .get_mut(&instruction.source)
-                .unwrap()
-                .pop()
-                .unwrap();
+        for lift in self.model.lifts(instruction.quantity).into_iter().rev() {
+            self.lift(instruction.destination, instruction.source, lift);
+        }
 
-            batch.push(item);
+        Some(instruction)
+    }
+
+    fn undo_all(&mut self) {
+        while self.undo().is_some() {}
+    }
+
+    /// Swaps in a different crane over the same stacks
+    ///
+    /// Only a crane with nothing to undo can be swapped, since the new model would play the old
+    /// lifts back differently.
+    fn with_model<N: CraneModel>(self, model: N) -> Crane<N> {
+        assert!(
+            self.undo_log.is_empty(),
+            "undo every instruction before changing the crane"
+        );
+
+        Crane {
+            crates: self.crates,
+            ids: self.ids,
+            model,
+            undo_log: vec![],
         }
+    }
+
+    fn lift(&mut self, source: usize, destination: usize, quantity: usize) {
+        let source = self.crates.get_mut(&source).unwrap();
 
-        batch.reverse();
+        let mut batch = source.split_off(source.len() - quantity);
 
         self.crates
-            .get_mut(&instruction.destination)
+            .get_mut(&destination)
             .unwrap()
             .append(&mut batch);
     }
+
+    /// Draws the stacks in the same format as the puzzle input
+    fn render(&self) -> String {
+        let height = self.crates.values().map(Vec::len).max().unwrap_or(0);
+
+        let mut lines: Vec<String> = (0..height)
+            .rev()
+            .map(|level| {
+                self.ids
+                    .iter()
+                    .map(|id| match self.crates[id].get(level) {
+                        Some(value) => format!("[{}]", value),
+                        None => "   ".to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect();
+
+        lines.push(
+            self.ids
+                .iter()
+                .map(|id| format!(" {} ", id))
+                .collect::<Vec<String>>()
+                .join(" "),
+        );
+
+        lines.join("\n")
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -236,6 +344,21 @@ fn instruction(i: &str) -> IResult<&str, Instruction> {
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 
+
+move 1 from 2 to 1
+move 3 from 1 to 3
+move 2 from 2 to 1
+move 1 from 1 to 2";
+
+    const DRAWING: &str = "    [D]    
+[N] [C]    
+[Z] [M] [P]
+ 1   2   3 ";
+
     #[test]
     fn test_crane() {
         let first_row = vec![CrateId::Empty, CrateId::Value('D'), CrateId::Empty];
@@ -246,14 +369,111 @@ mod tests {
             CrateId::Value('P'),
         ];
 
-        let mut crane = Crane::new(vec![first_row, second_row, third_row], vec![1, 2, 3]);
+        let mut crane = Crane::new(
+            vec![first_row, second_row, third_row],
+            vec![1, 2, 3],
+            in_batches_of(1),
+        );
 
         assert_eq!(crane.crates[&1], vec!['Z', 'N']);
         assert_eq!(crane.crates[&2], vec!['M', 'C', 'D']);
         assert_eq!(crane.crates[&3], vec!['P']);
 
-        crane.apply_in_series(&Instruction::new(1, 2, 1));
+        crane.apply(&Instruction::new(1, 2, 1));
 
         assert_eq!(crane.crates[&1], vec!['Z', 'N', 'D']);
     }
+
+    #[test]
+    fn test_lifts() {
+        assert_eq!(in_batches_of(1).lifts(3), vec![1, 1, 1]);
+        assert_eq!(AllAtOnce.lifts(3), vec![3]);
+        assert_eq!(in_batches_of(2).lifts(5), vec![2, 2, 1]);
+        assert_eq!(in_batches_of(2).lifts(4), vec![2, 2]);
+        assert_eq!(in_batches_of(1).lifts(0), vec![]);
+        assert!(matches!(
+            InBatchesOf::new(0),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+    }
+
+    fn in_batches_of(batch_size: usize) -> InBatchesOf {
+        InBatchesOf::new(batch_size).unwrap()
+    }
+
+    fn top_of_stacks<M: CraneModel>(model: M) -> String {
+        let (stacks, ids, instructions) = parse(EXAMPLE);
+        let mut crane = Crane::new(stacks, ids, model);
+
+        for instruction in instructions.iter() {
+            crane.apply(instruction);
+        }
+
+        crane.get_top_of_stacks()
+    }
+
+    #[test]
+    fn test_models() {
+        assert_eq!(top_of_stacks(in_batches_of(1)), "CMZ");
+        assert_eq!(top_of_stacks(AllAtOnce), "MCD");
+        assert_eq!(top_of_stacks(in_batches_of(3)), "MCD");
+        assert_eq!(top_of_stacks(in_batches_of(2)), "MCZ");
+    }
+
+    #[test]
+    fn test_render() {
+        let (stacks, ids, _) = parse(EXAMPLE);
+        let crane = Crane::new(stacks, ids, in_batches_of(1));
+
+        assert_eq!(crane.render(), DRAWING);
+
+        let (stacks, ids) = stacks_and_ids(&crane.render()).unwrap().1;
+
+        assert_eq!(Crane::new(stacks, ids, in_batches_of(1)).render(), DRAWING);
+    }
+
+    #[test]
+    fn test_undo() {
+        let (stacks, ids, instructions) = parse(EXAMPLE);
+        let mut crane = Crane::new(stacks, ids, in_batches_of(2));
+
+        for instruction in instructions.iter() {
+            crane.apply(instruction);
+        }
+
+        assert_eq!(
+            crane.render(),
+            "        [Z]
+        [D]
+        [N]
+[M] [C] [P]
+ 1   2   3 "
+        );
+
+        assert_eq!(crane.undo(), instructions.last().cloned());
+
+        crane.undo_all();
+
+        assert_eq!(crane.undo(), None);
+        assert_eq!(crane.render(), DRAWING);
+    }
+    #[test]
+    fn test_with_model() {
+        let (stacks, ids, instructions) = parse(EXAMPLE);
+        let mut crane = Crane::new(stacks, ids, in_batches_of(1));
+
+        for instruction in instructions.iter() {
+            crane.apply(instruction);
+        }
+
+        crane.undo_all();
+
+        let mut crane = crane.with_model(AllAtOnce);
+
+        for instruction in instructions.iter() {
+            crane.apply(instruction);
+        }
+
+        assert_eq!(crane.get_top_of_stacks(), "MCD");
+    }
 }
@@ -1,7 +1,11 @@
 use std::time::SystemTime;
 use crate::common::answer::*;
+use crate::common::interval::Interval;
 use nom::{
-    bytes::complete::tag, combinator::into, multi::separated_list1, sequence::separated_pair,
+    bytes::complete::tag,
+    combinator::{map, verify},
+    multi::separated_list1,
+    sequence::separated_pair,
     IResult,
 };
 use crate::common::parse::{finish, unsigned_number};
@@ -17,13 +21,13 @@ pub fn run() -> AdventOfCodeResult {
     Ok((part_one, part_two))
 }
 
-fn part_one(assignments: &[(Assignment, Assignment)]) -> PartAnswer {
+fn part_one(assignments: &[(Interval, Interval)]) -> PartAnswer {
     let start = SystemTime::now();
 
     let mut count = 0;
 
     for (first, second) in assignments {
-        if first.completely_overlaps(second) || second.completely_overlaps(first) {
+        if first.contains_interval(second) || second.contains_interval(first) {
             count += 1;
         }
     }
@@ -33,13 +37,13 @@ fn part_one(assignments: &[(Assignment, Assignment)]) -> PartAnswer {
     PartAnswer::new(count, elapsed)
 }
 
-fn part_two(assignments: &[(Assignment, Assignment)]) -> PartAnswer {
+fn part_two(assignments: &[(Interval, Interval)]) -> PartAnswer {
     let start = SystemTime::now();
 
     let mut count = 0;
 
     for (first, second) in assignments {
-        if first.has_any_overlap(second) || second.has_any_overlap(first) {
+        if first.overlaps(second) {
             count += 1;
         }
     }
@@ -49,45 +53,26 @@ fn part_two(assignments: &[(Assignment, Assignment)]) -> PartAnswer {
     PartAnswer::new(count, elapsed)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Assignment {
-    start: u32,
-    end: u32,
-}
-
-impl Assignment {
-    fn completely_overlaps(&self, other: &Self) -> bool {
-        self.start >= other.start && self.end <= other.end
-    }
-
-    fn has_any_overlap(&self, other: &Self) -> bool {
-        self.completely_overlaps(other)
-            || (self.start >= other.start && self.start <= other.end)
-            || (self.end >= other.start && self.end <= other.end)
-    }
-}
-
-impl From<(u32, u32)> for Assignment {
-    fn from(raw: (u32, u32)) -> Assignment {
-        let (start, end) = raw;
-        Assignment { start, end }
-    }
-}
-
-fn parse(i: &str) -> Vec<(Assignment, Assignment)> {
+fn parse(i: &str) -> Vec<(Interval, Interval)> {
     finish(pairs)(i).unwrap().1
 }
 
-fn pairs(i: &str) -> IResult<&str, Vec<(Assignment, Assignment)>> {
+fn pairs(i: &str) -> IResult<&str, Vec<(Interval, Interval)>> {
     separated_list1(tag("\n"), pair)(i)
 }
 
-fn pair(i: &str) -> IResult<&str, (Assignment, Assignment)> {
+fn pair(i: &str) -> IResult<&str, (Interval, Interval)> {
     separated_pair(assignment, tag(","), assignment)(i)
 }
 
-fn assignment(i: &str) -> IResult<&str, Assignment> {
-    into(separated_pair(unsigned_number, tag("-"), unsigned_number))(i)
+fn assignment(i: &str) -> IResult<&str, Interval> {
+    map(
+        verify(
+            separated_pair(unsigned_number, tag("-"), unsigned_number),
+            |(start, end)| start <= end,
+        ),
+        |(start, end)| Interval::new(start, end),
+    )(i)
 }
 
 #[cfg(test)]
@@ -95,13 +80,8 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_has_any_overlap() {
-        let first: Assignment = (2, 6).into();
-        let second: Assignment = (4, 8).into();
-
-        assert_eq!(
-            first.has_any_overlap(&second) || second.has_any_overlap(&first),
-            true
-        );
+    fn test_assignment() {
+        assert_eq!(assignment("2-6"), Ok(("", Interval::new(2, 6))));
+        assert!(assignment("6-2").is_err());
     }
 }
@@ -1,15 +1,22 @@
 use std::collections::HashSet;
+use std::iter;
 use std::time::SystemTime;
+use crate::common::animation::Frame;
 use crate::common::answer::*;
+use crate::common::output::OutputSettings;
 use nom::{
     bytes::complete::tag, combinator::map, multi::separated_list1, sequence::separated_pair,
     IResult,
 };
 use crate::common::parse::{finish, unsigned_number};
 
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-14.txt");
 
+    if output.is_animating() {
+        output.write_animation("2022-14", parse(input, false).into_frames());
+    }
+
     let part_one = part_one(input);
     let part_two = part_two(input);
 
@@ -105,6 +112,47 @@ impl FallingSand {
         }
     }
 
+    /// Yields a frame after every grain of sand, until sand starts flowing into the abyss
+    fn into_frames(mut self) -> impl Iterator<Item = Frame> {
+        let min_x = self.rocks.iter().map(|(x, _)| *x).min().unwrap();
+        let max_x = self.rocks.iter().map(|(x, _)| *x).max().unwrap();
+
+        let mut is_flowing = false;
+
+        iter::from_fn(move || {
+            if is_flowing {
+                return None;
+            }
+
+            let number_of_sand = self.sand.len();
+            self.add_sand();
+            is_flowing = self.sand.len() == number_of_sand;
+
+            Some(self.render(min_x, max_x))
+        })
+    }
+
+    fn render(&self, min_x: usize, max_x: usize) -> Frame {
+        // leave a column on either side so sand can be seen spilling over the edges
+        let mut frame = Frame::filled(max_x - min_x + 3, self.greatest_y + 2, '.');
+
+        let to_frame = |(x, y): &(usize, usize)| (x + 1 - min_x, *y);
+
+        frame.set(500 + 1 - min_x, 0, '+');
+
+        for rock in &self.rocks {
+            let (x, y) = to_frame(rock);
+            frame.set(x, y, '#');
+        }
+
+        for sand in &self.sand {
+            let (x, y) = to_frame(sand);
+            frame.set(x, y, 'o');
+        }
+
+        frame
+    }
+
     fn add_sand(&mut self) {
         let mut current = (500, 0);
 
@@ -286,6 +334,47 @@ mod tests {
         assert_eq!(count, 24);
     }
 
+    #[test]
+    fn test_render() {
+        let mut falling_sand = parse(
+            "498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9",
+            false,
+        );
+
+        for _ in 0..5 {
+            falling_sand.add_sand();
+        }
+
+        assert_eq!(
+            falling_sand.render(494, 503).to_string(),
+            [
+                ".......+....",
+                "............",
+                "............",
+                "............",
+                ".....#...##.",
+                ".....#...#..",
+                "...###...#..",
+                ".......o.#..",
+                ".....oooo#..",
+                ".#########..",
+                "............",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_into_frames() {
+        let falling_sand = parse(
+            "498,4 -> 498,6 -> 496,6\n503,4 -> 502,4 -> 502,9 -> 494,9",
+            false,
+        );
+
+        // one frame per settled grain, plus the grain that falls into the abyss
+        assert_eq!(falling_sand.into_frames().count(), 25);
+    }
+
     #[test]
     fn test_add_sand_until_plugged() {
         let mut falling_sand = parse(
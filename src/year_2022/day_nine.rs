@@ -1,6 +1,8 @@
 use std::{collections::HashSet, iter};
 use std::time::SystemTime;
+use crate::common::animation::Frame;
 use crate::common::answer::*;
+use crate::common::output::OutputSettings;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -11,9 +13,13 @@ use nom::{
 };
 use crate::common::parse::{finish, unsigned_number};
 
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-9.txt");
 
+    if output.is_animating() {
+        output.write_animation("2022-09", rope_frames(parse_instructions(input), 10));
+    }
+
     let directions = parse(input);
 
     let part_one = part_one(&directions);
@@ -95,6 +101,66 @@ impl Rope {
     }
 }
 
+/// Yields a frame after each instruction, framed so the whole path of the head stays in view
+fn rope_frames(
+    instructions: Vec<Vec<HeadMoveDirection>>,
+    number_of_knots: usize,
+) -> impl Iterator<Item = Frame> {
+    let mut head = (0, 0);
+    let mut min = head;
+    let mut max = head;
+
+    for direction in instructions.iter().flatten() {
+        head = direction.apply_to(&head);
+
+        min = (min.0.min(head.0), min.1.min(head.1));
+        max = (max.0.max(head.0), max.1.max(head.1));
+    }
+
+    let mut rope = Rope::new(number_of_knots);
+
+    instructions.into_iter().map(move |instruction| {
+        for direction in &instruction {
+            rope.move_rope(direction);
+        }
+
+        rope.render(min, max)
+    })
+}
+
+impl Rope {
+    fn render(&self, min: (isize, isize), max: (isize, isize)) -> Frame {
+        let width = (max.0 - min.0 + 1) as usize;
+        let height = (max.1 - min.1 + 1) as usize;
+
+        let mut frame = Frame::filled(width, height, '.');
+
+        // y increases up, but frames are drawn from the top down
+        let to_frame = |(x, y): &(isize, isize)| ((x - min.0) as usize, (max.1 - y) as usize);
+
+        for position in &self.tail_positions {
+            let (x, y) = to_frame(position);
+            frame.set(x, y, '#');
+        }
+
+        let (x, y) = to_frame(&(0, 0));
+        frame.set(x, y, 's');
+
+        // draw from the tail forward so knots closer to the head stay on top
+        for (index, knot) in self.knots.iter().enumerate().rev() {
+            let (x, y) = to_frame(knot);
+            let c = match index {
+                0 => 'H',
+                _ => std::char::from_digit(index as u32 % 10, 10).unwrap(),
+            };
+
+            frame.set(x, y, c);
+        }
+
+        frame
+    }
+}
+
 fn are_head_and_tail_touching(head: &(isize, isize), tail: &(isize, isize)) -> bool {
     let (head_x, head_y) = *head;
     let (tail_x, tail_y) = *tail;
@@ -197,11 +263,11 @@ impl TailMoveDirection {
 }
 
 fn parse(i: &str) -> Vec<HeadMoveDirection> {
-    map(finish(all_directions), |vec_of_vecs| {
-        vec_of_vecs.into_iter().flatten().collect()
-    })(i)
-    .unwrap()
-    .1
+    parse_instructions(i).into_iter().flatten().collect()
+}
+
+fn parse_instructions(i: &str) -> Vec<Vec<HeadMoveDirection>> {
+    finish(all_directions)(i).unwrap().1
 }
 
 fn all_directions(i: &str) -> IResult<&str, Vec<Vec<HeadMoveDirection>>> {
@@ -267,6 +333,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rope_frames() {
+        let instructions = parse_instructions("R 4\nU 4\nL 3\nD 1\nR 4\nD 1\nL 5\nR 2");
+
+        let frames: Vec<Frame> = rope_frames(instructions, 2).collect();
+
+        assert_eq!(frames.len(), 8);
+        assert_eq!(
+            frames.last().unwrap().to_string(),
+            ["..##..", "...##.", ".1H##.", "....#.", "s###.."].join("\n")
+        );
+    }
+
     #[test]
     fn test_tail_direction_of() {
         assert_eq!(
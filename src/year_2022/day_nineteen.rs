@@ -1,4 +1,5 @@
-use std::collections::VecDeque;
+use std::collections::HashSet;
+use std::thread;
 use std::time::SystemTime;
 use crate::common::{math::triangular_number, answer::*};
 use log::debug;
@@ -27,17 +28,10 @@ fn part_one(input: &str) -> PartAnswer {
 
     let blueprints = parse(input);
 
-    let mut sum = 0;
-
-    for blueprint in blueprints {
-        let blueprint_id = blueprint.id;
-        let geode_count = search_state_space(&blueprint, 24);
-
-        debug!("Blueprint {blueprint_id} produced {geode_count} geodes");
-
-        let quality_level = blueprint_id * geode_count;
-        sum += quality_level;
-    }
+    let sum: usize = evaluate_blueprints(&blueprints, 24)
+        .iter()
+        .map(|result| result.blueprint_id * result.geodes)
+        .sum();
 
     let elapsed = start.elapsed().unwrap();
 
@@ -51,13 +45,10 @@ fn part_two(input: &str) -> PartAnswer {
 
     let blueprints: Vec<Blueprint> = blueprints.into_iter().take(3).collect();
 
-    let mut product = 1;
-
-    for blueprint in blueprints {
-        let geode_count = search_state_space(&blueprint, 32);
-
-        product *= geode_count;
-    }
+    let product: usize = evaluate_blueprints(&blueprints, 32)
+        .iter()
+        .map(|result| result.geodes)
+        .product();
 
     let elapsed = start.elapsed().unwrap();
 
@@ -65,251 +56,260 @@ fn part_two(input: &str) -> PartAnswer {
 }
 
 /**
- * Return the most geodes that can be produced with this blueprint
+ * Searches every blueprint on its own thread, returning the results in the same order
  */
-fn search_state_space(blueprint: &Blueprint, time_remaining: usize) -> usize {
-    let mut max_geode_count = 0;
+fn evaluate_blueprints(blueprints: &[Blueprint], time_budget: usize) -> Vec<SearchResult> {
+    let results: Vec<SearchResult> = thread::scope(|scope| {
+        let handles: Vec<_> = blueprints
+            .iter()
+            .map(|blueprint| scope.spawn(move || search(blueprint, time_budget)))
+            .collect();
 
-    let mut queue = VecDeque::new();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
 
-    let starting_state = SearchState::new(vec![], time_remaining, vec![(Resource::Ore, 1)]);
-    queue.push_back(starting_state);
+    for result in &results {
+        debug!(
+            "Blueprint {} produced {} geodes in {time_budget} minutes by building {}",
+            result.blueprint_id,
+            result.geodes,
+            result.describe_build_order()
+        );
+    }
+
+    results
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct SearchResult {
+    blueprint_id: usize,
+    geodes: usize,
+    /// Each robot that was built, along with the minute it was finished in
+    build_order: Vec<(usize, Resource)>,
+}
 
-    let mut iterations = 0;
+impl SearchResult {
+    fn describe_build_order(&self) -> String {
+        self.build_order
+            .iter()
+            .map(|(minute, robot)| format!("{robot:?} at minute {minute}"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
 
-    while let Some(current_state) = queue.pop_front() {
-        iterations += 1;
+/**
+ * Returns the most geodes that can be produced with this blueprint, and a build order that
+ * produces them
+ */
+fn search(blueprint: &Blueprint, time_budget: usize) -> SearchResult {
+    let mut search = BlueprintSearch::new(blueprint, time_budget);
+
+    let mut robots = [0; RESOURCE_COUNT];
+    robots[Resource::Ore.index()] = 1;
+
+    search.visit(
+        SearchState::new([0; RESOURCE_COUNT], robots, time_budget),
+        &mut vec![],
+    );
+
+    debug!(
+        "searched {} states for blueprint {}",
+        search.seen.len(),
+        blueprint.id
+    );
+
+    SearchResult {
+        blueprint_id: blueprint.id,
+        geodes: search.best_geodes,
+        build_order: search.best_build_order,
+    }
+}
 
-        // if we're out of time, we've reached the final state
-        if current_state.time_remaining == 0 {
-            max_geode_count =
-                max_geode_count.max(current_state.get_amount_of_resource(&Resource::Geode));
+const RESOURCE_COUNT: usize = 4;
 
-            continue;
-        }
+/**
+ * Depth-first search over which robot to build next
+ *
+ * Instead of stepping minute by minute, each branch waits exactly as long as it takes to afford
+ * its robot. Branches are cut when they can't beat the best geode count found so far, even by
+ * building a geode robot every remaining minute
+ */
+struct BlueprintSearch {
+    costs: [[usize; RESOURCE_COUNT]; RESOURCE_COUNT],
+    max_spend: [usize; RESOURCE_COUNT],
+    time_budget: usize,
+    seen: HashSet<SearchState>,
+    best_geodes: usize,
+    best_build_order: Vec<(usize, Resource)>,
+}
 
-        // https://www.reddit.com/r/adventofcode/comments/zpihwi/2022_day_19_solutions/j0tls7a/
-        let most_optimistic_geode_count = current_state.get_amount_of_resource(&Resource::Geode)
-            + (current_state.number_of_resource_robots(&Resource::Geode)
-                * current_state.time_remaining)
-            + triangular_number(current_state.time_remaining) as usize;
+impl BlueprintSearch {
+    fn new(blueprint: &Blueprint, time_budget: usize) -> BlueprintSearch {
+        let mut costs = [[0; RESOURCE_COUNT]; RESOURCE_COUNT];
+        let mut max_spend = [0; RESOURCE_COUNT];
 
-        if most_optimistic_geode_count < max_geode_count {
-            continue;
+        for robot in Resource::all() {
+            for resource in Resource::all() {
+                costs[robot.index()][resource.index()] = blueprint
+                    .get_recipe_for_resource(&robot)
+                    .get_required_amount(&resource);
+            }
         }
 
+        // there's no point in having more robots than we can spend in a minute, since we can
+        // only build one robot a minute. Geodes are never spent, so there's no limit on those
         for resource in Resource::all() {
-            if let Some(next_state) = next_search_state(&current_state, blueprint, &resource) {
-                debug!("found search state for {resource:?}");
-                queue.push_back(next_state);
+            max_spend[resource.index()] = if resource == Resource::Geode {
+                usize::MAX
             } else {
-                debug!("no search state for {:?}", resource);
-            }
+                blueprint.get_max_resource_consumption(&resource)
+            };
+        }
+
+        BlueprintSearch {
+            costs,
+            max_spend,
+            time_budget,
+            seen: HashSet::new(),
+            best_geodes: 0,
+            best_build_order: vec![],
         }
     }
 
-    debug!("searched {iterations} states");
+    fn visit(&mut self, state: SearchState, build_order: &mut Vec<(usize, Resource)>) {
+        let geode = Resource::Geode.index();
 
-    max_geode_count
-}
+        // the geodes we'll end up with if we don't build anything else
+        let geodes = state.resources[geode] + state.robots[geode] * state.time_remaining;
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
-struct SearchState {
-    resources: Vec<(Resource, usize)>,
-    time_remaining: usize,
-    robots: Vec<(Resource, usize)>,
-}
+        if geodes > self.best_geodes {
+            self.best_geodes = geodes;
+            self.best_build_order = build_order.clone();
+        }
 
-impl SearchState {
-    fn new(
-        resources: Vec<(Resource, usize)>,
-        time_remaining: usize,
-        robots: Vec<(Resource, usize)>,
-    ) -> SearchState {
-        SearchState {
-            resources,
-            time_remaining,
-            robots,
+        // a new geode robot every minute from now on, each finished one minute after the last
+        let most_optimistic_geode_count =
+            geodes + triangular_number(state.time_remaining.saturating_sub(1)) as usize;
+
+        if most_optimistic_geode_count <= self.best_geodes {
+            return;
         }
-    }
 
-    fn get_amount_of_resource(&self, resource: &Resource) -> usize {
-        self.resources
-            .iter()
-            .find_map(|(resource_type, amount)| {
-                if resource_type == resource {
-                    Some(*amount)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0)
+        if !self.seen.insert(state.capped(&self.max_spend)) {
+            return;
+        }
+
+        // geode robots first, to find good answers (and prune more) early
+        for robot in Resource::all().into_iter().rev() {
+            if state.robots[robot.index()] >= self.max_spend[robot.index()] {
+                continue;
+            }
+
+            if let Some(next_state) = self.build(&state, robot) {
+                build_order.push((self.time_budget - next_state.time_remaining, robot));
+                self.visit(next_state, build_order);
+                build_order.pop();
+            }
+        }
     }
 
     /**
-     * Count the number of robots that can produce `resource`
+     * Waits until we can afford `robot`, then builds it. Returns `None` if we'll never afford it,
+     * or if it wouldn't be finished in time to produce anything
      */
-    fn number_of_resource_robots(&self, resource: &Resource) -> usize {
-        self.robots
-            .iter()
-            .find_map(|(produces_resource, count)| {
-                if produces_resource == resource {
-                    Some(*count)
-                } else {
-                    None
-                }
-            })
-            .unwrap_or(0)
-    }
-}
+    fn build(&self, state: &SearchState, robot: Resource) -> Option<SearchState> {
+        let cost = &self.costs[robot.index()];
 
-// build the next search state with the assumption that we're building a robot that outputs `resource`
-// we'll also assume that when we check the next search state, we've accumulated the resources between now and then
-fn next_search_state(
-    search_state: &SearchState,
-    blueprint: &Blueprint,
-    robot_type: &Resource,
-) -> Option<SearchState> {
-    // across all recipes, what is the max consumption of this resource
-    let max_consumption = blueprint.get_max_resource_consumption(&robot_type);
+        let mut wait = 0;
 
-    /*
-     * if we're already producing the maximum required amount of this resource (excluding geode),
-     * we don't need any more of this kind of robot
-     */
-    if *robot_type != Resource::Geode
-        && search_state.number_of_resource_robots(&robot_type) >= max_consumption
-    {
-        debug!("max consumption for {robot_type:?} has already been reached ({max_consumption})");
-        return None;
-    }
+        for (resource, amount) in cost.iter().enumerate() {
+            if state.resources[resource] >= *amount {
+                continue;
+            }
 
-    let recipe = blueprint.get_recipe_for_resource(robot_type);
+            let robots = state.robots[resource];
 
-    // Add 1 to time necessary, to account for building the robot
-    let time_necessary = calculate_time_necessary(search_state, &recipe, robot_type)? + 1;
+            // if we have no robots for this resource, we'll never have enough
+            if robots == 0 {
+                return None;
+            }
 
-    /*
-     * If we don't have enough time to build another resource robot, just skip to the end
-     */
-    if time_necessary > search_state.time_remaining {
-        let mut resources = vec![];
+            let needed = amount - state.resources[resource];
 
-        for resource in Resource::all() {
-            let existing_resources = search_state.get_amount_of_resource(&resource);
-            let resources_added =
-                search_state.number_of_resource_robots(&resource) * search_state.time_remaining;
+            // a partial minute of collecting still takes the whole minute
+            let minutes = if needed % robots == 0 {
+                needed / robots
+            } else {
+                (needed / robots) + 1
+            };
 
-            resources.push((resource, existing_resources + resources_added));
+            wait = wait.max(minutes);
         }
 
-        return Some(SearchState::new(resources, 0, search_state.robots.clone()));
-    }
+        // another minute to build the robot, and it needs at least a minute left to be useful
+        let time_taken = wait + 1;
 
-    let time_remaining = search_state.time_remaining - time_necessary;
+        if time_taken >= state.time_remaining {
+            return None;
+        }
 
-    /*
-     * Add 1 more robot
-     * Either increment the count or add a new value to the vector
-     */
-    let has_existing_robot = search_state
-        .robots
-        .iter()
-        .any(|(resource_type, _)| resource_type == robot_type);
+        let mut resources = state.resources;
+        let mut robots = state.robots;
 
-    let robots: Vec<(Resource, usize)> = if has_existing_robot {
-        search_state
-            .robots
-            .iter()
-            .map(|(resource_type, count)| {
-                if resource_type == robot_type {
-                    (*resource_type, count + 1)
-                } else {
-                    (*resource_type, *count)
-                }
-            })
-            .collect()
-    } else {
-        let mut robots = search_state.robots.clone();
-        robots.push((*robot_type, 1));
-        robots
-    };
-
-    /*
-     * Add resources from time advance, then subtract the cost of the robot that we'll have
-     */
-    let mut resources = vec![];
-    for resource in Resource::all() {
-        let amount_available = (search_state.number_of_resource_robots(&resource) * time_necessary)
-            + search_state.get_amount_of_resource(&resource);
-        let amount_required = recipe.get_required_amount(&resource);
+        for resource in 0..RESOURCE_COUNT {
+            resources[resource] += state.robots[resource] * time_taken;
+            resources[resource] -= cost[resource];
+        }
 
-        let amount_remaining = amount_available - amount_required;
+        robots[robot.index()] += 1;
 
-        resources.push((resource, amount_remaining))
+        Some(SearchState::new(
+            resources,
+            robots,
+            state.time_remaining - time_taken,
+        ))
     }
+}
 
-    Some(SearchState::new(resources, time_remaining, robots))
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+struct SearchState {
+    resources: [usize; RESOURCE_COUNT],
+    robots: [usize; RESOURCE_COUNT],
+    time_remaining: usize,
 }
 
-/*
- * Calculates the amount of time necessary to accumulate resources to build a robot for `robot_type` resources
- * Does not include the time necessary to build the robot
- */
-fn calculate_time_necessary(
-    search_state: &SearchState,
-    recipe: &Recipe,
-    robot_type: &Resource,
-) -> Option<usize> {
-    let mut time_necessary = 0;
-
-    for cost in &recipe.costs {
-        let current_resource_count = search_state.get_amount_of_resource(&cost.resource);
-
-        if current_resource_count >= cost.amount {
-            // if we already have the resources we need, we don't need to check robots
-            debug!(
-                "{robot_type:?} costs {} {:?}, which we already have ({current_resource_count})",
-                cost.amount, cost.resource
-            );
-            continue;
+impl SearchState {
+    fn new(
+        resources: [usize; RESOURCE_COUNT],
+        robots: [usize; RESOURCE_COUNT],
+        time_remaining: usize,
+    ) -> SearchState {
+        SearchState {
+            resources,
+            robots,
+            time_remaining,
         }
+    }
 
-        let resource_count_needed = cost.amount - current_resource_count;
-
-        debug!(
-            "need {resource_count_needed} {:?} to produce 1 {robot_type:?} robot",
-            cost.resource
-        );
-
-        // count the number of robots
-        // if we have no robots, we can't actually produce
-        let number_of_robots = search_state
-            .robots
-            .iter()
-            .find_map(|(resource_type, count)| {
-                if resource_type == &cost.resource {
-                    Some(*count)
-                } else {
-                    None
-                }
-            })?;
+    /**
+     * Anything beyond what we could spend in the time remaining is wasted, so states that only
+     * differ in wasted resources are equivalent
+     */
+    fn capped(&self, max_spend: &[usize; RESOURCE_COUNT]) -> SearchState {
+        let mut resources = self.resources;
 
-        debug!(
-            "we have {number_of_robots} robots to produce {:?}",
-            cost.resource
-        );
+        for resource in 0..RESOURCE_COUNT {
+            let spendable = max_spend[resource].saturating_mul(self.time_remaining);
 
-        let time_needed = if resource_count_needed % number_of_robots == 0 {
-            resource_count_needed / number_of_robots
-        } else {
-            (resource_count_needed / number_of_robots) + 1
-        };
+            resources[resource] = resources[resource].min(spendable);
+        }
 
-        time_necessary = time_necessary.max(time_needed);
+        SearchState::new(resources, self.robots, self.time_remaining)
     }
-
-    Some(time_necessary)
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
@@ -321,6 +321,15 @@ enum Resource {
 }
 
 impl Resource {
+    fn index(&self) -> usize {
+        match self {
+            Resource::Ore => 0,
+            Resource::Clay => 1,
+            Resource::Obsidian => 2,
+            Resource::Geode => 3,
+        }
+    }
+
     fn all() -> Vec<Resource> {
         vec![
             Resource::Ore,
@@ -511,100 +520,84 @@ mod tests {
         );
     }
 
+    const EXAMPLE: &str = "Blueprint 1: Each ore robot costs 4 ore. Each clay robot costs 2 ore. Each obsidian robot costs 3 ore and 14 clay. Each geode robot costs 2 ore and 7 obsidian.
+Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 3 ore. Each obsidian robot costs 3 ore and 8 clay. Each geode robot costs 3 ore and 12 obsidian.";
+
     #[test]
-    fn test_next_search_state() {
-        let search_state = SearchState::new(vec![], 24, vec![(Resource::Ore, 2)]);
+    fn test_build() {
         let blueprint = Blueprint::new(
             1,
             vec![
-                Recipe::new(Resource::Clay, vec![Cost::new(Resource::Ore, 2)]),
                 Recipe::new(Resource::Ore, vec![Cost::new(Resource::Ore, 3)]),
+                Recipe::new(Resource::Clay, vec![Cost::new(Resource::Ore, 2)]),
+                Recipe::new(Resource::Obsidian, vec![Cost::new(Resource::Clay, 4)]),
+                Recipe::new(Resource::Geode, vec![Cost::new(Resource::Obsidian, 1)]),
             ],
         );
+        let search = BlueprintSearch::new(&blueprint, 24);
 
-        let next_search_state = next_search_state(&search_state, &blueprint, &Resource::Ore);
-        assert!(next_search_state.is_some());
+        let state = SearchState::new([0, 0, 0, 0], [2, 0, 0, 0], 24);
 
-        let next_search_state = next_search_state.unwrap();
-        assert_eq!(next_search_state.time_remaining, 21);
-        assert_eq!(next_search_state.robots, vec![(Resource::Ore, 3)]);
+        // two minutes to mine 4 ore, then a minute to build
         assert_eq!(
-            next_search_state.resources,
-            vec![
-                (Resource::Ore, 3),
-                (Resource::Clay, 0),
-                (Resource::Obsidian, 0),
-                (Resource::Geode, 0)
-            ]
+            search.build(&state, Resource::Ore),
+            Some(SearchState::new([3, 0, 0, 0], [3, 0, 0, 0], 21))
         );
-    }
 
-    #[test]
-    fn test_next_search_state_not_enough_time_remaining() {
-        let search_state =
-            SearchState::new(vec![], 2, vec![(Resource::Ore, 1), (Resource::Clay, 1)]);
+        // we'll never have any obsidian without an obsidian robot
+        assert_eq!(search.build(&state, Resource::Geode), None);
 
-        let blueprint = Blueprint::new(
-            1,
-            vec![
-                Recipe::new(Resource::Clay, vec![Cost::new(Resource::Ore, 5)]),
-                Recipe::new(Resource::Geode, vec![Cost::new(Resource::Clay, 10)]),
-            ],
-        );
-
-        let next_search_state = next_search_state(&search_state, &blueprint, &Resource::Geode);
-        assert!(next_search_state.is_some());
+        // not enough time left for the robot to produce anything
+        let state = SearchState::new([0, 0, 0, 0], [2, 0, 0, 0], 3);
+        assert_eq!(search.build(&state, Resource::Ore), None);
+    }
 
-        let next_search_state = next_search_state.unwrap();
+    #[test]
+    fn test_capped() {
+        let state = SearchState::new([100, 3, 0, 50], [1, 1, 0, 0], 2);
 
         assert_eq!(
-            next_search_state.resources,
-            vec![
-                (Resource::Ore, 2),
-                (Resource::Clay, 2),
-                (Resource::Obsidian, 0),
-                (Resource::Geode, 0)
-            ]
+            state.capped(&[4, 14, 7, usize::MAX]),
+            SearchState::new([8, 3, 0, 50], [1, 1, 0, 0], 2)
         );
-        assert_eq!(next_search_state.time_remaining, 0);
-        assert_eq!(next_search_state.robots, search_state.robots);
     }
 
     #[test]
-    fn test_calculate_time_necessary() {
-        let search_state =
-            SearchState::new(vec![], 24, vec![(Resource::Ore, 2), (Resource::Clay, 1)]);
-
-        let recipe = Recipe::new(
-            Resource::Obsidian,
-            vec![Cost::new(Resource::Ore, 5), Cost::new(Resource::Clay, 2)],
-        );
+    fn test_search() {
+        let blueprints = parse(EXAMPLE);
 
-        let time_necessary = calculate_time_necessary(&search_state, &recipe, &Resource::Obsidian);
-        assert_eq!(time_necessary, Some(3));
+        let results = evaluate_blueprints(&blueprints, 24);
 
-        let search_state = SearchState::new(
-            vec![(Resource::Ore, 4)],
-            24,
-            vec![(Resource::Ore, 2), (Resource::Clay, 1)],
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| result.geodes)
+                .collect::<Vec<usize>>(),
+            vec![9, 12]
         );
 
-        let time_necessary = calculate_time_necessary(&search_state, &recipe, &Resource::Obsidian);
-        assert_eq!(time_necessary, Some(2));
+        // replaying the build order should produce the same number of geodes
+        for (blueprint, result) in blueprints.iter().zip(results) {
+            assert_eq!(replay(blueprint, &result.build_order, 24), result.geodes);
+        }
+    }
 
-        let search_state =
-            SearchState::new(vec![], 2, vec![(Resource::Ore, 1), (Resource::Clay, 1)]);
+    fn replay(
+        blueprint: &Blueprint,
+        build_order: &[(usize, Resource)],
+        time_budget: usize,
+    ) -> usize {
+        let search = BlueprintSearch::new(blueprint, time_budget);
 
-        let recipe = Recipe::new(Resource::Geode, vec![Cost::new(Resource::Obsidian, 1)]);
+        let mut state = SearchState::new([0; RESOURCE_COUNT], [1, 0, 0, 0], time_budget);
 
-        let time_necessary = calculate_time_necessary(&search_state, &recipe, &Resource::Geode);
-        assert_eq!(time_necessary, None);
+        for (minute, robot) in build_order {
+            state = search.build(&state, *robot).unwrap();
 
-        // test when all resources have already been acquired
-        let search_state = SearchState::new(vec![(Resource::Ore, 1)], 5, vec![]);
-        let recipe = Recipe::new(Resource::Ore, vec![Cost::new(Resource::Ore, 1)]);
+            assert_eq!(time_budget - state.time_remaining, *minute);
+        }
 
-        let time_necessary = calculate_time_necessary(&search_state, &recipe, &Resource::Ore);
-        assert_eq!(time_necessary, Some(0));
+        state.resources[Resource::Geode.index()]
+            + state.robots[Resource::Geode.index()] * state.time_remaining
     }
 }
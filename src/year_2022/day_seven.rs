@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::fmt::Display;
 use std::time::SystemTime;
 use crate::common::answer::*;
+use log::debug;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -17,113 +19,346 @@ pub fn run() -> AdventOfCodeResult {
 
     let commands = parse(input);
 
-    let part_one = part_one(&commands);
-    let part_two = part_two(&commands);
+    let part_one = part_one(&commands)?;
+    let part_two = part_two(&commands)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(commands: &[Command]) -> PartAnswer {
+fn part_one(commands: &[Command]) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut filesystem = Filesystem::new();
+    let filesystem = Filesystem::from_commands(commands)?;
 
-    filesystem.execute_commands(commands);
+    debug!("\n{}", filesystem.tree());
 
-    let mut sum = 0;
-
-    for (_, size) in filesystem.directory_sizes {
-        if size <= 100000 {
-            sum += size;
-        }
-    }
+    let sum: usize = filesystem
+        .directory_sizes()
+        .into_iter()
+        .map(|(_, size)| size)
+        .filter(|size| *size <= 100000)
+        .sum();
 
     let elapsed = start.elapsed().unwrap();
 
-    PartAnswer::new(sum, elapsed)
+    Ok(PartAnswer::new(sum, elapsed))
 }
 
-fn part_two(commands: &[Command]) -> PartAnswer {
+fn part_two(commands: &[Command]) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut filesystem = Filesystem::new();
+    let filesystem = Filesystem::from_commands(commands)?;
+
+    let total_space: usize = 70000000;
+    let necessary_free_space: usize = 30000000;
+    let current_free_space = total_space.saturating_sub(filesystem.du("/")?);
+    let space_to_free = necessary_free_space.saturating_sub(current_free_space);
 
-    filesystem.execute_commands(commands);
+    let smallest_deleted_directory_size = filesystem
+        .directory_sizes()
+        .into_iter()
+        .map(|(_, size)| size)
+        .filter(|size| *size >= space_to_free)
+        .min()
+        .ok_or_else(|| {
+            AdventOfCodeError::NoSolution(format!("no directory frees up {}", space_to_free))
+        })?;
 
-    let total_space = 70000000;
-    let necessary_free_space = 30000000;
-    let current_free_space = total_space - filesystem.directory_sizes["root"];
-    let space_to_free = necessary_free_space - current_free_space;
+    let elapsed = start.elapsed().unwrap();
 
-    let mut potentially_deleted_directory_sizes = vec![];
+    Ok(PartAnswer::new(smallest_deleted_directory_size, elapsed))
+}
 
-    for (name, size) in filesystem.directory_sizes {
-        println!("{} has size {}", name, size);
-        if size >= space_to_free {
-            potentially_deleted_directory_sizes.push(size);
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum FilesystemError {
+    /// `cd` into a directory that no `ls` has shown
+    UnknownDirectory { path: String, name: String },
+    /// `cd ..` from the root directory
+    AboveRoot,
+    /// `ls` showed an entry that contradicts an earlier listing
+    ConflictingEntry { path: String, name: String },
+    /// a path given to a query doesn't exist, or isn't a directory
+    NoSuchDirectory(String),
+}
+
+impl Display for FilesystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilesystemError::UnknownDirectory { path, name } => {
+                write!(f, "cd into unknown directory {name} from {path}")
+            }
+            FilesystemError::AboveRoot => write!(f, "cd .. from the root directory"),
+            FilesystemError::ConflictingEntry { path, name } => {
+                write!(
+                    f,
+                    "{name} in {path} was listed as something different before"
+                )
+            }
+            FilesystemError::NoSuchDirectory(path) => write!(f, "no such directory {path}"),
         }
     }
+}
 
-    let smallest_deleted_directory_size = potentially_deleted_directory_sizes.iter().min().unwrap();
-
-    let elapsed = start.elapsed().unwrap();
+impl From<FilesystemError> for AdventOfCodeError {
+    fn from(err: FilesystemError) -> AdventOfCodeError {
+        AdventOfCodeError::InvalidInput(err.to_string())
+    }
+}
 
-    PartAnswer::new(smallest_deleted_directory_size, elapsed)
+#[derive(Debug, PartialEq, Eq, Clone)]
+enum Node {
+    Directory {
+        name: String,
+        parent: Option<usize>,
+        children: BTreeMap<String, usize>,
+    },
+    File {
+        name: String,
+        size: usize,
+    },
 }
 
-#[derive(Debug)]
+/**
+ * The directory tree described by a terminal transcript
+ *
+ * Nodes are stored in a single vector and refer to each other by index; the root directory is
+ * always index 0. Directories and files only exist once an `ls` has shown them, so the transcript
+ * can't `cd` somewhere it hasn't seen
+ */
+#[derive(Debug, PartialEq, Eq, Clone)]
 struct Filesystem {
-    directory_sizes: HashMap<String, usize>,
-    current_path: Vec<String>,
+    nodes: Vec<Node>,
+    current_directory: usize,
 }
 
+const ROOT: usize = 0;
+
 impl Filesystem {
     fn new() -> Filesystem {
-        let mut file_sizes = HashMap::new();
-        file_sizes.insert("root".into(), 0);
-        let current_path = vec!["root".into()];
-
         Filesystem {
-            directory_sizes: file_sizes,
-            current_path,
+            nodes: vec![Node::Directory {
+                name: "/".into(),
+                parent: None,
+                children: BTreeMap::new(),
+            }],
+            current_directory: ROOT,
         }
     }
 
-    fn cd(&mut self, command: &Directory) {
-        match command {
-            Directory::Root => {
-                self.current_path = vec!["root".into()];
-            }
-            Directory::Up => {
-                self.current_path.pop();
+    fn from_commands(commands: &[Command]) -> Result<Filesystem, FilesystemError> {
+        let mut filesystem = Filesystem::new();
+
+        filesystem.execute_commands(commands)?;
+
+        Ok(filesystem)
+    }
+
+    fn execute_commands(&mut self, commands: &[Command]) -> Result<(), FilesystemError> {
+        for command in commands {
+            match command {
+                Command::Cd(directory) => self.cd(directory)?,
+                Command::Ls(directory_or_file_list) => self.ls(directory_or_file_list)?,
             }
-            Directory::Named(path) => {
-                self.current_path.push(path.clone());
+        }
+
+        Ok(())
+    }
+
+    fn cd(&mut self, directory: &Directory) -> Result<(), FilesystemError> {
+        self.current_directory = match directory {
+            Directory::Root => ROOT,
+            Directory::Up => self
+                .parent(self.current_directory)
+                .ok_or(FilesystemError::AboveRoot)?,
+            Directory::Named(name) => self
+                .children(self.current_directory)
+                .get(name)
+                .copied()
+                .filter(|child| self.is_directory(*child))
+                .ok_or_else(|| FilesystemError::UnknownDirectory {
+                    path: self.path(self.current_directory),
+                    name: name.clone(),
+                })?,
+        };
+
+        Ok(())
+    }
+
+    /**
+     * Records the listing of the current directory. Listing the same directory again is fine, as
+     * long as nothing has changed
+     */
+    fn ls(&mut self, directory_or_file_list: &[DirectoryOrFile]) -> Result<(), FilesystemError> {
+        for directory_or_file in directory_or_file_list {
+            let (name, node) = match directory_or_file {
+                DirectoryOrFile::Directory(name) => (
+                    name,
+                    Node::Directory {
+                        name: name.clone(),
+                        parent: Some(self.current_directory),
+                        children: BTreeMap::new(),
+                    },
+                ),
+                DirectoryOrFile::File(name, size) => (
+                    name,
+                    Node::File {
+                        name: name.clone(),
+                        size: *size,
+                    },
+                ),
+            };
+
+            match self.children(self.current_directory).get(name) {
+                Some(existing) => {
+                    let is_same = match (&self.nodes[*existing], &node) {
+                        (Node::Directory { .. }, Node::Directory { .. }) => true,
+                        (Node::File { size, .. }, Node::File { size: new_size, .. }) => {
+                            size == new_size
+                        }
+                        _ => false,
+                    };
+
+                    if !is_same {
+                        return Err(FilesystemError::ConflictingEntry {
+                            path: self.path(self.current_directory),
+                            name: name.clone(),
+                        });
+                    }
+                }
+                None => {
+                    let index = self.nodes.len();
+                    self.nodes.push(node);
+
+                    if let Node::Directory { children, .. } =
+                        &mut self.nodes[self.current_directory]
+                    {
+                        children.insert(name.clone(), index);
+                    }
+                }
             }
         }
+
+        Ok(())
     }
 
-    fn add_file_size(&mut self, size: usize) {
-        for i in 1..=self.current_path.len() {
-            let subpath: String = self.current_path[0..i].join("/");
-            if let Some(sum) = self.directory_sizes.get_mut(&subpath) {
-                *sum += size;
-            } else {
-                self.directory_sizes.insert(subpath, size);
+    fn name(&self, index: usize) -> &str {
+        match &self.nodes[index] {
+            Node::Directory { name, .. } | Node::File { name, .. } => name,
+        }
+    }
+
+    fn parent(&self, index: usize) -> Option<usize> {
+        match &self.nodes[index] {
+            Node::Directory { parent, .. } => *parent,
+            Node::File { .. } => None,
+        }
+    }
+
+    fn children(&self, index: usize) -> &BTreeMap<String, usize> {
+        match &self.nodes[index] {
+            Node::Directory { children, .. } => children,
+            Node::File { .. } => unreachable!("files have no children"),
+        }
+    }
+
+    fn is_directory(&self, index: usize) -> bool {
+        matches!(self.nodes[index], Node::Directory { .. })
+    }
+
+    /// The absolute path of a directory, like `/a/e`
+    fn path(&self, index: usize) -> String {
+        let mut names = vec![];
+        let mut current = index;
+
+        while let Some(parent) = self.parent(current) {
+            names.push(self.name(current));
+            current = parent;
+        }
+
+        names.reverse();
+
+        format!("/{}", names.join("/"))
+    }
+
+    fn resolve(&self, path: &str) -> Option<usize> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(ROOT, |current, name| {
+                self.children(current)
+                    .get(name)
+                    .copied()
+                    .filter(|child| self.is_directory(*child))
+            })
+    }
+
+    /// The total size of everything under the directory at `path`
+    fn du(&self, path: &str) -> Result<usize, FilesystemError> {
+        self.resolve(path)
+            .map(|index| self.size(index))
+            .ok_or_else(|| FilesystemError::NoSuchDirectory(path.to_string()))
+    }
+
+    fn size(&self, index: usize) -> usize {
+        match &self.nodes[index] {
+            Node::File { size, .. } => *size,
+            Node::Directory { children, .. } => {
+                children.values().map(|child| self.size(*child)).sum()
             }
         }
     }
 
-    fn execute_commands(&mut self, commands: &[Command]) {
-        for command in commands {
-            match command {
-                Command::Cd(directory) => self.cd(directory),
-                Command::Ls(directory_or_file_list) => {
-                    for directory_or_file in directory_or_file_list {
-                        if let DirectoryOrFile::File(_name, size) = directory_or_file {
-                            self.add_file_size(*size);
-                        }
-                    }
+    /// The total size of every directory, keyed by path
+    fn directory_sizes(&self) -> Vec<(String, usize)> {
+        let mut sizes = vec![];
+
+        self.collect_directory_sizes(ROOT, &mut sizes);
+
+        sizes
+    }
+
+    // returns the size of the directory at `index`, so each file is only visited once
+    fn collect_directory_sizes(&self, index: usize, sizes: &mut Vec<(String, usize)>) -> usize {
+        let total = self
+            .children(index)
+            .values()
+            .map(|child| match &self.nodes[*child] {
+                Node::File { size, .. } => *size,
+                Node::Directory { .. } => self.collect_directory_sizes(*child, sizes),
+            })
+            .sum();
+
+        sizes.push((self.path(index), total));
+
+        total
+    }
+
+    /**
+     * Draws the tree the same way the puzzle does, e.g.
+     *
+     * - / (dir)
+     *   - a (dir)
+     *     - f (file, size=29116)
+     */
+    fn tree(&self) -> String {
+        let mut lines = vec![];
+
+        self.render(ROOT, 0, &mut lines);
+
+        lines.join("\n")
+    }
+
+    fn render(&self, index: usize, depth: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+
+        match &self.nodes[index] {
+            Node::File { name, size } => {
+                lines.push(format!("{indent}- {name} (file, size={size})"));
+            }
+            Node::Directory { name, children, .. } => {
+                lines.push(format!("{indent}- {name} (dir)"));
+
+                for child in children.values() {
+                    self.render(*child, depth + 1, lines);
                 }
             }
         }
@@ -217,35 +452,160 @@ fn filename(i: &str) -> IResult<&str, String> {
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k";
+
     #[test]
     fn test_filesystem_cd() {
         let mut filesystem = Filesystem::new();
 
-        assert_eq!(filesystem.current_path, vec!["root"]);
+        assert_eq!(filesystem.path(filesystem.current_directory), "/");
+
+        filesystem
+            .ls(&[DirectoryOrFile::Directory("a".into())])
+            .unwrap();
+        filesystem.cd(&Directory::Named("a".into())).unwrap();
+
+        assert_eq!(filesystem.path(filesystem.current_directory), "/a");
+
+        filesystem
+            .ls(&[DirectoryOrFile::Directory("b".into())])
+            .unwrap();
+        filesystem.cd(&Directory::Named("b".into())).unwrap();
+
+        assert_eq!(filesystem.path(filesystem.current_directory), "/a/b");
+
+        filesystem.cd(&Directory::Up).unwrap();
+
+        assert_eq!(filesystem.path(filesystem.current_directory), "/a");
+
+        filesystem.cd(&Directory::Root).unwrap();
+
+        assert_eq!(filesystem.path(filesystem.current_directory), "/");
+    }
+
+    #[test]
+    fn test_invalid_transcripts() {
+        let mut filesystem = Filesystem::new();
+
+        assert_eq!(
+            filesystem.cd(&Directory::Named("a".into())),
+            Err(FilesystemError::UnknownDirectory {
+                path: "/".into(),
+                name: "a".into()
+            })
+        );
+        assert_eq!(
+            filesystem.cd(&Directory::Up),
+            Err(FilesystemError::AboveRoot)
+        );
 
-        let directory = Directory::Named("a".into());
+        filesystem
+            .ls(&[DirectoryOrFile::File("a".into(), 10)])
+            .unwrap();
 
-        filesystem.cd(&directory);
+        // a file isn't something we can cd into
+        assert!(filesystem.cd(&Directory::Named("a".into())).is_err());
 
-        assert_eq!(filesystem.current_path, vec!["root", "a"]);
+        // listing the same thing again is fine, but listing something different isn't
+        assert_eq!(
+            filesystem.ls(&[DirectoryOrFile::File("a".into(), 10)]),
+            Ok(())
+        );
+        assert_eq!(
+            filesystem.ls(&[DirectoryOrFile::Directory("a".into())]),
+            Err(FilesystemError::ConflictingEntry {
+                path: "/".into(),
+                name: "a".into()
+            })
+        );
 
-        let directory = Directory::Named("b".into());
+        assert_eq!(
+            part_one(&parse("$ cd /\n$ cd a")).err(),
+            Some(AdventOfCodeError::InvalidInput(
+                "cd into unknown directory a from /".into()
+            ))
+        );
+    }
 
-        filesystem.cd(&directory);
+    #[test]
+    fn test_part_two_with_enough_free_space() {
+        let commands = parse("$ cd /\n$ ls\n100 a.txt\ndir b\n$ cd b\n$ ls\n50 c.txt");
+        let answer = part_two(&commands).unwrap();
 
-        assert_eq!(filesystem.current_path, vec!["root", "a", "b"]);
+        // nothing has to be freed, so the smallest directory is enough
+        assert_eq!(answer.get_answer(), "50");
+    }
 
-        let directory = Directory::Up;
+    #[test]
+    fn test_du() {
+        let filesystem = Filesystem::from_commands(&parse(EXAMPLE)).unwrap();
 
-        filesystem.cd(&directory);
+        assert_eq!(filesystem.du("/a/e"), Ok(584));
+        assert_eq!(filesystem.du("/a"), Ok(94853));
+        assert_eq!(filesystem.du("/d"), Ok(24933642));
+        assert_eq!(filesystem.du("/"), Ok(48381165));
+        assert_eq!(
+            filesystem.du("/a/f"),
+            Err(FilesystemError::NoSuchDirectory("/a/f".into()))
+        );
 
-        assert_eq!(filesystem.current_path, vec!["root", "a"]);
+        let mut directory_sizes = filesystem.directory_sizes();
+        directory_sizes.sort();
 
-        let directory = Directory::Root;
+        assert_eq!(
+            directory_sizes,
+            vec![
+                ("/".into(), 48381165),
+                ("/a".into(), 94853),
+                ("/a/e".into(), 584),
+                ("/d".into(), 24933642)
+            ]
+        );
+    }
 
-        filesystem.cd(&directory);
+    #[test]
+    fn test_tree() {
+        let filesystem = Filesystem::from_commands(&parse(EXAMPLE)).unwrap();
 
-        assert_eq!(filesystem.current_path, vec!["root"]);
+        assert_eq!(
+            filesystem.tree(),
+            "- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - d.ext (file, size=5626152)
+    - d.log (file, size=8033020)
+    - j (file, size=4060174)
+    - k (file, size=7214296)"
+        );
     }
 
     #[test]
@@ -1,6 +1,9 @@
 use std::collections::{HashMap, HashSet};
+use std::iter;
 use std::time::SystemTime;
+use crate::common::animation::Frame;
 use crate::common::answer::*;
+use crate::common::output::OutputSettings;
 use log::{debug, trace};
 use nom::{branch::alt, bytes::complete::tag, combinator::value, multi::many1, IResult};
 use crate::common::parse::finish;
@@ -18,9 +21,14 @@ use crate::common::parse::finish;
  * The rocks below cannot (as of part 1) influence a falling rock
  */
 
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-17.txt");
 
+    if output.is_animating() {
+        let game = TetrisGame::new(parse(input));
+        output.write_animation("2022-17", game.into_frames(2022, 40));
+    }
+
     let part_one = part_one(input);
     let part_two = part_two(input);
 
@@ -187,6 +195,45 @@ impl TetrisGame {
         self.next_shape_type = self.next_shape_type.next();
     }
 
+    /// Yields the top of the tower after each of the next `number_of_rocks` rocks settles
+    fn into_frames(mut self, number_of_rocks: usize, rows: usize) -> impl Iterator<Item = Frame> {
+        iter::repeat_with(move || {
+            self.add_rock();
+            self.render(rows)
+        })
+        .take(number_of_rocks)
+    }
+
+    fn render(&self, rows: usize) -> Frame {
+        let mut frame = Frame::filled(9, rows, '.');
+
+        let top = self.highest_y.max(rows as isize - 1);
+
+        for row in 0..rows {
+            let y = top - row as isize;
+
+            if y == 0 {
+                for x in 0..9 {
+                    frame.set(x, row, '-');
+                }
+                frame.set(0, row, '+');
+                frame.set(8, row, '+');
+                continue;
+            }
+
+            frame.set(0, row, '|');
+            frame.set(8, row, '|');
+
+            for x in 0..7 {
+                if self.occupied_cells.contains(&(x, y)) {
+                    frame.set(x as usize + 1, row, '#');
+                }
+            }
+        }
+
+        frame
+    }
+
     fn next_direction(&mut self) -> WindDirection {
         let wind_direction = self.wind_directions.get(self.current_wind_index).unwrap();
 
@@ -420,4 +467,25 @@ mod tests {
             .collect()
         );
     }
+
+    #[test]
+    fn test_render() {
+        let game = TetrisGame::new(parse(">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>"));
+
+        let frames: Vec<Frame> = game.into_frames(2, 6).collect();
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(
+            frames[1].to_string(),
+            [
+                "|.......|",
+                "|...#...|",
+                "|..###..|",
+                "|...#...|",
+                "|..####.|",
+                "+-------+",
+            ]
+            .join("\n")
+        );
+    }
 }
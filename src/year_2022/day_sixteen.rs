@@ -1,6 +1,11 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::time::SystemTime;
-use crate::common::{answer::*, output::write_dot};
+use crate::common::{
+    answer::*,
+    graph::Graph,
+    output::OutputSettings,
+};
 use nom::{
     branch::alt, bytes::complete::tag, character::complete::alpha1, combinator::map,
     multi::separated_list1, sequence::tuple, IResult,
@@ -10,197 +15,200 @@ use crate::common::parse::{finish, unsigned_number};
 /**
  * A lot of inspiration and pseudocode taken from https://www.reddit.com/r/adventofcode/comments/zn6k1l/2022_day_16_solutions/?sort=top
  */
-pub fn run() -> AdventOfCodeResult {
+pub fn run(output: &OutputSettings) -> AdventOfCodeResult {
     let input = include_str!("input/day-16.txt");
 
-    let valves = parse(input);
-
-    let mut graph_parts = vec![];
-
-    graph_parts.push("graph Valves {".to_string());
-
-    for (label, neighbors) in valves.graph {
-        for (neighbor, weight) in neighbors {
-            graph_parts.push(format!(
-                "\t{} -- {} [ label=\"{}\"]",
-                label, neighbor, weight
-            ));
-        }
+    if output.is_visualizing() {
+        output.write_graph("2022-16", &parse(input).to_graph());
     }
 
-    graph_parts.push("}".to_string());
-
-    write_dot("2022-16.dot", &graph_parts.join("\n"));
-
-    let part_one = part_one(input);
-    let part_two = part_two(input);
+    let part_one = part_one(input)?;
+    let part_two = part_two(input)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(input: &str) -> PartAnswer {
-    let start = SystemTime::now();
+/// Valves are opened by setting bits in a `u64`, so this is as many as the search can handle
+const MAX_VALVES: usize = 64;
 
-    let valves = parse(input);
+/// With any more valves than this, a table for every subset of them would be too big
+const MAX_VALVES_FOR_SUBSETS: usize = 20;
 
-    let all_final_states = generate_all_final_states(&valves, 30);
+fn part_one(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
+    let start = SystemTime::now();
 
-    let answer = all_final_states
-        .into_iter()
-        .map(|state| state.current_score)
+    let valves = parse(input).compress("AA")?;
+
+    let answer = valves
+        .best_pressure_by_opened_valves(30)
+        .into_values()
         .max()
         .unwrap();
 
     let elapsed = start.elapsed().unwrap();
 
-    PartAnswer::new(answer, elapsed)
+    Ok(PartAnswer::new(answer, elapsed))
 }
 
-fn part_two(input: &str) -> PartAnswer {
+/**
+ * You and the elephant open disjoint sets of valves, so the best combined score is the best score
+ * for some set of valves plus the best score for any set of the remaining valves
+ */
+fn part_two(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let valves = parse(input);
-
-    let all_final_states = generate_all_final_states(&valves, 26);
+    let valves = parse(input).compress("AA")?;
 
-    let mut best = 0;
-
-    for outer_state in &all_final_states {
-        let outer_opened_set: HashSet<String> = outer_state.opened.iter().cloned().collect();
-
-        for inner_state in &all_final_states {
-            if outer_state == inner_state {
-                continue;
-            }
+    let best = valves.best_pressure_by_opened_valves(26);
 
-            let inner_opened_set: HashSet<String> = inner_state.opened.iter().cloned().collect();
-
-            let number_of_common_items = outer_opened_set.intersection(&inner_opened_set).count();
-
-            if number_of_common_items == 0 {
-                best = best.max(outer_state.current_score + inner_state.current_score);
-            }
-        }
-    }
+    let answer = if valves.flow_rates.len() <= MAX_VALVES_FOR_SUBSETS {
+        best_disjoint_pair_by_subsets(&best, valves.flow_rates.len())
+    } else {
+        best_disjoint_pair_by_pairs(&best)
+    };
 
     let elapsed = start.elapsed().unwrap();
 
-    PartAnswer::new(best, elapsed)
+    Ok(PartAnswer::new(answer, elapsed))
 }
 
-fn generate_all_final_states(valves: &ValveSystem, time_budget: usize) -> Vec<SearchState> {
-    let mut state_queue = VecDeque::new();
-    state_queue.push_back(SearchState::new(1, "AA".to_string(), 0, vec![]));
-
-    let mut seen_states = HashMap::new();
+fn best_disjoint_pair_by_subsets(best: &HashMap<u64, usize>, number_of_valves: usize) -> usize {
+    let mut best_by_mask = vec![0; 1 << number_of_valves];
 
-    let mut final_states = vec![];
+    for (mask, pressure) in best {
+        best_by_mask[*mask as usize] = *pressure;
+    }
 
-    let mut number_of_final_states = 0;
+    let best_by_mask = best_pressure_by_subset(best_by_mask);
 
-    while let Some(current_state) = state_queue.pop_front() {
-        println!("Checking state {current_state:?}");
+    let all_valves = best_by_mask.len() - 1;
 
-        let mut opened_set: HashSet<String> = current_state.opened.iter().cloned().collect();
+    (0..best_by_mask.len())
+        .map(|mask| best_by_mask[mask] + best_by_mask[all_valves ^ mask])
+        .max()
+        .unwrap()
+}
 
-        let seen_state = (
-            current_state.current_time,
-            current_state.current_label.clone(),
-        );
+/**
+ * Tries pairs of the sets of valves the search actually opened, most pressure first, stopping as
+ * soon as no remaining pair can beat the best disjoint pair so far
+ */
+fn best_disjoint_pair_by_pairs(best: &HashMap<u64, usize>) -> usize {
+    let mut opened: Vec<(u64, usize)> = best
+        .iter()
+        .map(|(mask, pressure)| (*mask, *pressure))
+        .collect();
+    opened.sort_unstable_by_key(|(_, pressure)| Reverse(*pressure));
+
+    let mut answer = 0;
+
+    for (index, (mask, pressure)) in opened.iter().enumerate() {
+        // every set after this one releases no more pressure than it does
+        if pressure * 2 <= answer {
+            break;
+        }
 
-        let seen_state_score = seen_states.get(&seen_state).cloned().unwrap_or(-1_isize);
+        for (other_mask, other_pressure) in &opened[index..] {
+            if pressure + other_pressure <= answer {
+                break;
+            }
 
-        if seen_state_score >= current_state.current_score {
-            continue;
-        } else {
-            seen_states.insert(seen_state, current_state.current_score);
+            if mask & other_mask == 0 {
+                answer = pressure + other_pressure;
+                break;
+            }
         }
+    }
 
-        if current_state.current_time == time_budget {
-            number_of_final_states += 1;
-            final_states.push(current_state);
-            continue;
-        }
+    answer
+}
 
-        let flow_rate = valves
-            .flow_rates_by_label
-            .get(&current_state.current_label)
-            .unwrap();
-
-        // pretend we just opened the current valve
-        if *flow_rate > 0 && !opened_set.contains(&current_state.current_label) {
-            opened_set.insert(current_state.current_label.clone());
-
-            let new_score: isize = current_state.current_score
-                + opened_set
-                    .iter()
-                    .filter_map(|valve| valves.flow_rates_by_label.get(valve).map(|u| *u as isize))
-                    .sum::<isize>();
-
-            let opened_list = opened_set.iter().cloned().collect::<Vec<String>>();
-            let new_state = SearchState::new(
-                current_state.current_time + 1,
-                current_state.current_label.clone(),
-                new_score,
-                opened_list,
-            );
+/**
+ * Turns the best score for opening exactly the valves in each mask into the best score for opening
+ * any subset of them, by letting every mask inherit from the masks with one valve removed
+ */
+fn best_pressure_by_subset(mut best: Vec<usize>) -> Vec<usize> {
+    let number_of_valves = best.len().trailing_zeros();
 
-            state_queue.push_back(new_state);
-            opened_set.remove(&current_state.current_label);
+    for valve in 0..number_of_valves {
+        for mask in 0..best.len() {
+            if mask & (1 << valve) != 0 {
+                best[mask] = best[mask].max(best[mask ^ (1 << valve)]);
+            }
         }
+    }
 
-        // pretend we didn't just open the current valve
-        let new_score: isize = current_state.current_score
-            + opened_set
-                .iter()
-                .filter_map(|valve| valves.flow_rates_by_label.get(valve).map(|u| *u as isize))
-                .sum::<isize>();
+    best
+}
 
-        if let Some(neighbors) = valves.graph.get(&current_state.current_label) {
-            let opened_list = opened_set.iter().cloned().collect::<Vec<String>>();
+/**
+ * The valves with a positive flow rate, plus the starting valve, with the shortest distance between
+ * every pair of them. Valve `i` is opened when bit `i` of a mask is set
+ */
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct CompressedValveSystem {
+    flow_rates: Vec<usize>,
+    distances: Vec<Vec<usize>>,
+    start: usize,
+}
 
-            for (neighbor, distance) in neighbors.iter() {
-                let next_time = current_state.current_time + distance;
+impl CompressedValveSystem {
+    /**
+     * Returns the most pressure that can be released by opening exactly the valves in each mask
+     * within the time budget, for every mask that can be opened in time
+     */
+    fn best_pressure_by_opened_valves(&self, time_budget: usize) -> HashMap<u64, usize> {
+        let mut best = HashMap::new();
+        let mut seen = HashMap::new();
 
-                if next_time <= time_budget {
-                    let new_state = SearchState::new(
-                        next_time,
-                        neighbor.clone(),
-                        new_score,
-                        opened_list.clone(),
-                    );
+        self.search(self.start, time_budget, 0, 0, &mut best, &mut seen);
 
-                    state_queue.push_back(new_state);
-                }
+        best
+    }
+
+    fn search(
+        &self,
+        position: usize,
+        time_remaining: usize,
+        opened: u64,
+        pressure: usize,
+        best: &mut HashMap<u64, usize>,
+        seen: &mut HashMap<(usize, usize, u64), usize>,
+    ) {
+        // another path already reached this valve at this time with the same valves open, and
+        // released at least as much pressure doing it
+        match seen.get(&(position, time_remaining, opened)) {
+            Some(seen_pressure) if *seen_pressure >= pressure => return,
+            _ => {
+                seen.insert((position, time_remaining, opened), pressure);
             }
         }
-    }
 
-    println!("Looked at {} final states", number_of_final_states);
+        let best_pressure = best.entry(opened).or_insert(0);
+        *best_pressure = (*best_pressure).max(pressure);
 
-    final_states
-}
+        for (valve, flow_rate) in self.flow_rates.iter().enumerate() {
+            if opened & (1 << valve) != 0 {
+                continue;
+            }
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
-struct SearchState {
-    current_time: usize,
-    current_label: String,
-    current_score: isize,
-    opened: Vec<String>,
-}
+            // walk there and spend a minute opening it
+            let time_taken = self.distances[position][valve] + 1;
 
-impl SearchState {
-    fn new(
-        current_time: usize,
-        current_label: String,
-        current_score: isize,
-        opened: Vec<String>,
-    ) -> SearchState {
-        SearchState {
-            current_time,
-            current_label,
-            current_score,
-            opened,
+            if time_taken >= time_remaining {
+                continue;
+            }
+
+            let time_remaining = time_remaining - time_taken;
+
+            self.search(
+                valve,
+                time_remaining,
+                opened | (1 << valve),
+                pressure + flow_rate * time_remaining,
+                best,
+                seen,
+            );
         }
     }
 }
@@ -227,6 +235,120 @@ impl ValveSystem {
             flow_rates_by_label,
         }
     }
+
+    /**
+     * Drops every valve with no flow (except where we start), replacing the tunnels between the
+     * remaining valves with their shortest distances, found with Floyd-Warshall
+     */
+    fn compress(&self, start_label: &str) -> Result<CompressedValveSystem, AdventOfCodeError> {
+        let mut labels: Vec<&String> = self.graph.keys().collect();
+        labels.sort();
+
+        let indices: HashMap<&String, usize> = labels
+            .iter()
+            .enumerate()
+            .map(|(index, label)| (*label, index))
+            .collect();
+
+        // large enough to mean "no tunnel" without overflowing when two are added together
+        let unreachable = usize::MAX / 4;
+
+        let mut distances = vec![vec![unreachable; labels.len()]; labels.len()];
+
+        for (label, neighbors) in &self.graph {
+            let from = indices[label];
+
+            distances[from][from] = 0;
+
+            for (neighbor, distance) in neighbors {
+                let to = indices.get(neighbor).ok_or_else(|| {
+                    AdventOfCodeError::InvalidInput(format!(
+                        "valve {} has a tunnel to unknown valve {}",
+                        label, neighbor
+                    ))
+                })?;
+
+                distances[from][*to] = *distance;
+            }
+        }
+
+        for via in 0..labels.len() {
+            for from in 0..labels.len() {
+                for to in 0..labels.len() {
+                    let distance = distances[from][via] + distances[via][to];
+
+                    if distance < distances[from][to] {
+                        distances[from][to] = distance;
+                    }
+                }
+            }
+        }
+
+        // indices into `labels`, in mask bit order, followed by the start
+        let mut kept: Vec<usize> = (0..labels.len())
+            .filter(|index| self.flow_rates_by_label[labels[*index]] > 0)
+            .collect();
+
+        if kept.len() > MAX_VALVES {
+            return Err(AdventOfCodeError::Unsupported(format!(
+                "{} valves with flow, but at most {} can be searched",
+                kept.len(),
+                MAX_VALVES
+            )));
+        }
+
+        let start = kept.len();
+        kept.push(
+            labels
+                .iter()
+                .position(|label| *label == start_label)
+                .ok_or_else(|| {
+                    AdventOfCodeError::InvalidInput(format!("there's no valve {}", start_label))
+                })?,
+        );
+
+        Ok(CompressedValveSystem {
+            flow_rates: kept[..start]
+                .iter()
+                .map(|index| self.flow_rates_by_label[labels[*index]])
+                .collect(),
+            distances: kept
+                .iter()
+                .map(|from| kept.iter().map(|to| distances[*from][*to]).collect())
+                .collect(),
+            start,
+        })
+    }
+
+    fn to_graph(&self) -> Graph {
+        let mut graph = Graph::undirected("Valves");
+
+        let mut labels: Vec<&String> = self.graph.keys().collect();
+        labels.sort();
+
+        for label in &labels {
+            let flow_rate = self.flow_rates_by_label.get(*label).unwrap();
+            graph.add_node(label.as_str(), format!("{} ({})", label, flow_rate));
+        }
+
+        // tunnels are listed from both ends, so only emit each one once
+        for label in labels {
+            let mut neighbors: Vec<(&String, &usize)> = self.graph[label].iter().collect();
+            neighbors.sort();
+
+            for (neighbor, distance) in neighbors {
+                if label < neighbor {
+                    graph.add_edge(
+                        label.as_str(),
+                        neighbor.as_str(),
+                        Some(distance.to_string()),
+                    );
+                }
+            }
+        }
+
+        graph
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -296,6 +418,76 @@ mod tests {
 
     use super::*;
 
+    const EXAMPLE: &str = "Valve AA has flow rate=0; tunnels lead to valves DD, II, BB
+Valve BB has flow rate=13; tunnels lead to valves CC, AA
+Valve CC has flow rate=2; tunnels lead to valves DD, BB
+Valve DD has flow rate=20; tunnels lead to valves CC, AA, EE
+Valve EE has flow rate=3; tunnels lead to valves FF, DD
+Valve FF has flow rate=0; tunnels lead to valves EE, GG
+Valve GG has flow rate=0; tunnels lead to valves FF, HH
+Valve HH has flow rate=22; tunnel leads to valve GG
+Valve II has flow rate=0; tunnels lead to valves AA, JJ
+Valve JJ has flow rate=21; tunnel leads to valve II";
+
+    #[test]
+    fn test_part_one_example() {
+        let valves = parse(EXAMPLE).compress("AA").unwrap();
+
+        assert_eq!(
+            valves
+                .best_pressure_by_opened_valves(30)
+                .into_values()
+                .max(),
+            Some(1651)
+        );
+    }
+
+    #[test]
+    fn test_part_two_example() {
+        let valves = parse(EXAMPLE).compress("AA").unwrap();
+
+        let best = valves.best_pressure_by_opened_valves(26);
+
+        assert_eq!(
+            best_disjoint_pair_by_subsets(&best, valves.flow_rates.len()),
+            1707
+        );
+        assert_eq!(best_disjoint_pair_by_pairs(&best), 1707);
+    }
+
+    #[test]
+    fn test_compress() {
+        let valves = parse(
+            "Valve AA has flow rate=0; tunnels lead to valves BB, CC\nValve BB has flow rate=13; tunnel leads to valve AA\nValve CC has flow rate=0; tunnels lead to valves AA, DD\nValve DD has flow rate=2; tunnel leads to valve CC",
+        );
+
+        assert_eq!(
+            valves.compress("AA"),
+            Ok(CompressedValveSystem {
+                flow_rates: vec![13, 2],
+                distances: vec![vec![0, 3, 1], vec![3, 0, 2], vec![1, 2, 0]],
+                start: 2,
+            })
+        );
+
+        assert!(matches!(
+            valves.compress("ZZ"),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+
+        let valves = parse("Valve AA has flow rate=1; tunnel leads to valve BB");
+
+        assert!(matches!(
+            valves.compress("AA"),
+            Err(AdventOfCodeError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_best_pressure_by_subset() {
+        assert_eq!(best_pressure_by_subset(vec![0, 5, 3, 4]), vec![0, 5, 3, 5]);
+    }
+
     #[test]
     fn test_valve() {
         assert_eq!(
@@ -319,6 +511,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_to_graph() {
+        let valves = parse(
+            "Valve AA has flow rate=0; tunnels lead to valves BB, CC\nValve BB has flow rate=13; tunnel leads to valve AA\nValve CC has flow rate=2; tunnel leads to valve AA",
+        );
+
+        assert_eq!(
+            valves.to_graph().to_dot(),
+            [
+                "graph \"Valves\" {",
+                "\t\"AA\" [ label=\"AA (0)\" ]",
+                "\t\"BB\" [ label=\"BB (13)\" ]",
+                "\t\"CC\" [ label=\"CC (2)\" ]",
+                "\t\"AA\" -- \"BB\" [ label=\"1\" ]",
+                "\t\"AA\" -- \"CC\" [ label=\"1\" ]",
+                "}"
+            ]
+            .join("\n")
+        );
+    }
+
     #[test]
     fn test_connections() {
         assert_eq!(
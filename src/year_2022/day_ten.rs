@@ -1,5 +1,7 @@
-use std::time::SystemTime;
 use crate::common::answer::*;
+use crate::common::ocr;
+use std::fmt::{self, Display, Formatter};
+use std::time::SystemTime;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -25,13 +27,9 @@ pub fn run() -> AdventOfCodeResult {
 fn part_one(operations: &[Operation]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut cpu = Cpu::new();
+    let probes: Vec<usize> = (20..=220).step_by(40).collect();
 
-    for operation in operations {
-        cpu.apply(operation);
-    }
-
-    let answer: isize = cpu.signal_strength_values.iter().sum();
+    let answer = signal_strength(Cpu::new(operations, Crt::default()), &probes);
 
     let elapsed = start.elapsed().unwrap();
 
@@ -41,110 +39,167 @@ fn part_one(operations: &[Operation]) -> PartAnswer {
 fn part_two(operations: &[Operation]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut cpu = Cpu::new();
+    let crt = Crt::default();
+    let screen = Screen::draw(crt, Cpu::new(operations, crt));
 
-    for operation in operations {
-        cpu.apply(operation);
+    let elapsed = start.elapsed().unwrap();
+
+    match screen.decode() {
+        Some(text) => PartAnswer::new(text, elapsed),
+        None => PartAnswer::new(PixelatedString::new(format!("\n{}", screen)), elapsed),
     }
+}
 
-    let image = cpu
-        .pixels
-        .chunks_exact(40)
-        .map(|v| {
-            v.iter()
-                .map(|c| c.to_string())
-                .collect::<Vec<String>>()
-                .join("")
-        })
-        .collect::<Vec<String>>()
-        .join("\n");
-
-    let image = "\n".to_string() + &image + "\n";
-    let image = PixelatedString::new(image);
+/// The sum of the signal strengths during each of the probed cycles
+fn signal_strength<I: IntoIterator<Item = CycleEvent>>(events: I, probes: &[usize]) -> isize {
+    events
+        .into_iter()
+        .filter(|event| probes.contains(&event.cycle))
+        .map(|event| event.signal_strength())
+        .sum()
+}
 
-    let elapsed = start.elapsed().unwrap();
+/// The screen the CPU draws on, and how wide a sprite is
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct Crt {
+    width: usize,
+    height: usize,
+    sprite_width: usize,
+}
+
+impl Default for Crt {
+    fn default() -> Crt {
+        Crt {
+            width: 40,
+            height: 6,
+            sprite_width: 3,
+        }
+    }
+}
+
+impl Crt {
+    /// The pixel under the beam during a cycle, which wraps back to the top after the last row
+    fn position(&self, cycle: usize) -> (usize, usize) {
+        let index = (cycle - 1) % (self.width * self.height);
+
+        (index / self.width, index % self.width)
+    }
+
+    /// Whether the sprite, positioned by the X register at its middle, covers the column
+    fn is_lit(&self, column: usize, x: isize) -> bool {
+        let left = x - (self.sprite_width as isize - 1) / 2;
+
+        (left..left + self.sprite_width as isize).contains(&(column as isize))
+    }
+}
 
-    PartAnswer::new(image, elapsed)
+/// What happened during a single cycle
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct CycleEvent {
+    cycle: usize,
+    /// The X register during the cycle, before any `addx` finishing in this cycle takes effect
+    x: isize,
+    /// The (row, column) drawn this cycle
+    pixel: (usize, usize),
+    lit: bool,
+}
+
+impl CycleEvent {
+    fn signal_strength(&self) -> isize {
+        self.cycle as isize * self.x
+    }
 }
 
+/// Runs a program a cycle at a time, yielding an event for every cycle until the program ends
 #[derive(Debug)]
-struct Cpu {
-    current_cycle: usize,
-    current_value: isize,
-    signal_strength_values: Vec<isize>,
-    pixels: Vec<char>,
-    crt_position: usize,
-}
-
-impl Cpu {
-    fn new() -> Cpu {
-        let current_cycle = 1;
-        let current_value = 1;
-        let signal_strength_values = vec![];
-        let pixels = vec![];
-        let crt_position = 0;
+struct Cpu<'a> {
+    operations: std::slice::Iter<'a, Operation>,
+    crt: Crt,
+    cycle: usize,
+    x: isize,
+    current: Option<(Operation, usize)>,
+}
 
+impl<'a> Cpu<'a> {
+    fn new(operations: &'a [Operation], crt: Crt) -> Cpu<'a> {
         Cpu {
-            current_cycle,
-            current_value,
-            signal_strength_values,
-            pixels,
-            crt_position,
+            operations: operations.iter(),
+            crt,
+            cycle: 1,
+            x: 1,
+            current: None,
         }
     }
+}
 
-    fn apply(&mut self, operation: &Operation) {
-        self.advance_cycle(operation);
+impl Iterator for Cpu<'_> {
+    type Item = CycleEvent;
 
-        match operation {
-            Operation::Noop => {}
-            Operation::Add(value) => {
-                self.current_value += value;
+    fn next(&mut self) -> Option<CycleEvent> {
+        let (operation, cycles_left) = match self.current.take() {
+            Some(current) => current,
+            None => {
+                let operation = *self.operations.next()?;
+                (operation, operation.cycles())
             }
+        };
+
+        let pixel = self.crt.position(self.cycle);
+
+        let event = CycleEvent {
+            cycle: self.cycle,
+            x: self.x,
+            pixel,
+            lit: self.crt.is_lit(pixel.1, self.x),
+        };
+
+        if cycles_left > 1 {
+            self.current = Some((operation, cycles_left - 1));
+        } else if let Operation::Add(value) = operation {
+            self.x += value;
         }
+
+        self.cycle += 1;
+
+        Some(event)
     }
+}
 
-    fn advance_cycle(&mut self, operation: &Operation) {
-        let number_of_cycles = match operation {
-            Operation::Noop => 1,
-            Operation::Add(_) => 2,
-        };
+/// The pixels lit by the last time the beam passed over them
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Screen {
+    rows: Vec<Vec<bool>>,
+}
 
-        for _ in 0..number_of_cycles {
-            if self.current_cycle == 20
-                || self.current_cycle == 60
-                || self.current_cycle == 100
-                || self.current_cycle == 140
-                || self.current_cycle == 180
-                || self.current_cycle == 220
-            {
-                // println!("cycle {}, value {}", self.current_cycle, self.current_value);
-
-                self.signal_strength_values
-                    .push(self.current_cycle as isize * self.current_value);
-            }
+impl Screen {
+    fn draw<I: IntoIterator<Item = CycleEvent>>(crt: Crt, events: I) -> Screen {
+        let mut rows = vec![vec![false; crt.width]; crt.height];
 
-            let pixel_value = if ((self.crt_position as isize % 40) - self.current_value).abs() <= 1
-            {
-                // one of the three pixel sprites is under the CRT beam
-                solid_square()
-            } else {
-                empty_square()
-            };
+        for event in events {
+            let (row, column) = event.pixel;
+            rows[row][column] = event.lit;
+        }
 
-            // if self.current_cycle <= 21 {
-            //     println!(
-            //         "[current_cycle={:02}|crt_position={:02}|current_value={:02}] {pixel_value}",
-            //         self.current_cycle, self.crt_position, self.current_value
-            //     );
-            // }
+        Screen { rows }
+    }
 
-            self.pixels.push(pixel_value);
+    fn decode(&self) -> Option<String> {
+        ocr::decode(&self.rows)
+    }
+}
 
-            self.crt_position += 1;
+impl Display for Screen {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for row in &self.rows {
+            let row: String = row
+                .iter()
+                .map(|lit| if *lit { solid_square() } else { empty_square() })
+                .collect();
 
-            self.current_cycle += 1;
+            writeln!(f, "{}", row)?;
         }
+
+        Ok(())
     }
 }
 
@@ -154,6 +209,15 @@ enum Operation {
     Noop,
 }
 
+impl Operation {
+    fn cycles(&self) -> usize {
+        match self {
+            Operation::Noop => 1,
+            Operation::Add(_) => 2,
+        }
+    }
+}
+
 fn parse(i: &str) -> Vec<Operation> {
     finish(operations)(i).unwrap().1
 }
@@ -173,3 +237,48 @@ fn add(i: &str) -> IResult<&str, Operation> {
 fn noop(i: &str) -> IResult<&str, Operation> {
     value(Operation::Noop, tag("noop"))(i)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events() {
+        let operations = parse("noop\naddx 3\naddx -5");
+
+        let events: Vec<(usize, isize)> = Cpu::new(&operations, Crt::default())
+            .map(|event| (event.cycle, event.x))
+            .collect();
+
+        assert_eq!(events, vec![(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+    }
+
+    #[test]
+    fn test_crt() {
+        let crt = Crt {
+            width: 5,
+            height: 2,
+            sprite_width: 1,
+        };
+
+        assert_eq!(crt.position(1), (0, 0));
+        assert_eq!(crt.position(7), (1, 1));
+        assert_eq!(crt.position(11), (0, 0));
+        assert!(crt.is_lit(2, 2));
+        assert!(!crt.is_lit(3, 2));
+
+        let crt = Crt::default();
+
+        assert!(crt.is_lit(0, 1));
+        assert!(crt.is_lit(2, 1));
+        assert!(!crt.is_lit(3, 1));
+    }
+
+    #[test]
+    fn test_answers() {
+        let (part_one, part_two) = run().unwrap();
+
+        assert_eq!(*part_one.get_answer(), "14060".to_string());
+        assert_eq!(*part_two.get_answer(), "PAPKFKEJ".to_string());
+    }
+}
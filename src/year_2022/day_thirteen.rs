@@ -1,10 +1,13 @@
 use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::str::FromStr;
 use std::time::SystemTime;
-use crate::common::answer::*;
+use crate::common::{answer::*, json::Json};
 use nom::{
     branch::alt,
     bytes::complete::tag,
-    combinator::map,
+    combinator::{all_consuming, map},
     multi::{separated_list0, separated_list1},
     sequence::{delimited, separated_pair},
     IResult,
@@ -28,7 +31,7 @@ fn part_one(packet_pairs: &[(PacketValue, PacketValue)]) -> PartAnswer {
     let mut sum = 0;
 
     for (index, (left, right)) in packet_pairs.iter().enumerate() {
-        if are_packets_ordered_correctly(left, right) == Comparison::CorrectOrder {
+        if left < right {
             sum += index + 1;
         }
     }
@@ -41,95 +44,134 @@ fn part_one(packet_pairs: &[(PacketValue, PacketValue)]) -> PartAnswer {
 fn part_two(packet_pairs: &[(PacketValue, PacketValue)]) -> PartAnswer {
     let start = SystemTime::now();
 
-    let mut all_packets = vec![];
+    let product = decoder_key(packet_pairs);
 
-    let marker_two = PacketValue::List(vec![PacketValue::List(vec![PacketValue::Integer(2)])]);
-    let marker_six = PacketValue::List(vec![PacketValue::List(vec![PacketValue::Integer(6)])]);
+    let elapsed = start.elapsed().unwrap();
 
-    all_packets.push(marker_two.clone());
-    all_packets.push(marker_six.clone());
+    PartAnswer::new(product, elapsed)
+}
 
-    for (left, right) in packet_pairs {
-        all_packets.push(left.clone());
-        all_packets.push(right.clone());
-    }
+/**
+ * The divider packets' positions in the sorted packets only depend on how many packets sort before
+ * them, so there's no need to sort everything. `[[2]]` is also before `[[6]]`, which pushes the
+ * second divider back one more place
+ */
+fn decoder_key(packet_pairs: &[(PacketValue, PacketValue)]) -> usize {
+    let divider_two: PacketValue = "[[2]]".parse().unwrap();
+    let divider_six: PacketValue = "[[6]]".parse().unwrap();
 
-    all_packets.sort_by(|left, right| {
-        let comparison = are_packets_ordered_correctly(left, right);
-        match comparison {
-            Comparison::Inconclusive => unreachable!(),
-            Comparison::CorrectOrder => Ordering::Less,
-            Comparison::IncorrectOrder => Ordering::Greater,
-        }
-    });
+    let packets = packet_pairs.iter().flat_map(|(left, right)| [left, right]);
 
-    let mut product = 1;
+    let mut before_two = 0;
+    let mut before_six = 0;
 
-    for (index, packet) in all_packets.iter().enumerate() {
-        if *packet == marker_two || *packet == marker_six {
-            product *= index + 1;
+    for packet in packets {
+        if *packet < divider_two {
+            before_two += 1;
         }
-    }
 
-    let elapsed = start.elapsed().unwrap();
+        if *packet < divider_six {
+            before_six += 1;
+        }
+    }
 
-    PartAnswer::new(product, elapsed)
+    (before_two + 1) * (before_six + 2)
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/**
+ * Packets are ordered as the puzzle describes: integers numerically, lists lexicographically, and
+ * an integer compared with a list as if it were a list containing just that integer
+ *
+ * Equality follows the same rules so that it agrees with the ordering, which means `[1]` and
+ * `[[1]]` are equal
+ */
+#[derive(Debug, Clone)]
 enum PacketValue {
     Integer(usize),
     List(Vec<PacketValue>),
 }
 
-fn are_packets_ordered_correctly(left: &PacketValue, right: &PacketValue) -> Comparison {
-    match (left, right) {
-        (PacketValue::Integer(left_value), PacketValue::Integer(right_value)) => {
-            if left_value < right_value {
-                Comparison::CorrectOrder
-            } else if left_value > right_value {
-                Comparison::IncorrectOrder
-            } else {
-                Comparison::Inconclusive
+impl Ord for PacketValue {
+    fn cmp(&self, other: &PacketValue) -> Ordering {
+        match (self, other) {
+            (PacketValue::Integer(left), PacketValue::Integer(right)) => left.cmp(right),
+            (PacketValue::List(left), PacketValue::List(right)) => left.cmp(right),
+            (PacketValue::List(left), PacketValue::Integer(_)) => {
+                left.as_slice().cmp(std::slice::from_ref(other))
+            }
+            (PacketValue::Integer(_), PacketValue::List(right)) => {
+                std::slice::from_ref(self).cmp(right.as_slice())
             }
         }
-        (PacketValue::List(left_value), PacketValue::List(right_value)) => {
-            let mut i = 0;
-            let mut j = 0;
+    }
+}
 
-            while i < left_value.len() && j < right_value.len() {
-                let comparison = are_packets_ordered_correctly(&left_value[i], &right_value[j]);
+impl PartialOrd for PacketValue {
+    fn partial_cmp(&self, other: &PacketValue) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-                if comparison != Comparison::Inconclusive {
-                    return comparison;
-                }
+impl PartialEq for PacketValue {
+    fn eq(&self, other: &PacketValue) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
 
-                i += 1;
-                j += 1;
-            }
+impl Eq for PacketValue {}
 
-            if i == left_value.len() && j == right_value.len() {
-                return Comparison::Inconclusive;
-            } else if i == left_value.len() {
-                return Comparison::CorrectOrder;
-            } else {
-                return Comparison::IncorrectOrder;
-            }
-        }
-        (PacketValue::List(_), PacketValue::Integer(_)) => {
-            are_packets_ordered_correctly(left, &PacketValue::List(vec![right.clone()]))
+impl FromStr for PacketValue {
+    type Err = AdventOfCodeError;
+
+    fn from_str(s: &str) -> Result<PacketValue, AdventOfCodeError> {
+        all_consuming(list)(s)
+            .map(|(_, packet)| packet)
+            .map_err(|_| AdventOfCodeError::NomParseError)
+    }
+}
+
+impl Display for PacketValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", Json::from(self))
+    }
+}
+
+impl From<&PacketValue> for Json {
+    fn from(packet: &PacketValue) -> Json {
+        match packet {
+            PacketValue::Integer(integer) => Json::Number(*integer as i64),
+            PacketValue::List(list) => Json::Array(list.iter().map(Json::from).collect()),
         }
-        (PacketValue::Integer(_), PacketValue::List(_)) => {
-            are_packets_ordered_correctly(&PacketValue::List(vec![left.clone()]), right)
+    }
+}
+
+/// Only arrays of non-negative integers and further such arrays are packets
+impl TryFrom<&Json> for PacketValue {
+    type Error = InvalidPacketValue;
+
+    fn try_from(json: &Json) -> Result<PacketValue, InvalidPacketValue> {
+        match json {
+            Json::Number(number) => usize::try_from(*number)
+                .map(PacketValue::Integer)
+                .map_err(|_| InvalidPacketValue(json.clone())),
+            Json::Array(elements) => elements
+                .iter()
+                .map(PacketValue::try_from)
+                .collect::<Result<_, _>>()
+                .map(PacketValue::List),
+            _ => Err(InvalidPacketValue(json.clone())),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
-enum Comparison {
-    CorrectOrder,
-    IncorrectOrder,
-    Inconclusive,
+/// The part of a JSON value that can't be represented in a packet
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct InvalidPacketValue(Json);
+
+impl Display for InvalidPacketValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} isn't a packet value", self.0)
+    }
 }
 
 fn parse(i: &str) -> Vec<(PacketValue, PacketValue)> {
@@ -168,107 +210,68 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_are_packets_ordered_correctly_integers() {
+    fn test_cmp_integers() {
         let left = PacketValue::Integer(1);
         let right = PacketValue::Integer(1);
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::Inconclusive
-        );
+        assert_eq!(left.cmp(&right), Ordering::Equal);
 
         let left = PacketValue::Integer(3);
         let right = PacketValue::Integer(5);
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
 
         let left = PacketValue::Integer(9);
         let right = PacketValue::Integer(8);
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::IncorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Greater);
     }
 
     #[test]
-    fn test_are_packets_ordered_correctly_lists() {
+    fn test_cmp_lists() {
         let left = list("[1,1,3,1,1]").unwrap().1;
         let right = list("[1,1,5,1,1]").unwrap().1;
 
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
     }
 
     #[test]
-    fn test_are_packets_ordered_correctly_examples() {
+    fn test_cmp_examples() {
         let left = list("[9]").unwrap().1;
         let right = list("[[8,7,6]]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::IncorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Greater);
 
         let left = list("[[4,4],4,4]").unwrap().1;
         let right = list("[[4,4],4,4,4]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
 
         let left = list("[7,7,7,7]").unwrap().1;
         let right = list("[7,7,7]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::IncorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Greater);
 
         let left = list("[]").unwrap().1;
         let right = list("[3]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
 
         let left = list("[[[]]]").unwrap().1;
         let right = list("[[]]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::IncorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Greater);
 
         let left = list("[1,[2,[3,[4,[5,6,7]]]],8,9]").unwrap().1;
         let right = list("[1,[2,[3,[4,[5,6,0]]]],8,9]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::IncorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Greater);
     }
 
     #[test]
     fn test_scratch() {
         let left = list("[]").unwrap().1;
         let right = list("[[]]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
 
         let left = list("[1,1,5,1,1]").unwrap().1;
         let right = list("[[1],[2,3,4]]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
 
         let left = list("[1,[2,[3,[4,[5,6,7]]]],8,9]").unwrap().1;
         let right = list("[[1],4]").unwrap().1;
-        assert_eq!(
-            are_packets_ordered_correctly(&left, &right),
-            Comparison::CorrectOrder
-        );
+        assert_eq!(left.cmp(&right), Ordering::Less);
     }
 
     #[test]
@@ -340,4 +343,77 @@ mod tests {
             Ok(("", PacketValue::List(vec![PacketValue::List(vec![])])))
         );
     }
+
+    const EXAMPLE: &str = "[1,1,3,1,1]
+[1,1,5,1,1]
+
+[[1],[2,3,4]]
+[[1],4]
+
+[9]
+[[8,7,6]]
+
+[[4,4],4,4]
+[[4,4],4,4,4]
+
+[7,7,7,7]
+[7,7,7]
+
+[]
+[3]
+
+[[[]]]
+[[]]
+
+[1,[2,[3,[4,[5,6,7]]]],8,9]
+[1,[2,[3,[4,[5,6,0]]]],8,9]";
+
+    #[test]
+    fn test_mixed_equality() {
+        assert_eq!(list("[1]").unwrap().1, list("[[1]]").unwrap().1);
+        assert_ne!(list("[1]").unwrap().1, list("[[1,2]]").unwrap().1);
+    }
+
+    #[test]
+    fn test_decoder_key() {
+        let packet_pairs = parse(EXAMPLE);
+
+        assert_eq!(decoder_key(&packet_pairs), 140);
+
+        let mut all_packets: Vec<_> = packet_pairs
+            .into_iter()
+            .flat_map(|(left, right)| [left, right])
+            .collect();
+        all_packets.sort();
+
+        assert_eq!(all_packets[0].to_string(), "[]");
+        assert_eq!(all_packets[15].to_string(), "[9]");
+    }
+
+    #[test]
+    fn test_from_str_and_display() {
+        let packet: PacketValue = "[1,[2,[3,[4,[5,6,7]]]],8,9]".parse().unwrap();
+
+        assert_eq!(packet.to_string(), "[1,[2,[3,[4,[5,6,7]]]],8,9]");
+        assert!("[1,2".parse::<PacketValue>().is_err());
+        assert!("7".parse::<PacketValue>().is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let json: Json = "[[1],[2, 3, 4], []]".parse().unwrap();
+        let packet = PacketValue::try_from(&json).unwrap();
+
+        assert_eq!(packet.to_string(), "[[1],[2,3,4],[]]");
+        assert_eq!(Json::from(&packet), json);
+
+        let json: Json = r#"[1, [-2]]"#.parse().unwrap();
+        assert_eq!(
+            PacketValue::try_from(&json),
+            Err(InvalidPacketValue(Json::Number(-2)))
+        );
+
+        let json: Json = r#"[1, {"a": 2}]"#.parse().unwrap();
+        assert!(PacketValue::try_from(&json).is_err());
+    }
 }
@@ -1,291 +1,262 @@
 use crate::common::answer::*;
+use crate::common::expr::{BinaryOperator, Environment, EvaluationError, Expr, OperatorTable};
+use crate::common::math::gcd;
 use log::debug;
 use nom::{
-    branch::alt,
-    bytes::complete::tag,
-    character::complete::alpha1,
-    combinator::{map, value},
-    multi::separated_list1,
-    sequence::tuple,
-    IResult,
+    bytes::complete::tag, character::complete::alpha1, combinator::map, multi::separated_list1,
+    sequence::tuple, IResult,
 };
-use std::{collections::HashMap, fmt::Debug};
+use std::fmt::Display;
 use std::time::SystemTime;
-use crate::common::parse::{finish, unsigned_number};
+use crate::common::parse::finish;
 
 pub fn run() -> AdventOfCodeResult {
     let input = include_str!("input/day-21.txt");
 
-    let part_one = part_one(input);
-    let part_two = part_two(input);
+    let part_one = part_one(input)?;
+    let part_two = part_two(input)?;
 
     Ok((part_one, part_two))
 }
 
-fn part_one(input: &str) -> PartAnswer {
+fn part_one(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut equations = parse(input);
+    let equations = parse(input);
 
-    equations.evaluate();
-
-    let root_value = equations.root_value().unwrap_or(0);
+    let root_value = equations.root_value()?;
 
     let elapsed = start.elapsed().unwrap();
 
-    PartAnswer::new(root_value, elapsed)
+    Ok(PartAnswer::new(root_value, elapsed))
 }
 
-fn part_two(input: &str) -> PartAnswer {
+/**
+ * `humn` appears exactly once in the tree under `root`, so we can evaluate the side of `root` that
+ * doesn't contain it and then undo each operation on the way down from `root` to `humn`
+ */
+fn part_two(input: &str) -> Result<PartAnswer, AdventOfCodeError> {
     let start = SystemTime::now();
 
-    let mut lower: u128 = 1;
-
-    let mut checked = vec![];
+    let equations = parse(input);
 
-    let value_to_match = evaluate_and_return_values_at_root(input, 1).1;
+    let answer = equations.solve_for("humn").ok_or_else(|| {
+        AdventOfCodeError::CannotEvaluate("no whole number for humn balances root".to_string())
+    })?;
 
-    loop {
-        checked.push(lower);
+    let elapsed = start.elapsed().unwrap();
 
-        let (left, right) = evaluate_and_return_values_at_root(input, lower);
-        let (left_added, _) = evaluate_and_return_values_at_root(input, lower + 1000);
+    Ok(PartAnswer::new(answer, elapsed))
+}
 
-        debug!("{lower} -> {left} ... {left_added}");
+/**
+ * Each monkey's job is bound to its name, so evaluating a monkey evaluates everything it's waiting on
+ */
+struct Equations {
+    environment: Environment,
+}
 
-        if left > right && left_added < left {
-            // we're too big and we're decreasing as we increment
-            lower *= 2;
-        } else if left < right && left_added > left {
-            // we're too small and we're increasing as we increment
-            lower *= 2;
-        } else {
-            break;
+impl Equations {
+    fn new(equations: Vec<(String, Expr)>) -> Equations {
+        Equations {
+            environment: equations.into_iter().collect(),
         }
     }
 
-    let mut upper = checked[checked.len() - 1];
-    let mut lower = checked[checked.len() - 2];
-
-    debug!("searching between {lower} and {upper} for {value_to_match}");
-
-    let mut iterations = 0;
+    fn root_value(&self) -> Result<i128, EvaluationError> {
+        self.environment.evaluate_variable("root")
+    }
 
-    while upper.abs_diff(lower) > 100 {
-        if iterations > 100 {
-            panic!();
+    /// Builds the full tree for `name`, substituting every variable except `unknown`
+    fn expand(&self, name: &str, unknown: &str) -> Option<Expr> {
+        if name == unknown {
+            return Some(Expr::variable(name));
         }
 
-        let lower_value = evaluate_and_return_values_at_root(input, lower).0;
-        let upper_value = evaluate_and_return_values_at_root(input, upper).0;
-
-        debug!("{lower} - {lower_value} ... {upper} - {upper_value}");
+        self.expand_expression(self.environment.get(name)?, unknown)
+    }
 
-        let midpoint = (lower + upper) / 2;
+    fn expand_expression(&self, expression: &Expr, unknown: &str) -> Option<Expr> {
+        match expression {
+            Expr::Number(number) => Some(Expr::Number(*number)),
+            Expr::Variable(name) => self.expand(name, unknown),
+            Expr::Binary { lhs, operator, rhs } => Some(Expr::binary(
+                self.expand_expression(lhs, unknown)?,
+                *operator,
+                self.expand_expression(rhs, unknown)?,
+            )),
+        }
+    }
 
-        if lower_value.abs_diff(value_to_match) > upper_value.abs_diff(value_to_match) {
-            lower = midpoint - 1;
+    /**
+     * Finds the value of `unknown` that makes both sides of `root` equal
+     *
+     * Starting from the value of the side without the unknown, each operation between `root` and
+     * the unknown is inverted in turn. The monkeys' divisions aren't necessarily exact along the
+     * way, so the intermediate values are kept as fractions
+     */
+    fn solve_for(&self, unknown: &str) -> Option<i128> {
+        let (lhs, rhs) = match self.expand("root", unknown)? {
+            Expr::Binary { lhs, rhs, .. } => (*lhs, *rhs),
+            _ => return None,
+        };
+
+        let (mut current, known) = if contains_variable(&lhs, unknown) {
+            (lhs, rhs)
         } else {
-            upper = midpoint + 1;
+            (rhs, lhs)
+        };
+
+        let mut target = evaluate_rational(&known)?;
+
+        loop {
+            match current {
+                Expr::Variable(name) if name == unknown => break,
+                Expr::Binary { lhs, operator, rhs } => {
+                    if contains_variable(&lhs, unknown) {
+                        let known = evaluate_rational(&rhs)?;
+
+                        // target = unknown <operator> known
+                        target = match operator {
+                            BinaryOperator::Add => target.checked_sub(known)?,
+                            BinaryOperator::Subtract => target.checked_add(known)?,
+                            BinaryOperator::Multiply => target.checked_div(known)?,
+                            BinaryOperator::Divide => target.checked_mul(known)?,
+                        };
+
+                        current = *lhs;
+                    } else {
+                        let known = evaluate_rational(&lhs)?;
+
+                        // target = known <operator> unknown
+                        target = match operator {
+                            BinaryOperator::Add => target.checked_sub(known)?,
+                            BinaryOperator::Subtract => known.checked_sub(target)?,
+                            BinaryOperator::Multiply => target.checked_div(known)?,
+                            BinaryOperator::Divide => known.checked_div(target)?,
+                        };
+
+                        current = *rhs;
+                    }
+
+                    debug!("{unknown} must make {current} equal {target}");
+                }
+                _ => return None,
+            }
         }
 
-        iterations += 1;
+        target.to_integer()
     }
+}
 
-    debug!("{lower} ... {upper}");
-
-    for value in lower..=upper {
-        let (left, right) = evaluate_and_return_values_at_root(input, value);
-
-        debug!("{value} gives {left} = {right}");
-
-        if left == right {
-            let elapsed = start.elapsed().unwrap();
-
-            // 3769668716710 is too high
-            return PartAnswer::new(value, elapsed);
+fn contains_variable(expression: &Expr, name: &str) -> bool {
+    match expression {
+        Expr::Number(_) => false,
+        Expr::Variable(variable) => variable == name,
+        Expr::Binary { lhs, rhs, .. } => {
+            contains_variable(lhs, name) || contains_variable(rhs, name)
         }
     }
-
-    PartAnswer::default()
 }
 
-fn evaluate_and_return_values_at_root(input: &str, humn_value: u128) -> (u128, u128) {
-    let mut equations = parse(input);
-
-    let root_equation = equations.root_equation();
-
-    let lhs_equation = match &root_equation.expression {
-        Expression::Constant { value: _ } => unreachable!(),
-        Expression::Expression {
-            lhs,
-            operator: _,
-            rhs: _,
-        } => lhs,
-    };
-
-    let rhs_equation = match &root_equation.expression {
-        Expression::Constant { value: _ } => unreachable!(),
-        Expression::Expression {
-            lhs: _,
-            operator: _,
-            rhs,
-        } => rhs,
-    };
-
-    equations.results.insert("humn".to_string(), humn_value);
-    equations.evaluate();
-
-    let lhs_value = equations.results.get(lhs_equation).cloned().unwrap_or(0);
-    let rhs_value = equations.results.get(rhs_equation).cloned().unwrap_or(0);
-
-    (lhs_value, rhs_value)
+fn evaluate_rational(expression: &Expr) -> Option<Rational> {
+    match expression {
+        Expr::Number(number) => Some(Rational::from(*number)),
+        Expr::Variable(_) => None,
+        Expr::Binary { lhs, operator, rhs } => {
+            let lhs = evaluate_rational(lhs)?;
+            let rhs = evaluate_rational(rhs)?;
+
+            match operator {
+                BinaryOperator::Add => lhs.checked_add(rhs),
+                BinaryOperator::Subtract => lhs.checked_sub(rhs),
+                BinaryOperator::Multiply => lhs.checked_mul(rhs),
+                BinaryOperator::Divide => lhs.checked_div(rhs),
+            }
+        }
+    }
 }
 
-struct Equations {
-    equations: HashMap<String, Equation>,
-    results: HashMap<String, u128>,
+/// An exact fraction, always stored in lowest terms with a positive denominator
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+struct Rational {
+    numerator: i128,
+    denominator: i128,
 }
 
-impl Equations {
-    fn new(equations: Vec<Equation>) -> Equations {
-        let mut results = HashMap::new();
-
-        for equation in equations.iter() {
-            match equation.expression {
-                Expression::Constant { value } => {
-                    results.insert(equation.result.clone(), value);
-                }
-                _ => {}
-            }
+/// Every operation gives `None` rather than overflowing or dividing by zero
+impl Rational {
+    fn checked_new(numerator: i128, denominator: i128) -> Option<Rational> {
+        if denominator == 0 {
+            return None;
         }
 
-        let equations = equations
-            .into_iter()
-            .map(|equation| (equation.result.clone(), equation))
-            .collect();
+        let divisor = gcd(numerator, denominator)
+            .checked_abs()?
+            .checked_mul(denominator.signum())?;
 
-        Equations { equations, results }
+        Some(Rational {
+            numerator: numerator.checked_div(divisor)?,
+            denominator: denominator.checked_div(divisor)?,
+        })
     }
 
-    fn root_value(&self) -> Option<u128> {
-        self.results.get("root").copied()
+    fn checked_add(self, other: Rational) -> Option<Rational> {
+        Rational::checked_new(
+            self.numerator
+                .checked_mul(other.denominator)?
+                .checked_add(other.numerator.checked_mul(self.denominator)?)?,
+            self.denominator.checked_mul(other.denominator)?,
+        )
     }
 
-    fn root_equation(&self) -> Equation {
-        self.equations.get("root").cloned().unwrap()
+    fn checked_sub(self, other: Rational) -> Option<Rational> {
+        Rational::checked_new(
+            self.numerator
+                .checked_mul(other.denominator)?
+                .checked_sub(other.numerator.checked_mul(self.denominator)?)?,
+            self.denominator.checked_mul(other.denominator)?,
+        )
     }
 
-    fn evaluate(&mut self) {
-        while self.results.len() < self.equations.len() {
-            let before_length = self.results.len();
-
-            for equation in self.equations.values() {
-                if self.results.contains_key(&equation.result) {
-                    continue;
-                }
-
-                if !self.can_evaluate(equation) {
-                    continue;
-                }
-
-                if let Some(result) = self.evaluate_expression(equation) {
-                    self.results.insert(equation.result.clone(), result);
-                }
-            }
-
-            let after_length = self.results.len();
-
-            if before_length == after_length {
-                break;
-            }
-        }
+    fn checked_mul(self, other: Rational) -> Option<Rational> {
+        Rational::checked_new(
+            self.numerator.checked_mul(other.numerator)?,
+            self.denominator.checked_mul(other.denominator)?,
+        )
     }
 
-    fn evaluate_expression(&self, equation: &Equation) -> Option<u128> {
-        match &equation.expression {
-            Expression::Constant { value } => Some(*value),
-            Expression::Expression { lhs, operator, rhs } => {
-                let lhs = self.results.get(lhs)?;
-
-                let rhs = self.results.get(rhs)?;
-
-                operator.apply(*lhs, *rhs)
-            }
-        }
+    fn checked_div(self, other: Rational) -> Option<Rational> {
+        Rational::checked_new(
+            self.numerator.checked_mul(other.denominator)?,
+            self.denominator.checked_mul(other.numerator)?,
+        )
     }
 
-    fn can_evaluate(&self, equation: &Equation) -> bool {
-        match &equation.expression {
-            Expression::Constant { value: _ } => false,
-            Expression::Expression {
-                lhs,
-                operator: _,
-                rhs,
-            } => self.results.contains_key(lhs) && self.results.contains_key(rhs),
-        }
-    }
-}
-
-#[derive(PartialEq, Eq, Clone)]
-struct Equation {
-    result: String,
-    expression: Expression,
-}
-
-impl Debug for Equation {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {:?}", self.result, self.expression)
-    }
-}
-
-#[derive(PartialEq, Eq, Clone)]
-enum Expression {
-    Constant {
-        value: u128,
-    },
-    Expression {
-        lhs: String,
-        operator: Operator,
-        rhs: String,
-    },
-}
-
-impl Debug for Expression {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Constant { value } => write!(f, "{value}"),
-            Self::Expression { lhs, operator, rhs } => write!(f, "{lhs} {operator:?} {rhs}"),
+    fn to_integer(self) -> Option<i128> {
+        if self.denominator == 1 {
+            Some(self.numerator)
+        } else {
+            None
         }
     }
 }
 
-#[derive(PartialEq, Eq, Copy, Clone)]
-enum Operator {
-    Multiply,
-    Subtract,
-    Add,
-    Divide,
-}
-
-impl Operator {
-    fn apply(&self, lhs: u128, rhs: u128) -> Option<u128> {
-        match self {
-            Operator::Multiply => lhs.checked_mul(rhs),
-            Operator::Subtract => lhs.checked_sub(rhs),
-            Operator::Add => lhs.checked_add(rhs),
-            Operator::Divide => lhs.checked_div(rhs),
+impl From<i128> for Rational {
+    fn from(value: i128) -> Rational {
+        Rational {
+            numerator: value,
+            denominator: 1,
         }
     }
 }
 
-impl Debug for Operator {
+impl Display for Rational {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::Multiply => write!(f, "*"),
-            Self::Subtract => write!(f, "-"),
-            Self::Add => write!(f, "+"),
-            Self::Divide => write!(f, "."),
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
         }
     }
 }
@@ -298,48 +269,121 @@ fn equations(i: &str) -> IResult<&str, Equations> {
     map(separated_list1(tag("\n"), equation), Equations::new)(i)
 }
 
-fn equation(i: &str) -> IResult<&str, Equation> {
+fn equation(i: &str) -> IResult<&str, (String, Expr)> {
     map(
-        tuple((identifier, tag(": "), term)),
-        |(result, _, expression)| Equation { result, expression },
+        tuple((identifier, tag(": "), job)),
+        |(name, _, expression)| (name, expression),
     )(i)
 }
 
-fn term(i: &str) -> IResult<&str, Expression> {
-    alt((expression, constant))(i)
-}
-
-fn expression(i: &str) -> IResult<&str, Expression> {
-    map(
-        tuple((identifier, tag(" "), operator, tag(" "), identifier)),
-        |(lhs, _, operator, _, rhs)| Expression::Expression { lhs, operator, rhs },
-    )(i)
+fn job(i: &str) -> IResult<&str, Expr> {
+    OperatorTable::arithmetic().parse_expression(i)
 }
 
 fn identifier(i: &str) -> IResult<&str, String> {
     map(alpha1, |s: &str| s.to_string())(i)
 }
 
-fn operator(i: &str) -> IResult<&str, Operator> {
-    alt((add, subtract, multiply, divide))(i)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "root: pppw + sjmn
+dbpl: 5
+cczh: sllz + lgvd
+zczc: 2
+ptdq: humn - dvpt
+dvpt: 3
+lfqf: 4
+humn: 5
+ljgn: 2
+sjmn: drzm * dbpl
+sllz: 4
+pppw: cczh / lfqf
+lgvd: ljgn * ptdq
+drzm: hmdt - zczc
+hmdt: 32";
+
+    #[test]
+    fn test_equation() {
+        assert_eq!(
+            equation("cczh: sllz + lgvd"),
+            Ok((
+                "",
+                (
+                    "cczh".to_string(),
+                    Expr::binary(
+                        Expr::variable("sllz"),
+                        BinaryOperator::Add,
+                        Expr::variable("lgvd")
+                    )
+                )
+            ))
+        );
+        assert_eq!(
+            equation("dbpl: 5"),
+            Ok(("", ("dbpl".to_string(), Expr::Number(5))))
+        );
+    }
 
-fn add(i: &str) -> IResult<&str, Operator> {
-    value(Operator::Add, tag("+"))(i)
-}
+    #[test]
+    fn test_root_value() {
+        assert_eq!(parse(EXAMPLE).root_value(), Ok(152));
+        assert_eq!(
+            parse("root: a / b\na: 1\nb: 0").root_value(),
+            Err(EvaluationError::DivisionByZero)
+        );
+    }
 
-fn subtract(i: &str) -> IResult<&str, Operator> {
-    value(Operator::Subtract, tag("-"))(i)
-}
+    #[test]
+    fn test_solve_for() {
+        assert_eq!(parse(EXAMPLE).solve_for("humn"), Some(301));
+    }
 
-fn multiply(i: &str) -> IResult<&str, Operator> {
-    value(Operator::Multiply, tag("*"))(i)
-}
+    #[test]
+    fn test_solve_for_unknown_on_right() {
+        // root: 3 - (20 / humn) must equal 1, so humn is 10
+        let equations = parse(
+            "root: lhs + rhs
+lhs: 1
+rhs: three - ratio
+three: 3
+ratio: twenty / humn
+twenty: 20
+humn: 0",
+        );
+
+        assert_eq!(equations.solve_for("humn"), Some(10));
+    }
 
-fn divide(i: &str) -> IResult<&str, Operator> {
-    value(Operator::Divide, tag("/"))(i)
-}
+    #[test]
+    fn test_rational() {
+        let rational =
+            |numerator, denominator| Rational::checked_new(numerator, denominator).unwrap();
+        let half = rational(2, 4);
+
+        assert_eq!(half, rational(-1, -2));
+        assert_eq!(half.checked_add(rational(1, 3)), Some(rational(5, 6)));
+        assert_eq!(half.checked_sub(Rational::from(1)), Some(rational(-1, 2)));
+        assert_eq!(half.checked_mul(Rational::from(4)), Some(Rational::from(2)));
+        assert_eq!(half.checked_div(rational(1, 4)), Some(Rational::from(2)));
+        assert_eq!(half.checked_div(Rational::from(0)), None);
+        assert_eq!(Rational::checked_new(1, 0), None);
+
+        assert_eq!(half.to_integer(), None);
+        assert_eq!(rational(6, 3).to_integer(), Some(2));
+        assert_eq!(rational(3, -6).to_string(), "-1/2");
+    }
 
-fn constant(i: &str) -> IResult<&str, Expression> {
-    map(unsigned_number, |value| Expression::Constant { value })(i)
+    #[test]
+    fn test_rational_overflow() {
+        let huge = Rational::from(i128::MAX);
+
+        assert_eq!(huge.checked_add(Rational::from(1)), None);
+        assert_eq!(huge.checked_mul(Rational::from(2)), None);
+        assert_eq!(
+            huge.checked_sub(Rational::from(1)),
+            Some(Rational::from(i128::MAX - 1))
+        );
+    }
 }